@@ -7,7 +7,6 @@ use ig_client::{
     session::auth::IgAuth,
     session::interface::IgAuthenticator,
     transport::http_client::IgHttpClientImpl,
-    utils::finance::calculate_pnl,
     utils::logger::setup_logger,
 };
 
@@ -41,18 +40,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Get open positions
     info!("Fetching open positions...");
-    let mut positions = account_service.get_positions(&session).await?;
+    let positions = account_service.get_positions_with_pnl(&session).await?;
 
     if positions.positions.is_empty() {
         info!("No open positions currently");
     } else {
-        info!("Open positions: {}", positions.positions.len());
+        info!(
+            "Open positions: {}, total P&L: {:.2}",
+            positions.positions.len(),
+            positions.total_pnl()
+        );
 
         // Display positions
-        for (i, position) in positions.positions.iter_mut().enumerate() {
-            // Calculate P&L using the utility function
-            position.pnl = calculate_pnl(position);
-
+        for (i, position) in positions.positions.iter().enumerate() {
             // Log the position as pretty JSON
             info!(
                 "Position #{}: {}",
@@ -3,7 +3,7 @@ use ig_client::application::services::ig_tx_client::{IgTxClient, IgTxFetcher};
 use ig_client::config::Config;
 use ig_client::session::auth::IgAuth;
 use ig_client::session::interface::IgAuthenticator;
-use ig_client::storage::utils::store_transactions;
+use ig_client::storage::utils::{migrate_ig_options, store_transactions};
 use ig_client::utils::logger::setup_logger;
 use tracing::{debug, info};
 
@@ -16,6 +16,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // build the Postgres pool
     let pool = cfg.pg_pool().await?;
     info!("Postgres pool established");
+    migrate_ig_options(&pool).await?;
 
     let auth = IgAuth::new(&cfg);
     let sess = auth.login().await?;
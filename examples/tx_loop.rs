@@ -1,6 +1,6 @@
 use ig_client::config::Config;
 use ig_client::utils::logger::setup_logger;
-use ig_client::utils::transactions::fetch_and_store_transactions;
+use ig_client::utils::transactions::fetch_and_store_new_transactions;
 use std::time::Duration as StdDuration;
 use tokio::signal;
 use tokio::time;
@@ -46,7 +46,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 // If this is the first run, the interval will tick immediately
                 info!("Starting scheduled transaction fetch");
 
-                match fetch_and_store_transactions(&cfg, &pool, None).await {
+                match fetch_and_store_new_transactions(&cfg, &pool).await {
                     Ok(inserted) => {
                         info!("Successfully processed {} transactions", inserted);
                         consecutive_errors = 0; // Reset error counter on success
@@ -52,7 +52,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     for market in &markets {
         info!("Subscribing to market: {}", market);
-        let subscription_id = ws_client.subscribe_market(market).await?;
+        let subscription_id = ws_client.subscribe_market(*market).await?;
         info!("Subscribed with ID: {}", subscription_id);
     }
 
@@ -70,12 +70,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     while start_time.elapsed() < duration {
         tokio::select! {
             // Process market updates
-            Some(update) = market_rx.recv() => {
+            Ok(update) = market_rx.recv() => {
                 info!("Market update received: {:?}", update);
             }
 
             // Process account updates
-            Some(update) = account_rx.recv() => {
+            Ok(update) = account_rx.recv() => {
                 info!("Account update received: {:?}", update);
             }
 
@@ -87,8 +87,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Unsubscribe and disconnect
     for market in &markets {
         info!("Unsubscribing from market: {}", market);
-        // Note: In a real application, you would store the subscription IDs
-        // Here we're just demonstrating the concept
+        ws_client.unsubscribe_market(*market).await?;
     }
 
     info!("Unsubscribing from account updates");
@@ -0,0 +1,223 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tracing::info;
+
+use crate::{
+    application::models::account::{Positions, WorkingOrders},
+    application::models::market::{Epic, MarketDetails, MarketSearchResult},
+    application::models::order::{
+        ClosePositionRequest, ClosePositionResponse, CreateOrderRequest, CreateOrderResponse,
+        OrderConfirmation,
+    },
+    application::services::account_service::{AccountService, AccountServiceImpl},
+    application::services::instrument_catalog::InstrumentCatalog,
+    application::services::market_service::{MarketService, MarketServiceImpl},
+    application::services::order_service::{OrderService, OrderServiceImpl},
+    config::Config,
+    error::AppError,
+    session::auth::IgAuth,
+    session::interface::{IgAuthenticator, IgSession},
+    transport::http_client::{IgHttpClient, IgHttpClientImpl},
+    transport::websocket_client::IgWebSocketClientImpl,
+};
+
+/// Facade that owns the configuration, the authenticated session and the
+/// account, market and order services, injecting the current session into
+/// every call
+pub struct IgClient<T: IgHttpClient = IgHttpClientImpl> {
+    config: Arc<Config>,
+    session: RwLock<IgSession>,
+    accounts: AccountServiceImpl<T>,
+    markets: MarketServiceImpl<T>,
+    orders: OrderServiceImpl<T>,
+    catalog: Option<Arc<InstrumentCatalog>>,
+}
+
+impl IgClient<IgHttpClientImpl> {
+    /// Validates the configuration, logs in with the configured credentials
+    /// and builds the services
+    pub async fn connect(config: Arc<Config>) -> Result<Self, AppError> {
+        config.validate()?;
+        info!("Connecting to IG at {}", config.rest_api.base_url);
+        let session = IgAuth::new(&config).login().await?;
+        let client = Arc::new(IgHttpClientImpl::new(config.clone()));
+        Ok(Self::new(config, client, session))
+    }
+}
+
+impl<T: IgHttpClient + 'static> IgClient<T> {
+    /// Builds the facade from an existing HTTP client and session
+    pub fn new(config: Arc<Config>, client: Arc<T>, session: IgSession) -> Self {
+        Self {
+            accounts: AccountServiceImpl::new(config.clone(), client.clone()),
+            markets: MarketServiceImpl::new(config.clone(), client.clone()),
+            orders: OrderServiceImpl::new(config.clone(), client),
+            session: RwLock::new(session),
+            catalog: None,
+            config,
+        }
+    }
+
+    /// Instrument metadata handed to the streaming clients built by
+    /// [`streaming_client`](Self::streaming_client), which scale market
+    /// prices by its scaling factors
+    pub fn with_instrument_catalog(mut self, catalog: Arc<InstrumentCatalog>) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
+    pub fn instrument_catalog(&self) -> Option<Arc<InstrumentCatalog>> {
+        self.catalog.clone()
+    }
+
+    /// Builds a streaming client for this configuration, with the instrument
+    /// catalog if one was set. Connect it with [`session`](Self::session).
+    pub fn streaming_client(&self) -> IgWebSocketClientImpl {
+        let client = IgWebSocketClientImpl::new(self.config.clone());
+        match &self.catalog {
+            Some(catalog) => client.with_instrument_catalog(catalog.clone()),
+            None => client,
+        }
+    }
+
+    pub fn get_config(&self) -> Arc<Config> {
+        self.config.clone()
+    }
+
+    /// Returns a copy of the current session
+    pub fn session(&self) -> IgSession {
+        self.session.read().unwrap().clone()
+    }
+
+    /// Replaces the session, e.g. after a re-login
+    pub fn set_session(&self, session: IgSession) {
+        *self.session.write().unwrap() = session;
+    }
+
+    /// Account service, for calls without a passthrough
+    pub fn accounts(&self) -> &AccountServiceImpl<T> {
+        &self.accounts
+    }
+
+    /// Market service, for calls without a passthrough
+    pub fn markets(&self) -> &MarketServiceImpl<T> {
+        &self.markets
+    }
+
+    /// Order service, for calls without a passthrough
+    pub fn orders(&self) -> &OrderServiceImpl<T> {
+        &self.orders
+    }
+
+    /// Open positions
+    pub async fn positions(&self) -> Result<Positions, AppError> {
+        self.accounts.get_positions(&self.session()).await
+    }
+
+    /// Working orders
+    pub async fn working_orders(&self) -> Result<WorkingOrders, AppError> {
+        self.accounts.get_working_orders(&self.session()).await
+    }
+
+    /// Searches markets by term
+    pub async fn search(&self, search_term: &str) -> Result<MarketSearchResult, AppError> {
+        self.markets.search_markets(&self.session(), search_term).await
+    }
+
+    /// Details of a single market
+    pub async fn market_details(&self, epic: impl Into<Epic> + Send) -> Result<MarketDetails, AppError> {
+        self.markets.get_market_details(&self.session(), epic).await
+    }
+
+    /// Creates an order
+    pub async fn create_order(
+        &self,
+        order: &CreateOrderRequest,
+    ) -> Result<CreateOrderResponse, AppError> {
+        self.orders.create_order(&self.session(), order).await
+    }
+
+    /// Creates an order and waits up to `timeout` for its confirmation
+    pub async fn create_order_and_confirm(
+        &self,
+        order: &CreateOrderRequest,
+        timeout: Duration,
+    ) -> Result<OrderConfirmation, AppError> {
+        self.orders
+            .create_order_and_confirm(&self.session(), order, timeout)
+            .await
+    }
+
+    /// Closes a position
+    pub async fn close_position(
+        &self,
+        close_request: &ClosePositionRequest,
+    ) -> Result<ClosePositionResponse, AppError> {
+        self.orders.close_position(&self.session(), close_request).await
+    }
+}
+
+#[cfg(test)]
+mod tests_client {
+    use super::*;
+    use crate::utils::money::price;
+    use crate::application::models::order::Direction;
+    use crate::transport::mock::{test_session, MockHttpClient};
+    use reqwest::Method;
+    use serde_json::json;
+
+    fn client(mock: Arc<MockHttpClient>) -> IgClient<MockHttpClient> {
+        IgClient::new(Arc::new(Config::new()), mock, test_session())
+    }
+
+    #[tokio::test]
+    async fn test_passthroughs_use_services() {
+        let mock = Arc::new(MockHttpClient::new());
+        mock.push_json(json!({"positions": []}));
+        mock.push_json(json!({"markets": []}));
+        mock.push_json(json!({"dealReference": "REF1"}));
+        let ig = client(mock.clone());
+
+        assert!(ig.positions().await.unwrap().positions.is_empty());
+        assert!(ig.search("DAX").await.unwrap().markets.is_empty());
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(1.0));
+        assert_eq!(ig.create_order(&order).await.unwrap().deal_reference, "REF1");
+
+        let requests: Vec<_> = mock
+            .requests()
+            .into_iter()
+            .map(|r| (r.method, r.path))
+            .collect();
+        assert_eq!(
+            requests,
+            vec![
+                (Method::GET, "positions".to_string()),
+                (Method::GET, "markets?searchTerm=DAX".to_string()),
+                (Method::POST, "positions/otc".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_streaming_client_gets_catalog() {
+        let ig = client(Arc::new(MockHttpClient::new()));
+        assert!(ig.streaming_client().instrument_catalog().is_none());
+
+        let catalog = Arc::new(InstrumentCatalog::default());
+        let ig = ig.with_instrument_catalog(catalog.clone());
+        let streaming = ig.streaming_client();
+        assert!(Arc::ptr_eq(streaming.instrument_catalog().unwrap(), &catalog));
+    }
+
+    #[test]
+    fn test_set_session() {
+        let ig = client(Arc::new(MockHttpClient::new()));
+        assert_eq!(ig.session().account_id, "ACC1");
+
+        let mut session = test_session();
+        session.account_id = "ACC2".to_string();
+        ig.set_session(session);
+        assert_eq!(ig.session().account_id, "ACC2");
+    }
+}
@@ -1,3 +1,4 @@
 pub mod api;
+pub mod client;
 pub mod models;
-pub mod services;
+pub mod services;
\ No newline at end of file
@@ -3,18 +3,24 @@
    Email: jb@taunais.com
    Date: 13/5/25
 ******************************************************************************/
+use std::collections::{HashMap, HashSet};
+
 use serde::{Deserialize, Serialize};
 
+use super::market::MarketStatus;
 use super::order::Direction;
+use super::paging::{Paged, Paging};
+use crate::utils::finance::convert_pnl;
+use crate::utils::money::{price_to_f64, Price};
 
 /// Información de la cuenta
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountInfo {
     pub accounts: Vec<Account>,
 }
 
 /// Detalles de una cuenta específica
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Account {
     #[serde(rename = "accountId")]
     pub account_id: String,
@@ -26,10 +32,13 @@ pub struct Account {
     pub currency: String,
     pub status: String,
     pub preferred: bool,
+    /// Fields IG sent that are not modelled above
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 /// Balance de la cuenta
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountBalance {
     pub balance: f64,
     pub deposit: f64,
@@ -39,9 +48,43 @@ pub struct AccountBalance {
 }
 
 /// Actividad de la cuenta
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountActivity {
     pub activities: Vec<Activity>,
+    /// Paging information returned by the v3 activity endpoint
+    #[serde(default)]
+    pub metadata: Option<ActivityMetadata>,
+}
+
+/// Metadata attached to a page of account activity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityMetadata {
+    pub paging: Option<ActivityPaging>,
+}
+
+/// Cursor-based paging used by the v3 activity endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityPaging {
+    pub size: Option<i32>,
+    /// Relative path of the next page, `None` on the last page
+    pub next: Option<String>,
+}
+
+impl From<AccountActivity> for Paged<Activity> {
+    fn from(activity: AccountActivity) -> Self {
+        let paging = activity.metadata.and_then(|m| m.paging);
+        let size = paging
+            .as_ref()
+            .and_then(|p| p.size)
+            .unwrap_or(activity.activities.len() as i32);
+        Paged {
+            items: activity.activities,
+            paging: Paging {
+                next: paging.and_then(|p| p.next),
+                size,
+            },
+        }
+    }
 }
 
 /// Actividad individual
@@ -67,19 +110,54 @@ pub struct Positions {
     pub positions: Vec<Position>,
 }
 
+impl Positions {
+    /// Epics of the held positions, without duplicates, in first-seen order.
+    /// Pass them to `InstrumentCatalog::build` or `subscribe_market` to price
+    /// everything held.
+    pub fn epics(&self) -> Vec<String> {
+        unique_epics(self.positions.iter().map(|p| p.market.epic.as_str()))
+    }
+
+    /// Sum of the populated `pnl` values; positions without P&L count as zero
+    pub fn total_pnl(&self) -> Price {
+        self.positions.iter().filter_map(|p| p.pnl).sum()
+    }
+
+    /// Sum of the populated `pnl` values converted into `currency` with
+    /// [`convert_pnl`]; None if a position's currency has no rate
+    pub fn total_pnl_in(&self, currency: &str, rates: &HashMap<String, f64>) -> Option<f64> {
+        self.positions
+            .iter()
+            .filter_map(|p| p.pnl.map(|pnl| (pnl, &p.position.currency)))
+            .map(|(pnl, from)| convert_pnl(price_to_f64(pnl), from, currency, rates))
+            .sum()
+    }
+}
+
+/// Positions, working orders and accounts fetched together
+#[derive(Debug, Clone)]
+pub struct PortfolioSnapshot {
+    pub positions: Positions,
+    pub working_orders: WorkingOrders,
+    pub accounts: AccountInfo,
+}
+
 /// Posición individual
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub position: PositionDetails,
     pub market: PositionMarket,
-    pub pnl: Option<f64>,
+    pub pnl: Option<Price>,
+    /// Fields IG sent that are not modelled above
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 /// Details of a position
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PositionDetails {
     #[serde(rename = "contractSize")]
-    pub contract_size: f64,
+    pub contract_size: Price,
     #[serde(rename = "createdDate")]
     pub created_date: String,
     #[serde(rename = "createdDateUTC")]
@@ -90,20 +168,20 @@ pub struct PositionDetails {
     pub deal_reference: String,
     pub direction: Direction,
     #[serde(rename = "limitLevel")]
-    pub limit_level: Option<f64>,
-    pub level: f64,
-    pub size: f64,
+    pub limit_level: Option<Price>,
+    pub level: Price,
+    pub size: Price,
     #[serde(rename = "stopLevel")]
-    pub stop_level: Option<f64>,
+    pub stop_level: Option<Price>,
     #[serde(rename = "trailingStep")]
-    pub trailing_step: Option<f64>,
+    pub trailing_step: Option<Price>,
     #[serde(rename = "trailingStopDistance")]
-    pub trailing_stop_distance: Option<f64>,
+    pub trailing_stop_distance: Option<Price>,
     pub currency: String,
     #[serde(rename = "controlledRisk")]
     pub controlled_risk: bool,
     #[serde(rename = "limitedRiskPremium")]
-    pub limited_risk_premium: Option<f64>,
+    pub limited_risk_premium: Option<Price>,
 }
 
 /// Market information for a position
@@ -117,14 +195,14 @@ pub struct PositionMarket {
     pub instrument_type: String,
     #[serde(rename = "lotSize")]
     pub lot_size: f64,
-    pub high: f64,
-    pub low: f64,
+    pub high: Price,
+    pub low: Price,
     #[serde(rename = "percentageChange")]
     pub percentage_change: f64,
     #[serde(rename = "netChange")]
     pub net_change: f64,
-    pub bid: f64,
-    pub offer: f64,
+    pub bid: Price,
+    pub offer: Price,
     #[serde(rename = "updateTime")]
     pub update_time: String,
     #[serde(rename = "updateTimeUTC")]
@@ -134,7 +212,7 @@ pub struct PositionMarket {
     #[serde(rename = "streamingPricesAvailable")]
     pub streaming_prices_available: bool,
     #[serde(rename = "marketStatus")]
-    pub market_status: String,
+    pub market_status: MarketStatus,
     #[serde(rename = "scalingFactor")]
     pub scaling_factor: i64
 }
@@ -146,6 +224,21 @@ pub struct WorkingOrders {
     pub working_orders: Vec<WorkingOrder>,
 }
 
+impl WorkingOrders {
+    /// Epics of the working orders, without duplicates, in first-seen order
+    pub fn epics(&self) -> Vec<String> {
+        unique_epics(self.working_orders.iter().map(|o| o.working_order_data.epic.as_str()))
+    }
+}
+
+fn unique_epics<'a>(epics: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    epics
+        .filter(|epic| seen.insert(*epic))
+        .map(str::to_string)
+        .collect()
+}
+
 /// Working order
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct WorkingOrder {
@@ -163,9 +256,9 @@ pub struct WorkingOrderData {
     pub direction: Direction,
     pub epic: String,
     #[serde(rename = "orderSize")]
-    pub order_size: f64,
+    pub order_size: Price,
     #[serde(rename = "orderLevel")]
-    pub order_level: f64,
+    pub order_level: Price,
     #[serde(rename = "timeInForce")]
     pub time_in_force: String,
     #[serde(rename = "goodTillDate")]
@@ -181,19 +274,19 @@ pub struct WorkingOrderData {
     #[serde(rename = "orderType")]
     pub order_type: String,
     #[serde(rename = "stopDistance")]
-    pub stop_distance: Option<f64>,
+    pub stop_distance: Option<Price>,
     #[serde(rename = "limitDistance")]
-    pub limit_distance: Option<f64>,
+    pub limit_distance: Option<Price>,
     #[serde(rename = "currencyCode")]
     pub currency_code: String,
     pub dma: bool,
     #[serde(rename = "limitedRiskPremium")]
-    pub limited_risk_premium: Option<f64>,
+    pub limited_risk_premium: Option<Price>,
     // Optional fields that might be present in other responses
     #[serde(rename = "limitLevel", default)]
-    pub limit_level: Option<f64>,
+    pub limit_level: Option<Price>,
     #[serde(rename = "stopLevel", default)]
-    pub stop_level: Option<f64>,
+    pub stop_level: Option<Price>,
     #[serde(rename = "dealReference", default)]
     pub deal_reference: Option<String>,
 }
@@ -207,20 +300,20 @@ pub struct MarketData {
     pub exchange_id: String,
     pub expiry: String,
     #[serde(rename = "marketStatus")]
-    pub market_status: String,
+    pub market_status: MarketStatus,
     pub epic: String,
     #[serde(rename = "instrumentType")]
     pub instrument_type: String,
     #[serde(rename = "lotSize")]
     pub lot_size: f64,
-    pub high: f64,
-    pub low: f64,
+    pub high: Price,
+    pub low: Price,
     #[serde(rename = "percentageChange")]
     pub percentage_change: f64,
     #[serde(rename = "netChange")]
     pub net_change: f64,
-    pub bid: f64,
-    pub offer: f64,
+    pub bid: Price,
+    pub offer: Price,
     #[serde(rename = "updateTime")]
     pub update_time: String,
     #[serde(rename = "updateTimeUTC")]
@@ -282,3 +375,33 @@ pub struct Transaction {
     #[serde(rename = "cashTransaction")]
     pub cash_transaction: bool,
 }
+
+#[cfg(test)]
+mod tests_account {
+    use super::*;
+    use crate::transport::mock::{position_json, working_order_json};
+    use serde_json::json;
+
+    #[test]
+    fn test_epics_are_deduplicated_in_order() {
+        let positions: Positions = serde_json::from_value(json!({
+            "positions": [
+                position_json("IX.D.DAX.IFMM.IP", "BUY", 1.0),
+                position_json("IX.D.FTSE.IFMM.IP", "BUY", 1.0),
+                position_json("IX.D.DAX.IFMM.IP", "SELL", 1.0)
+            ]
+        }))
+        .unwrap();
+        assert_eq!(positions.epics(), vec!["IX.D.DAX.IFMM.IP", "IX.D.FTSE.IFMM.IP"]);
+
+        let orders: WorkingOrders = serde_json::from_value(json!({
+            "workingOrders": [
+                working_order_json("CS.D.EURUSD.TODAY.IP"),
+                working_order_json("IX.D.DAX.IFMM.IP"),
+                working_order_json("CS.D.EURUSD.TODAY.IP")
+            ]
+        }))
+        .unwrap();
+        assert_eq!(orders.epics(), vec!["CS.D.EURUSD.TODAY.IP", "IX.D.DAX.IFMM.IP"]);
+    }
+}
@@ -3,8 +3,89 @@
     Email: jb@taunais.com 
     Date: 13/5/25
  ******************************************************************************/
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
+use crate::application::models::order::CreateOrderRequest;
+use crate::error::AppError;
+use crate::utils::money::Price;
+
+/// IG market identifier, e.g. `IX.D.DAX.IFMM.IP`.
+///
+/// [`Epic::new`] checks the shape: dot-separated segments of ASCII letters,
+/// digits, `_` or `-`. The `From` conversions from strings do not check;
+/// services validate the epic before sending it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Epic(String);
+
+impl Epic {
+    /// Validates and wraps an epic
+    pub fn new(epic: &str) -> Result<Epic, AppError> {
+        let epic = Epic(epic.to_string());
+        epic.validate()?;
+        Ok(epic)
+    }
+
+    /// Checks that the epic has IG's shape
+    pub fn validate(&self) -> Result<(), AppError> {
+        let invalid = |reason: &str| {
+            Err(AppError::SerializationError(format!("invalid epic {:?}: {}", self.0, reason)))
+        };
+        let segments: Vec<&str> = self.0.split('.').collect();
+        if self.0.is_empty() {
+            return invalid("empty");
+        }
+        if segments.len() < 2 {
+            return invalid("expected dot-separated segments");
+        }
+        if segments.iter().any(|s| s.is_empty()) {
+            return invalid("empty segment");
+        }
+        if !self.0.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')) {
+            return invalid("only letters, digits, '_' and '-' are allowed");
+        }
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Epic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for Epic {
+    fn from(epic: &str) -> Self {
+        Epic(epic.to_string())
+    }
+}
+
+impl From<String> for Epic {
+    fn from(epic: String) -> Self {
+        Epic(epic)
+    }
+}
+
+impl From<&String> for Epic {
+    fn from(epic: &String) -> Self {
+        Epic(epic.clone())
+    }
+}
+
+impl From<Epic> for String {
+    fn from(epic: Epic) -> Self {
+        epic.0
+    }
+}
+
 /// Tipo de instrumento
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -17,12 +98,63 @@ pub enum InstrumentType {
     Options,
     #[serde(rename = "BINARY")]
     Binary,
+    BungeeCapped,
+    BungeeCommodities,
+    BungeeCurrencies,
+    BungeeIndices,
+    KnockoutsCommodities,
+    KnockoutsCurrencies,
+    KnockoutsIndices,
+    KnockoutsShares,
+    Warrants,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Trading status of a market
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MarketStatus {
+    Tradeable,
+    Closed,
+    EditsOnly,
+    OnAuction,
+    OnAuctionNoEdits,
+    Offline,
+    Suspended,
     #[serde(other)]
     Unknown,
 }
 
+impl MarketStatus {
+    /// Whether deals can be opened or closed right now
+    pub fn is_tradeable(&self) -> bool {
+        *self == MarketStatus::Tradeable
+    }
+
+    /// Name used by IG, e.g. `EDITS_ONLY`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketStatus::Tradeable => "TRADEABLE",
+            MarketStatus::Closed => "CLOSED",
+            MarketStatus::EditsOnly => "EDITS_ONLY",
+            MarketStatus::OnAuction => "ON_AUCTION",
+            MarketStatus::OnAuctionNoEdits => "ON_AUCTION_NO_EDITS",
+            MarketStatus::Offline => "OFFLINE",
+            MarketStatus::Suspended => "SUSPENDED",
+            MarketStatus::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+impl fmt::Display for MarketStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Modelo para un instrumento de mercado
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Instrument {
     pub epic: String,
     pub name: String,
@@ -50,10 +182,13 @@ pub struct Instrument {
     #[serde(rename = "chartCode")]
     pub chart_code: Option<String>,
     pub currencies: Option<Vec<Currency>>,
+    /// Fields IG sent that are not modelled above
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 /// Modelo para la divisa de un instrumento
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Currency {
     pub code: String,
     pub symbol: Option<String>,
@@ -66,36 +201,123 @@ pub struct Currency {
 }
 
 /// Modelo para los datos de mercado
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketDetails {
     pub instrument: Instrument,
     pub snapshot: MarketSnapshot,
+    #[serde(rename = "dealingRules", default, skip_serializing_if = "Option::is_none")]
+    pub dealing_rules: Option<DealingRules>,
+}
+
+/// Unit of a [`DealingRule`] value
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DealingRuleUnit {
+    Points,
+    Percentage,
+}
+
+/// A single dealing rule, e.g. `{"unit": "POINTS", "value": 0.5}`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DealingRule {
+    pub unit: DealingRuleUnit,
+    pub value: Price,
+}
+
+impl DealingRule {
+    /// Value when expressed in points; percentage rules need a price and are not checked
+    fn points(&self) -> Option<Price> {
+        (self.unit == DealingRuleUnit::Points).then_some(self.value)
+    }
 }
 
 /// Reglas de negociación para un mercado
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DealingRules {
     #[serde(rename = "minDealSize")]
-    pub min_deal_size: Option<f64>,
+    pub min_deal_size: Option<DealingRule>,
     #[serde(rename = "maxDealSize")]
-    pub max_deal_size: Option<f64>,
+    pub max_deal_size: Option<DealingRule>,
     #[serde(rename = "minControlledRiskStopDistance")]
-    pub min_controlled_risk_stop_distance: Option<f64>,
+    pub min_controlled_risk_stop_distance: Option<DealingRule>,
     #[serde(rename = "minNormalStopOrLimitDistance")]
-    pub min_normal_stop_or_limit_distance: Option<f64>,
+    pub min_normal_stop_or_limit_distance: Option<DealingRule>,
     #[serde(rename = "maxStopOrLimitDistance")]
-    pub max_stop_or_limit_distance: Option<f64>,
+    pub max_stop_or_limit_distance: Option<DealingRule>,
     #[serde(rename = "marketOrderPreference")]
     pub market_order_preference: String,
     #[serde(rename = "trailingStopsPreference")]
     pub trailing_stops_preference: String,
 }
 
+impl DealingRules {
+    /// Checks the order's size and stop/limit distances against these rules,
+    /// so that orders IG would reject fail before being sent.
+    ///
+    /// Stop and limit levels can only be checked when the order has a `level`;
+    /// rules expressed as a percentage are skipped.
+    pub fn check_order(&self, order: &CreateOrderRequest) -> Result<(), AppError> {
+        if let Some(min) = self.min_deal_size.as_ref().and_then(DealingRule::points)
+            && order.size < min
+        {
+            return Err(AppError::SerializationError(format!(
+                "size {} is below the minimum deal size {}",
+                order.size, min
+            )));
+        }
+        if let Some(max) = self.max_deal_size.as_ref().and_then(DealingRule::points)
+            && order.size > max
+        {
+            return Err(AppError::SerializationError(format!(
+                "size {} is above the maximum deal size {}",
+                order.size, max
+            )));
+        }
+
+        let distance = |level: Option<Price>, distance: Option<Price>| {
+            distance.or_else(|| Some((order.level? - level?).abs()))
+        };
+        let min_stop = if order.guaranteed_stop == Some(true) {
+            &self.min_controlled_risk_stop_distance
+        } else {
+            &self.min_normal_stop_or_limit_distance
+        };
+        let checks = [
+            ("stop", distance(order.stop_level, order.stop_distance), min_stop),
+            (
+                "limit",
+                distance(order.limit_level, order.limit_distance),
+                &self.min_normal_stop_or_limit_distance,
+            ),
+        ];
+        for (name, distance, min) in checks {
+            let Some(distance) = distance else { continue };
+            if let Some(min) = min.as_ref().and_then(DealingRule::points)
+                && distance < min
+            {
+                return Err(AppError::SerializationError(format!(
+                    "{} distance {} is below the minimum {}",
+                    name, distance, min
+                )));
+            }
+            if let Some(max) = self.max_stop_or_limit_distance.as_ref().and_then(DealingRule::points)
+                && distance > max
+            {
+                return Err(AppError::SerializationError(format!(
+                    "{} distance {} is above the maximum {}",
+                    name, distance, max
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Instantánea de mercado
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketSnapshot {
     #[serde(rename = "marketStatus")]
-    pub market_status: String,
+    pub market_status: MarketStatus,
     #[serde(rename = "netChange")]
     pub net_change: Option<f64>,
     #[serde(rename = "percentageChange")]
@@ -104,12 +326,12 @@ pub struct MarketSnapshot {
     pub update_time: Option<String>,
     #[serde(rename = "delayTime")]
     pub delay_time: Option<i64>,
-    pub bid: Option<f64>,
-    pub offer: Option<f64>,
+    pub bid: Option<Price>,
+    pub offer: Option<Price>,
     #[serde(rename = "high")]
-    pub high: Option<f64>,
+    pub high: Option<Price>,
     #[serde(rename = "low")]
-    pub low: Option<f64>,
+    pub low: Option<Price>,
     #[serde(rename = "binaryOdds")]
     pub binary_odds: Option<f64>,
     #[serde(rename = "decimalPlacesFactor")]
@@ -118,16 +340,29 @@ pub struct MarketSnapshot {
     pub scaling_factor: Option<i64>,
     #[serde(rename = "controlledRiskExtraSpread")]
     pub controlled_risk_extra_spread: Option<f64>,
+    /// Fields IG sent that are not modelled above
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 /// Modelo para la búsqueda de mercados
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketSearchResult {
     pub markets: Vec<MarketData>,
 }
 
+impl MarketSearchResult {
+    /// Markets whose instrument type is one of `types`
+    pub fn filter_by_type(&self, types: &[InstrumentType]) -> Vec<&MarketData> {
+        self.markets
+            .iter()
+            .filter(|m| types.contains(&m.instrument_type))
+            .collect()
+    }
+}
+
 /// Datos básicos de un mercado
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketData {
     pub epic: String,
     #[serde(rename = "instrumentName")]
@@ -140,7 +375,7 @@ pub struct MarketData {
     #[serde(rename = "lowLimitPrice")]
     pub low_limit_price: Option<f64>,
     #[serde(rename = "marketStatus")]
-    pub market_status: String,
+    pub market_status: MarketStatus,
     #[serde(rename = "netChange")]
     pub net_change: Option<f64>,
     #[serde(rename = "percentageChange")]
@@ -151,8 +386,147 @@ pub struct MarketData {
     pub offer: Option<f64>,
 }
 
+/// Deserializes a list that IG may send as `null`, mapping `null` to empty
+fn null_as_empty<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<Vec<T>>::deserialize(deserializer).map(Option::unwrap_or_default)
+}
+
+/// One level of IG's market tree, as returned by `marketnavigation`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketNavigation {
+    /// Child nodes; empty at a leaf
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub nodes: Vec<Node>,
+    /// Markets directly under this node; empty at the root
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub markets: Vec<MarketData>,
+}
+
+/// Node of the market tree, e.g. `Indices` or `Germany`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Node {
+    /// Id to pass to `get_market_navigation` to browse this node
+    pub id: String,
+    pub name: String,
+}
+
+/// Bar resolution accepted by IG's historical prices endpoints
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Resolution {
+    Second,
+    Minute,
+    #[serde(rename = "MINUTE_2")]
+    Minute2,
+    #[serde(rename = "MINUTE_3")]
+    Minute3,
+    #[serde(rename = "MINUTE_5")]
+    Minute5,
+    #[serde(rename = "MINUTE_10")]
+    Minute10,
+    #[serde(rename = "MINUTE_15")]
+    Minute15,
+    #[serde(rename = "MINUTE_30")]
+    Minute30,
+    Hour,
+    #[serde(rename = "HOUR_2")]
+    Hour2,
+    #[serde(rename = "HOUR_3")]
+    Hour3,
+    #[serde(rename = "HOUR_4")]
+    Hour4,
+    Day,
+    Week,
+    Month,
+}
+
+impl Resolution {
+    /// Every resolution, from finest to coarsest
+    pub const ALL: [Resolution; 15] = [
+        Resolution::Second,
+        Resolution::Minute,
+        Resolution::Minute2,
+        Resolution::Minute3,
+        Resolution::Minute5,
+        Resolution::Minute10,
+        Resolution::Minute15,
+        Resolution::Minute30,
+        Resolution::Hour,
+        Resolution::Hour2,
+        Resolution::Hour3,
+        Resolution::Hour4,
+        Resolution::Day,
+        Resolution::Week,
+        Resolution::Month,
+    ];
+
+    /// IG token for this resolution, e.g. `MINUTE_5`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Resolution::Second => "SECOND",
+            Resolution::Minute => "MINUTE",
+            Resolution::Minute2 => "MINUTE_2",
+            Resolution::Minute3 => "MINUTE_3",
+            Resolution::Minute5 => "MINUTE_5",
+            Resolution::Minute10 => "MINUTE_10",
+            Resolution::Minute15 => "MINUTE_15",
+            Resolution::Minute30 => "MINUTE_30",
+            Resolution::Hour => "HOUR",
+            Resolution::Hour2 => "HOUR_2",
+            Resolution::Hour3 => "HOUR_3",
+            Resolution::Hour4 => "HOUR_4",
+            Resolution::Day => "DAY",
+            Resolution::Week => "WEEK",
+            Resolution::Month => "MONTH",
+        }
+    }
+}
+
+impl Resolution {
+    /// Scale token used by streaming `CHART:{epic}:{scale}` subscriptions;
+    /// only a few resolutions can be streamed
+    pub fn chart_scale(&self) -> Option<&'static str> {
+        match self {
+            Resolution::Second => Some("SECOND"),
+            Resolution::Minute => Some("1MINUTE"),
+            Resolution::Minute5 => Some("5MINUTE"),
+            Resolution::Hour => Some("HOUR"),
+            _ => None,
+        }
+    }
+
+    /// Inverse of [`chart_scale`](Self::chart_scale)
+    pub fn from_chart_scale(scale: &str) -> Option<Self> {
+        Resolution::ALL
+            .into_iter()
+            .find(|r| r.chart_scale() == Some(scale))
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Resolution {
+    type Err = AppError;
+
+    /// Parses an IG token such as `"MINUTE_5"` (case-insensitive)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Resolution::ALL
+            .into_iter()
+            .find(|r| r.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| AppError::SerializationError(format!("unknown resolution: {s}")))
+    }
+}
+
 /// Modelo para los precios históricos
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoricalPricesResponse {
     pub prices: Vec<HistoricalPrice>,
     #[serde(rename = "instrumentType")]
@@ -162,7 +536,7 @@ pub struct HistoricalPricesResponse {
 }
 
 /// Precio histórico
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoricalPrice {
     #[serde(rename = "snapshotTime")]
     pub snapshot_time: String,
@@ -179,7 +553,7 @@ pub struct HistoricalPrice {
 }
 
 /// Punto de precio
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PricePoint {
     pub bid: Option<f64>,
     pub ask: Option<f64>,
@@ -188,7 +562,7 @@ pub struct PricePoint {
 }
 
 /// Información sobre la asignación de precios
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriceAllowance {
     #[serde(rename = "remainingAllowance")]
     pub remaining_allowance: i64,
@@ -197,3 +571,291 @@ pub struct PriceAllowance {
     #[serde(rename = "allowanceExpiry")]
     pub allowance_expiry: i64,
 }
+
+#[cfg(test)]
+mod tests_market {
+    use super::*;
+    use crate::utils::money::price;
+
+    #[test]
+    fn test_market_details_round_trip() {
+        let json = serde_json::json!({
+            "instrument": {
+                "epic": "IX.D.DAX.IFMM.IP",
+                "name": "Germany 40",
+                "instrumentType": "INDICES",
+                "expiry": "-",
+                "contractSize": 1.0,
+                "lotSize": 1.0,
+                "highLimitPrice": null,
+                "lowLimitPrice": null,
+                "marginFactor": 5.0,
+                "marginFactorUnit": "PERCENTAGE",
+                "slippageFactor": null,
+                "limitedRiskPremium": null,
+                "newsCode": ".GDAXI",
+                "chartCode": "DAX",
+                "currencies": [{
+                    "code": "EUR",
+                    "symbol": "E",
+                    "baseExchangeRate": 1.0,
+                    "exchangeRate": 0.9,
+                    "isDefault": true
+                }]
+            },
+            "snapshot": {
+                "marketStatus": "TRADEABLE",
+                "netChange": 12.5,
+                "percentageChange": 0.07,
+                "updateTime": "12:00:00",
+                "delayTime": 0,
+                "bid": 18000.5,
+                "offer": 18001.5,
+                "high": 18050.0,
+                "low": 17950.0,
+                "binaryOdds": null,
+                "decimalPlacesFactor": 1,
+                "scalingFactor": 1,
+                "controlledRiskExtraSpread": 1.0
+            }
+        });
+
+        let details: MarketDetails = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&details).unwrap(), json);
+    }
+
+    #[test]
+    fn test_unknown_fields_are_kept() {
+        let json = serde_json::json!({
+            "marketStatus": "TRADEABLE",
+            "netChange": null,
+            "percentageChange": null,
+            "updateTime": null,
+            "delayTime": null,
+            "bid": 18000.5,
+            "offer": 18001.5,
+            "high": null,
+            "low": null,
+            "binaryOdds": null,
+            "decimalPlacesFactor": null,
+            "scalingFactor": null,
+            "controlledRiskExtraSpread": null,
+            "auctionPrice": {"level": 18000.0}
+        });
+
+        let snapshot: MarketSnapshot = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(snapshot.bid, Some(price!(18000.5)));
+        assert_eq!(snapshot.extra.len(), 1);
+        assert_eq!(snapshot.extra["auctionPrice"], serde_json::json!({"level": 18000.0}));
+        assert_eq!(serde_json::to_value(&snapshot).unwrap(), json);
+    }
+
+    fn dealing_rules() -> DealingRules {
+        serde_json::from_value(serde_json::json!({
+            "minDealSize": {"unit": "POINTS", "value": 0.5},
+            "maxDealSize": {"unit": "POINTS", "value": 100},
+            "minControlledRiskStopDistance": {"unit": "POINTS", "value": 20},
+            "minNormalStopOrLimitDistance": {"unit": "POINTS", "value": 8},
+            "maxStopOrLimitDistance": {"unit": "PERCENTAGE", "value": 75.0},
+            "marketOrderPreference": "AVAILABLE_DEFAULT_ON",
+            "trailingStopsPreference": "AVAILABLE"
+        }))
+        .unwrap()
+    }
+
+    fn assert_rejected(result: Result<(), AppError>, expected: &str) {
+        match result {
+            Err(AppError::SerializationError(msg)) => assert_eq!(msg, expected),
+            other => panic!("expected SerializationError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_order_deal_size() {
+        use crate::application::models::order::Direction;
+        let rules = dealing_rules();
+
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(1.0));
+        assert!(rules.check_order(&order).is_ok());
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(0.1));
+        assert_rejected(rules.check_order(&order), "size 0.1 is below the minimum deal size 0.5");
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(150.0));
+        assert_rejected(rules.check_order(&order), "size 150 is above the maximum deal size 100");
+    }
+
+    #[test]
+    fn test_check_order_stop_distance() {
+        use crate::application::models::order::{Direction, OrderType};
+        let rules = dealing_rules();
+        let builder = || {
+            CreateOrderRequest::builder(
+                "IX.D.DAX.IFMM.IP".to_string(),
+                Direction::Buy,
+                price!(1.0),
+                OrderType::Limit,
+            )
+            .level(price!(18000.0))
+        };
+
+        let order = builder().stop_level(price!(17990.0)).build().unwrap();
+        assert!(rules.check_order(&order).is_ok());
+        let order = builder().stop_level(price!(17995.0)).build().unwrap();
+        assert_rejected(rules.check_order(&order), "stop distance 5 is below the minimum 8");
+        let order = builder().limit_distance(price!(2.0)).build().unwrap();
+        assert_rejected(rules.check_order(&order), "limit distance 2 is below the minimum 8");
+        let order = builder().stop_distance(price!(10.0)).guaranteed_stop(true).build().unwrap();
+        assert_rejected(rules.check_order(&order), "stop distance 10 is below the minimum 20");
+    }
+
+    #[test]
+    fn test_epic_accepts_ig_shapes() {
+        for epic in ["IX.D.DAX.IFMM.IP", "CS.D.EURUSD.MINI.IP", "ED.D.SAN_ES.CASH.IP", "OP.D.FTSE2.7500C-1.IP"] {
+            let parsed = Epic::new(epic).unwrap();
+            assert_eq!(parsed.to_string(), epic);
+            assert_eq!(String::from(parsed), epic);
+        }
+    }
+
+    #[test]
+    fn test_epic_rejects_malformed() {
+        let reason = |epic: &str| match Epic::new(epic) {
+            Err(AppError::SerializationError(msg)) => msg,
+            other => panic!("expected SerializationError for {:?}, got {:?}", epic, other),
+        };
+        assert_eq!(reason(""), "invalid epic \"\": empty");
+        assert_eq!(reason("DAX"), "invalid epic \"DAX\": expected dot-separated segments");
+        assert_eq!(reason("IX..DAX.IP"), "invalid epic \"IX..DAX.IP\": empty segment");
+        assert_eq!(reason("IX.D.DAX.IFMM.IP."), "invalid epic \"IX.D.DAX.IFMM.IP.\": empty segment");
+        assert_eq!(
+            reason("IX.D.DAX IFMM.IP"),
+            "invalid epic \"IX.D.DAX IFMM.IP\": only letters, digits, '_' and '-' are allowed"
+        );
+    }
+
+    #[test]
+    fn test_resolution_ig_tokens() {
+        let expected = [
+            (Resolution::Second, "SECOND"),
+            (Resolution::Minute, "MINUTE"),
+            (Resolution::Minute2, "MINUTE_2"),
+            (Resolution::Minute3, "MINUTE_3"),
+            (Resolution::Minute5, "MINUTE_5"),
+            (Resolution::Minute10, "MINUTE_10"),
+            (Resolution::Minute15, "MINUTE_15"),
+            (Resolution::Minute30, "MINUTE_30"),
+            (Resolution::Hour, "HOUR"),
+            (Resolution::Hour2, "HOUR_2"),
+            (Resolution::Hour3, "HOUR_3"),
+            (Resolution::Hour4, "HOUR_4"),
+            (Resolution::Day, "DAY"),
+            (Resolution::Week, "WEEK"),
+            (Resolution::Month, "MONTH"),
+        ];
+        assert_eq!(expected.len(), Resolution::ALL.len());
+        for (resolution, token) in expected {
+            assert_eq!(resolution.as_str(), token);
+            assert_eq!(resolution.to_string(), token);
+            assert_eq!(serde_json::to_value(resolution).unwrap(), token);
+            assert_eq!(token.parse::<Resolution>().unwrap(), resolution);
+        }
+    }
+
+    #[test]
+    fn test_instrument_type_turbo_round_trip() {
+        let expected = [
+            (InstrumentType::BungeeCapped, "BUNGEE_CAPPED"),
+            (InstrumentType::BungeeCommodities, "BUNGEE_COMMODITIES"),
+            (InstrumentType::BungeeCurrencies, "BUNGEE_CURRENCIES"),
+            (InstrumentType::BungeeIndices, "BUNGEE_INDICES"),
+            (InstrumentType::KnockoutsCommodities, "KNOCKOUTS_COMMODITIES"),
+            (InstrumentType::KnockoutsCurrencies, "KNOCKOUTS_CURRENCIES"),
+            (InstrumentType::KnockoutsIndices, "KNOCKOUTS_INDICES"),
+            (InstrumentType::KnockoutsShares, "KNOCKOUTS_SHARES"),
+            (InstrumentType::Warrants, "WARRANTS"),
+        ];
+        for (instrument_type, token) in expected {
+            assert_eq!(serde_json::to_value(&instrument_type).unwrap(), token);
+            let decoded: InstrumentType = serde_json::from_value(token.into()).unwrap();
+            assert_eq!(decoded, instrument_type);
+        }
+    }
+
+    #[test]
+    fn test_instrument_type_unknown_fallback() {
+        let decoded: InstrumentType = serde_json::from_value("SOMETHING_NEW".into()).unwrap();
+        assert_eq!(decoded, InstrumentType::Unknown);
+    }
+
+    fn market(epic: &str, instrument_type: &str) -> serde_json::Value {
+        serde_json::json!({
+            "epic": epic,
+            "instrumentName": epic,
+            "instrumentType": instrument_type,
+            "expiry": "-",
+            "highLimitPrice": null,
+            "lowLimitPrice": null,
+            "marketStatus": "TRADEABLE",
+            "netChange": null,
+            "percentageChange": null,
+            "updateTime": null,
+            "bid": null,
+            "offer": null
+        })
+    }
+
+    #[test]
+    fn test_filter_by_type() {
+        let result: MarketSearchResult = serde_json::from_value(serde_json::json!({
+            "markets": [
+                market("IX.D.DAX.IFMM.IP", "INDICES"),
+                market("OP.D.DAX1.18000C.IP", "OPTIONS"),
+                market("CS.D.EURUSD.CFD.IP", "CURRENCIES"),
+                market("IX.D.FTSE.IFMM.IP", "INDICES")
+            ]
+        }))
+        .unwrap();
+
+        let epics: Vec<_> = result
+            .filter_by_type(&[InstrumentType::Indices, InstrumentType::Options])
+            .into_iter()
+            .map(|m| m.epic.as_str())
+            .collect();
+        assert_eq!(
+            epics,
+            vec!["IX.D.DAX.IFMM.IP", "OP.D.DAX1.18000C.IP", "IX.D.FTSE.IFMM.IP"]
+        );
+        assert!(result.filter_by_type(&[InstrumentType::Shares]).is_empty());
+    }
+
+    #[test]
+    fn test_market_status_round_trip() {
+        let statuses = [
+            (MarketStatus::Tradeable, "TRADEABLE"),
+            (MarketStatus::Closed, "CLOSED"),
+            (MarketStatus::EditsOnly, "EDITS_ONLY"),
+            (MarketStatus::OnAuction, "ON_AUCTION"),
+            (MarketStatus::OnAuctionNoEdits, "ON_AUCTION_NO_EDITS"),
+            (MarketStatus::Offline, "OFFLINE"),
+            (MarketStatus::Suspended, "SUSPENDED"),
+            (MarketStatus::Unknown, "UNKNOWN"),
+        ];
+        for (status, name) in statuses {
+            let json = serde_json::to_value(status).unwrap();
+            assert_eq!(json, serde_json::json!(name));
+            assert_eq!(serde_json::from_value::<MarketStatus>(json).unwrap(), status);
+            assert_eq!(status.to_string(), name);
+            assert_eq!(status.is_tradeable(), status == MarketStatus::Tradeable);
+        }
+        let unknown: MarketStatus = serde_json::from_str("\"HALTED\"").unwrap();
+        assert_eq!(unknown, MarketStatus::Unknown);
+    }
+
+    #[test]
+    fn test_resolution_from_str() {
+        assert_eq!("minute_15".parse::<Resolution>().unwrap(), Resolution::Minute15);
+        assert!(matches!(
+            "MINUTE_7".parse::<Resolution>(),
+            Err(AppError::SerializationError(_))
+        ));
+    }
+}
@@ -1,4 +1,6 @@
 pub mod transaction;
 pub mod order;
 pub mod market;
-pub mod account;
\ No newline at end of file
+pub mod account;
+pub mod operations;
+pub mod paging;
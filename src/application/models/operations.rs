@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+/// An API application (key) and its request allowances, as returned by
+/// `GET /operations/application`. Allowances are requests per minute.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationDetails {
+    pub api_key: String,
+    pub name: Option<String>,
+    pub status: Option<String>,
+    /// Requests per minute across every account using this application
+    pub allowance_application_overall: Option<u32>,
+    /// Trading requests per minute across every account using this application
+    pub allowance_application_trading: Option<u32>,
+    /// Requests per minute for a single account
+    pub allowance_account_overall: Option<u32>,
+    /// Trading requests per minute for a single account
+    pub allowance_account_trading: Option<u32>,
+    /// Historical price data points per week for a single account
+    pub allowance_account_historical_data: Option<u32>,
+    pub concurrent_subscriptions_limit: Option<u32>,
+    pub allow_equities: Option<bool>,
+    pub allow_quote_orders: Option<bool>,
+    pub created_date: Option<String>,
+}
+
+#[cfg(test)]
+mod tests_operations {
+    use super::*;
+
+    #[test]
+    fn test_application_details_decodes_ig_payload() {
+        let body = r#"[{
+            "apiKey": "abc123",
+            "name": "my bot",
+            "status": "ENABLED",
+            "allowanceApplicationOverall": 60,
+            "allowanceApplicationTrading": 100,
+            "allowanceAccountOverall": 30,
+            "allowanceAccountTrading": 100,
+            "allowanceAccountHistoricalData": 10000,
+            "concurrentSubscriptionsLimit": 40,
+            "allowEquities": false,
+            "allowQuoteOrders": false,
+            "createdDate": "2024-09-03"
+        }]"#;
+
+        let applications: Vec<ApplicationDetails> = serde_json::from_str(body).unwrap();
+
+        let app = &applications[0];
+        assert_eq!(app.api_key, "abc123");
+        assert_eq!(app.allowance_account_overall, Some(30));
+        assert_eq!(app.allowance_account_trading, Some(100));
+        assert_eq!(app.allowance_account_historical_data, Some(10000));
+        assert_eq!(app.concurrent_subscriptions_limit, Some(40));
+        assert_eq!(app.allow_equities, Some(false));
+    }
+}
@@ -3,8 +3,14 @@
     Email: jb@taunais.com 
     Date: 13/5/25
  ******************************************************************************/
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::application::models::account::Position;
+use crate::error::AppError;
+use crate::utils::money::Price;
+use crate::utils::dealref::validate_deal_reference;
+
 /// Dirección de la orden (compra o venta)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
@@ -13,6 +19,16 @@ pub enum Direction {
     Sell,
 }
 
+impl Direction {
+    /// Direction that offsets a position opened in this direction
+    pub fn opposite(&self) -> Self {
+        match self {
+            Direction::Buy => Direction::Sell,
+            Direction::Sell => Direction::Buy,
+        }
+    }
+}
+
 /// Tipo de orden
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
@@ -34,8 +50,20 @@ pub enum OrderStatus {
     Filled,
     Cancelled,
     Expired,
+    Open,
+    Updated,
+    Amended,
+    Deleted,
+    Closed,
+    #[serde(rename = "FULLY_CLOSED")]
+    FullyClosed,
+    #[serde(rename = "PARTIALLY_CLOSED")]
+    PartiallyClosed,
 }
 
+/// Pattern IG expects for `goodTillDate`
+pub const GOOD_TILL_DATE_FORMAT: &str = "%Y/%m/%d %H:%M:%S";
+
 /// Duración de la orden
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum TimeInForce {
@@ -54,80 +82,314 @@ pub enum TimeInForce {
 pub struct CreateOrderRequest {
     pub epic: String,
     pub direction: Direction,
-    pub size: f64,
+    pub size: Price,
     #[serde(rename = "orderType")]
     pub order_type: OrderType,
     #[serde(rename = "timeInForce")]
     pub time_in_force: TimeInForce,
     #[serde(rename = "level", skip_serializing_if = "Option::is_none")]
-    pub level: Option<f64>,
+    pub level: Option<Price>,
     #[serde(rename = "guaranteedStop", skip_serializing_if = "Option::is_none")]
     pub guaranteed_stop: Option<bool>,
     #[serde(rename = "stopLevel", skip_serializing_if = "Option::is_none")]
-    pub stop_level: Option<f64>,
+    pub stop_level: Option<Price>,
     #[serde(rename = "stopDistance", skip_serializing_if = "Option::is_none")]
-    pub stop_distance: Option<f64>,
+    pub stop_distance: Option<Price>,
     #[serde(rename = "limitLevel", skip_serializing_if = "Option::is_none")]
-    pub limit_level: Option<f64>,
+    pub limit_level: Option<Price>,
     #[serde(rename = "limitDistance", skip_serializing_if = "Option::is_none")]
-    pub limit_distance: Option<f64>,
+    pub limit_distance: Option<Price>,
     #[serde(rename = "expiry", skip_serializing_if = "Option::is_none")]
     pub expiry: Option<String>,
     #[serde(rename = "dealReference", skip_serializing_if = "Option::is_none")]
     pub deal_reference: Option<String>,
     #[serde(rename = "forceOpen", skip_serializing_if = "Option::is_none")]
     pub force_open: Option<bool>,
+    /// Expiry of a GOOD_TILL_DATE order, formatted with [`GOOD_TILL_DATE_FORMAT`]
+    #[serde(rename = "goodTillDate", skip_serializing_if = "Option::is_none")]
+    pub good_till_date: Option<String>,
 }
 
 impl CreateOrderRequest {
+    /// Starts a builder for an order of any type
+    pub fn builder(
+        epic: String,
+        direction: Direction,
+        size: Price,
+        order_type: OrderType,
+    ) -> CreateOrderRequestBuilder {
+        CreateOrderRequestBuilder::new(epic, direction, size, order_type)
+    }
+
     /// Crea una nueva orden de mercado
-    pub fn market(epic: String, direction: Direction, size: f64) -> Self {
+    pub fn market(epic: String, direction: Direction, size: Price) -> Self {
+        Self::builder(epic, direction, size, OrderType::Market).into_request()
+    }
+
+    /// Crea una nueva orden limitada
+    pub fn limit(epic: String, direction: Direction, size: Price, level: Price) -> Self {
+        Self::builder(epic, direction, size, OrderType::Limit)
+            .level(level)
+            .into_request()
+    }
+
+    /// Añade un stop loss a la orden
+    pub fn with_stop_loss(mut self, stop_level: Price) -> Self {
+        self.stop_level = Some(stop_level);
+        self
+    }
+
+    /// Añade un take profit a la orden
+    pub fn with_take_profit(mut self, limit_level: Price) -> Self {
+        self.limit_level = Some(limit_level);
+        self
+    }
+
+    /// Añade una referencia a la orden
+    pub fn with_reference(mut self, reference: String) -> Self {
+        self.deal_reference = Some(reference);
+        self
+    }
+
+    /// Like [`with_reference`](Self::with_reference), but rejects references
+    /// IG would not accept
+    pub fn try_with_reference(self, reference: String) -> Result<Self, AppError> {
+        validate_deal_reference(&reference)?;
+        Ok(self.with_reference(reference))
+    }
+
+    /// Checks the field combinations IG rejects before the order is sent
+    pub fn validate(&self) -> Result<(), AppError> {
+        match self.order_type {
+            OrderType::Limit | OrderType::Stop | OrderType::StopLimit if self.level.is_none() => {
+                return Err(AppError::SerializationError(format!(
+                    "{:?} order requires a level",
+                    self.order_type
+                )));
+            }
+            OrderType::Market if self.level.is_some() => {
+                return Err(AppError::SerializationError(
+                    "Market order must not set a level".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        if self.stop_level.is_some() && self.stop_distance.is_some() {
+            return Err(AppError::SerializationError(
+                "stop_level and stop_distance are mutually exclusive".to_string(),
+            ));
+        }
+        if self.limit_level.is_some() && self.limit_distance.is_some() {
+            return Err(AppError::SerializationError(
+                "limit_level and limit_distance are mutually exclusive".to_string(),
+            ));
+        }
+        if self.guaranteed_stop == Some(true) && self.stop_level.is_none() && self.stop_distance.is_none() {
+            return Err(AppError::SerializationError(
+                "guaranteed_stop requires a stop_level or stop_distance".to_string(),
+            ));
+        }
+        match (&self.time_in_force, &self.good_till_date) {
+            (TimeInForce::GoodTillDate, None) => {
+                return Err(AppError::SerializationError(
+                    "GoodTillDate order requires a good_till_date".to_string(),
+                ));
+            }
+            (time_in_force, Some(_)) if *time_in_force != TimeInForce::GoodTillDate => {
+                return Err(AppError::SerializationError(format!(
+                    "good_till_date is only allowed with GoodTillDate, not {:?}",
+                    time_in_force
+                )));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Fluent builder for [`CreateOrderRequest`]. Market orders default to
+/// FILL_OR_KILL and other types to GOOD_TILL_CANCELLED; `force_open` defaults to true.
+#[derive(Debug, Clone)]
+pub struct CreateOrderRequestBuilder {
+    request: CreateOrderRequest,
+}
+
+impl CreateOrderRequestBuilder {
+    pub fn new(epic: String, direction: Direction, size: Price, order_type: OrderType) -> Self {
+        let time_in_force = match order_type {
+            OrderType::Market => TimeInForce::FillOrKill,
+            _ => TimeInForce::GoodTillCancelled,
+        };
         Self {
-            epic,
-            direction,
-            size,
-            order_type: OrderType::Market,
-            time_in_force: TimeInForce::FillOrKill,
-            level: None,
-            guaranteed_stop: None,
-            stop_level: None,
-            stop_distance: None,
-            limit_level: None,
-            limit_distance: None,
-            expiry: None,
-            deal_reference: None,
-            force_open: Some(true),
+            request: CreateOrderRequest {
+                epic,
+                direction,
+                size,
+                order_type,
+                time_in_force,
+                level: None,
+                guaranteed_stop: None,
+                stop_level: None,
+                stop_distance: None,
+                limit_level: None,
+                limit_distance: None,
+                expiry: None,
+                deal_reference: None,
+                force_open: Some(true),
+                good_till_date: None,
+            },
         }
     }
 
-    /// Crea una nueva orden limitada
-    pub fn limit(epic: String, direction: Direction, size: f64, level: f64) -> Self {
+    /// Order level, required for LIMIT and STOP orders
+    pub fn level(mut self, level: Price) -> Self {
+        self.request.level = Some(level);
+        self
+    }
+
+    pub fn stop_level(mut self, stop_level: Price) -> Self {
+        self.request.stop_level = Some(stop_level);
+        self
+    }
+
+    /// Stop as a distance in points from the opening level
+    pub fn stop_distance(mut self, stop_distance: Price) -> Self {
+        self.request.stop_distance = Some(stop_distance);
+        self
+    }
+
+    pub fn limit_level(mut self, limit_level: Price) -> Self {
+        self.request.limit_level = Some(limit_level);
+        self
+    }
+
+    /// Limit as a distance in points from the opening level
+    pub fn limit_distance(mut self, limit_distance: Price) -> Self {
+        self.request.limit_distance = Some(limit_distance);
+        self
+    }
+
+    pub fn guaranteed_stop(mut self, guaranteed_stop: bool) -> Self {
+        self.request.guaranteed_stop = Some(guaranteed_stop);
+        self
+    }
+
+    pub fn force_open(mut self, force_open: bool) -> Self {
+        self.request.force_open = Some(force_open);
+        self
+    }
+
+    /// Contract expiry, e.g. `DFB` or `-`
+    pub fn expiry(mut self, expiry: String) -> Self {
+        self.request.expiry = Some(expiry);
+        self
+    }
+
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.request.time_in_force = time_in_force;
+        self
+    }
+
+    pub fn deal_reference(mut self, reference: String) -> Self {
+        self.request.deal_reference = Some(reference);
+        self
+    }
+
+    /// Keeps the order alive until `date`; sets the time in force to GOOD_TILL_DATE
+    pub fn good_till_date(mut self, date: DateTime<Utc>) -> Self {
+        self.request.time_in_force = TimeInForce::GoodTillDate;
+        self.request.good_till_date = Some(date.format(GOOD_TILL_DATE_FORMAT).to_string());
+        self
+    }
+
+    /// Validates and returns the request
+    pub fn build(self) -> Result<CreateOrderRequest, AppError> {
+        self.request.validate()?;
+        Ok(self.request)
+    }
+
+    fn into_request(self) -> CreateOrderRequest {
+        self.request
+    }
+}
+
+/// Model for creating a working order (a LIMIT or STOP order resting at `level`)
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateWorkingOrderRequest {
+    pub epic: String,
+    pub direction: Direction,
+    pub size: Price,
+    pub level: Price,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    #[serde(rename = "timeInForce")]
+    pub time_in_force: TimeInForce,
+    #[serde(rename = "guaranteedStop")]
+    pub guaranteed_stop: bool,
+    #[serde(rename = "currencyCode", skip_serializing_if = "Option::is_none")]
+    pub currency_code: Option<String>,
+    #[serde(rename = "expiry", skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<String>,
+    #[serde(rename = "goodTillDate", skip_serializing_if = "Option::is_none")]
+    pub good_till_date: Option<String>,
+    #[serde(rename = "stopLevel", skip_serializing_if = "Option::is_none")]
+    pub stop_level: Option<Price>,
+    #[serde(rename = "stopDistance", skip_serializing_if = "Option::is_none")]
+    pub stop_distance: Option<Price>,
+    #[serde(rename = "limitLevel", skip_serializing_if = "Option::is_none")]
+    pub limit_level: Option<Price>,
+    #[serde(rename = "limitDistance", skip_serializing_if = "Option::is_none")]
+    pub limit_distance: Option<Price>,
+    #[serde(rename = "dealReference", skip_serializing_if = "Option::is_none")]
+    pub deal_reference: Option<String>,
+}
+
+impl CreateWorkingOrderRequest {
+    /// Creates a LIMIT working order, good till cancelled
+    pub fn limit(epic: String, direction: Direction, size: Price, level: Price) -> Self {
+        Self::new(epic, direction, size, level, OrderType::Limit)
+    }
+
+    /// Creates a STOP working order, good till cancelled
+    pub fn stop(epic: String, direction: Direction, size: Price, level: Price) -> Self {
+        Self::new(epic, direction, size, level, OrderType::Stop)
+    }
+
+    fn new(epic: String, direction: Direction, size: Price, level: Price, order_type: OrderType) -> Self {
         Self {
             epic,
             direction,
             size,
-            order_type: OrderType::Limit,
+            level,
+            order_type,
             time_in_force: TimeInForce::GoodTillCancelled,
-            level: Some(level),
-            guaranteed_stop: None,
+            guaranteed_stop: false,
+            currency_code: None,
+            expiry: Some("-".to_string()),
+            good_till_date: None,
             stop_level: None,
             stop_distance: None,
             limit_level: None,
             limit_distance: None,
-            expiry: None,
             deal_reference: None,
-            force_open: Some(true),
         }
     }
 
+    /// Keeps the order alive until the given date (`yyyy/mm/dd hh:mm:ss`)
+    pub fn with_good_till_date(mut self, date: String) -> Self {
+        self.time_in_force = TimeInForce::GoodTillDate;
+        self.good_till_date = Some(date);
+        self
+    }
+
     /// Añade un stop loss a la orden
-    pub fn with_stop_loss(mut self, stop_level: f64) -> Self {
+    pub fn with_stop_loss(mut self, stop_level: Price) -> Self {
         self.stop_level = Some(stop_level);
         self
     }
 
     /// Añade un take profit a la orden
-    pub fn with_take_profit(mut self, limit_level: f64) -> Self {
+    pub fn with_take_profit(mut self, limit_level: Price) -> Self {
         self.limit_level = Some(limit_level);
         self
     }
@@ -139,6 +401,33 @@ impl CreateOrderRequest {
     }
 }
 
+/// Model for amending an existing working order
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateWorkingOrderRequest {
+    pub level: Price,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    #[serde(rename = "timeInForce")]
+    pub time_in_force: TimeInForce,
+    #[serde(rename = "goodTillDate", skip_serializing_if = "Option::is_none")]
+    pub good_till_date: Option<String>,
+    #[serde(rename = "stopLevel", skip_serializing_if = "Option::is_none")]
+    pub stop_level: Option<Price>,
+    #[serde(rename = "stopDistance", skip_serializing_if = "Option::is_none")]
+    pub stop_distance: Option<Price>,
+    #[serde(rename = "limitLevel", skip_serializing_if = "Option::is_none")]
+    pub limit_level: Option<Price>,
+    #[serde(rename = "limitDistance", skip_serializing_if = "Option::is_none")]
+    pub limit_distance: Option<Price>,
+}
+
+/// Response to creating, amending or deleting a working order
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkingOrderResponse {
+    #[serde(rename = "dealReference")]
+    pub deal_reference: String,
+}
+
 /// Respuesta a la creación de una orden
 #[derive(Debug, Clone, Deserialize)]
 pub struct CreateOrderResponse {
@@ -164,32 +453,119 @@ pub struct OrderConfirmation {
     #[serde(rename = "guaranteedStop")]
     pub guaranteed_stop: Option<bool>,
     #[serde(rename = "level")]
-    pub level: Option<f64>,
+    pub level: Option<Price>,
     #[serde(rename = "limitDistance")]
-    pub limit_distance: Option<f64>,
+    pub limit_distance: Option<Price>,
     #[serde(rename = "limitLevel")]
-    pub limit_level: Option<f64>,
-    pub size: Option<f64>,
+    pub limit_level: Option<Price>,
+    pub size: Option<Price>,
     #[serde(rename = "stopDistance")]
-    pub stop_distance: Option<f64>,
+    pub stop_distance: Option<Price>,
     #[serde(rename = "stopLevel")]
-    pub stop_level: Option<f64>,
+    pub stop_level: Option<Price>,
     #[serde(rename = "trailingStop")]
     pub trailing_stop: Option<bool>,
     pub direction: Option<Direction>,
 }
 
+impl OrderConfirmation {
+    /// Canned accepted confirmation for an order that was never sent
+    pub fn dry_run(deal_reference: &str) -> Self {
+        Self {
+            date: chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3f").to_string(),
+            status: OrderStatus::Accepted,
+            reason: Some("SUCCESS".to_string()),
+            deal_id: Some(deal_reference.to_string()),
+            deal_reference: deal_reference.to_string(),
+            deal_status: Some("ACCEPTED".to_string()),
+            epic: None,
+            expiry: None,
+            guaranteed_stop: None,
+            level: None,
+            limit_distance: None,
+            limit_level: None,
+            size: None,
+            stop_distance: None,
+            stop_level: None,
+            trailing_stop: None,
+            direction: None,
+        }
+    }
+}
+
+/// Trailing stop parameters, in points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrailingStop {
+    /// Distance of the stop from the current price
+    pub distance: Price,
+    /// Price movement after which the stop moves up
+    pub increment: Price,
+}
+
+impl TrailingStop {
+    pub fn new(distance: Price, increment: Price) -> Self {
+        Self { distance, increment }
+    }
+}
+
 /// Modelo para modificar una posición existente
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct UpdatePositionRequest {
     #[serde(rename = "stopLevel", skip_serializing_if = "Option::is_none")]
-    pub stop_level: Option<f64>,
+    pub stop_level: Option<Price>,
     #[serde(rename = "limitLevel", skip_serializing_if = "Option::is_none")]
-    pub limit_level: Option<f64>,
+    pub limit_level: Option<Price>,
     #[serde(rename = "trailingStop", skip_serializing_if = "Option::is_none")]
     pub trailing_stop: Option<bool>,
     #[serde(rename = "trailingStopDistance", skip_serializing_if = "Option::is_none")]
-    pub trailing_stop_distance: Option<f64>,
+    pub trailing_stop_distance: Option<Price>,
+    #[serde(rename = "trailingStopIncrement", skip_serializing_if = "Option::is_none")]
+    pub trailing_stop_increment: Option<Price>,
+}
+
+impl UpdatePositionRequest {
+    /// Update that keeps the position's current stop, limit and trailing
+    /// stop; IG clears any level left out of the request
+    pub fn preserving(position: &Position) -> Self {
+        let details = &position.position;
+        Self {
+            stop_level: details.stop_level,
+            limit_level: details.limit_level,
+            trailing_stop: details.trailing_stop_distance.map(|_| true),
+            trailing_stop_distance: details.trailing_stop_distance,
+            trailing_stop_increment: details.trailing_stop_distance.and(details.trailing_step),
+        }
+    }
+
+    /// Turns on a trailing stop starting at `stop_level`
+    pub fn with_trailing_stop(mut self, stop_level: Price, trailing: TrailingStop) -> Self {
+        self.stop_level = Some(stop_level);
+        self.trailing_stop = Some(true);
+        self.trailing_stop_distance = Some(trailing.distance);
+        self.trailing_stop_increment = Some(trailing.increment);
+        self
+    }
+
+    /// Checks the trailing-stop combinations IG's v2 endpoint rejects: a
+    /// trailing stop needs a stop level, distance and increment, and the
+    /// distance and increment are only allowed with a trailing stop
+    pub fn validate(&self) -> Result<(), AppError> {
+        let invalid = |msg: &str| Err(AppError::SerializationError(msg.to_string()));
+        if self.trailing_stop == Some(true) {
+            if self.stop_level.is_none() {
+                return invalid("trailing stop requires a stop_level");
+            }
+            if self.trailing_stop_distance.is_none() {
+                return invalid("trailing stop requires a trailing_stop_distance");
+            }
+            if self.trailing_stop_increment.is_none() {
+                return invalid("trailing stop requires a trailing_stop_increment");
+            }
+        } else if self.trailing_stop_distance.is_some() || self.trailing_stop_increment.is_some() {
+            return invalid("trailing_stop_distance and trailing_stop_increment require trailing_stop");
+        }
+        Ok(())
+    }
 }
 
 /// Modelo para cerrar una posición existente
@@ -198,18 +574,18 @@ pub struct ClosePositionRequest {
     #[serde(rename = "dealId")]
     pub deal_id: String,
     pub direction: Direction,
-    pub size: f64,
+    pub size: Price,
     #[serde(rename = "orderType")]
     pub order_type: OrderType,
     #[serde(rename = "timeInForce")]
     pub time_in_force: TimeInForce,
     #[serde(rename = "level", skip_serializing_if = "Option::is_none")]
-    pub level: Option<f64>,
+    pub level: Option<Price>,
 }
 
 impl ClosePositionRequest {
     /// Crea una solicitud para cerrar una posición al mercado
-    pub fn market(deal_id: String, direction: Direction, size: f64) -> Self {
+    pub fn market(deal_id: String, direction: Direction, size: Price) -> Self {
         Self {
             deal_id,
             direction,
@@ -219,6 +595,12 @@ impl ClosePositionRequest {
             level: None,
         }
     }
+
+    /// Market close of the whole position, dealt in the opposite direction
+    pub fn close(position: &Position) -> Self {
+        let details = &position.position;
+        Self::market(details.deal_id.clone(), details.direction.opposite(), details.size)
+    }
 }
 
 /// Respuesta al cerrar una posición
@@ -227,3 +609,191 @@ pub struct ClosePositionResponse {
     #[serde(rename = "dealReference")]
     pub deal_reference: String,
 }
+
+#[cfg(test)]
+mod tests_order {
+    use super::*;
+    use crate::transport::mock::position_json;
+    use crate::utils::money::price;
+
+    const EPIC: &str = "IX.D.DAX.IFMM.IP";
+
+    fn assert_invalid(order: CreateOrderRequest, expected: &str) {
+        match order.validate() {
+            Err(AppError::SerializationError(msg)) => assert_eq!(msg, expected),
+            other => panic!("expected SerializationError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validate_accepts_constructors() {
+        assert!(CreateOrderRequest::market(EPIC.to_string(), Direction::Buy, price!(1.0)).validate().is_ok());
+        assert!(
+            CreateOrderRequest::limit(EPIC.to_string(), Direction::Buy, price!(1.0), price!(18000.0))
+                .with_stop_loss(price!(17900.0))
+                .with_take_profit(price!(18200.0))
+                .validate()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_validate_requires_level_for_resting_orders() {
+        for order_type in [OrderType::Limit, OrderType::Stop, OrderType::StopLimit] {
+            let mut order = CreateOrderRequest::limit(EPIC.to_string(), Direction::Buy, price!(1.0), price!(18000.0));
+            order.order_type = order_type.clone();
+            order.level = None;
+            assert_invalid(order, &format!("{:?} order requires a level", order_type));
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_market_with_level() {
+        let mut order = CreateOrderRequest::market(EPIC.to_string(), Direction::Sell, price!(1.0));
+        order.level = Some(price!(18000.0));
+        assert_invalid(order, "Market order must not set a level");
+    }
+
+    #[test]
+    fn test_validate_rejects_stop_level_and_distance() {
+        let mut order = CreateOrderRequest::market(EPIC.to_string(), Direction::Buy, price!(1.0))
+            .with_stop_loss(price!(17900.0));
+        order.stop_distance = Some(price!(100.0));
+        assert_invalid(order, "stop_level and stop_distance are mutually exclusive");
+    }
+
+    #[test]
+    fn test_try_with_reference() {
+        let order = CreateOrderRequest::market(EPIC.to_string(), Direction::Buy, price!(1.0));
+        assert!(order.clone().try_with_reference("bad ref!".to_string()).is_err());
+        let order = order.try_with_reference("good_ref-1".to_string()).unwrap();
+        assert_eq!(order.deal_reference.as_deref(), Some("good_ref-1"));
+    }
+
+    #[test]
+    fn test_validate_rejects_limit_level_and_distance() {
+        let mut order = CreateOrderRequest::market(EPIC.to_string(), Direction::Buy, price!(1.0))
+            .with_take_profit(price!(18200.0));
+        order.limit_distance = Some(price!(200.0));
+        assert_invalid(order, "limit_level and limit_distance are mutually exclusive");
+    }
+
+    #[test]
+    fn test_builder_distance_based_guaranteed_stop() {
+        let order = CreateOrderRequest::builder(EPIC.to_string(), Direction::Sell, price!(2.0), OrderType::Market)
+            .stop_distance(price!(20.0))
+            .limit_distance(price!(40.0))
+            .guaranteed_stop(true)
+            .force_open(false)
+            .expiry("-".to_string())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&order).unwrap(),
+            serde_json::json!({
+                "epic": EPIC,
+                "direction": "SELL",
+                "size": price!(2.0),
+                "orderType": "MARKET",
+                "timeInForce": "FILL_OR_KILL",
+                "guaranteedStop": true,
+                "stopDistance": price!(20.0),
+                "limitDistance": price!(40.0),
+                "expiry": "-",
+                "forceOpen": false
+            })
+        );
+    }
+
+    #[test]
+    fn test_builder_runs_validation() {
+        let result = CreateOrderRequest::builder(EPIC.to_string(), Direction::Buy, price!(1.0), OrderType::Market)
+            .guaranteed_stop(true)
+            .build();
+        assert!(matches!(
+            result,
+            Err(AppError::SerializationError(msg)) if msg == "guaranteed_stop requires a stop_level or stop_distance"
+        ));
+
+        let result = CreateOrderRequest::builder(EPIC.to_string(), Direction::Buy, price!(1.0), OrderType::Stop)
+            .time_in_force(TimeInForce::GoodTillDate)
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_good_till_date_invariants() {
+        use chrono::TimeZone;
+
+        let date = Utc.with_ymd_and_hms(2025, 6, 20, 16, 30, 0).unwrap();
+        let order = CreateOrderRequest::builder(EPIC.to_string(), Direction::Buy, price!(1.0), OrderType::Limit)
+            .level(price!(18000.0))
+            .good_till_date(date)
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&order).unwrap();
+        assert_eq!(json["timeInForce"], "GOOD_TILL_DATE");
+        assert_eq!(json["goodTillDate"], "2025/06/20 16:30:00");
+
+        let mut missing = order.clone();
+        missing.good_till_date = None;
+        assert_invalid(missing, "GoodTillDate order requires a good_till_date");
+
+        let mut stray = order;
+        stray.time_in_force = TimeInForce::GoodTillCancelled;
+        assert_invalid(stray, "good_till_date is only allowed with GoodTillDate, not GoodTillCancelled");
+
+        let market = CreateOrderRequest::market(EPIC.to_string(), Direction::Buy, price!(1.0));
+        assert!(serde_json::to_value(&market).unwrap().get("goodTillDate").is_none());
+    }
+
+    #[test]
+    fn test_update_trailing_stop_validation() {
+        let trailing = UpdatePositionRequest::default().with_trailing_stop(price!(17900.0), TrailingStop::new(price!(50.0), price!(5.0)));
+        assert!(trailing.validate().is_ok());
+        assert_eq!(
+            serde_json::to_value(&trailing).unwrap(),
+            serde_json::json!({
+                "stopLevel": price!(17900.0),
+                "trailingStop": true,
+                "trailingStopDistance": price!(50.0),
+                "trailingStopIncrement": price!(5.0)
+            })
+        );
+        assert!(UpdatePositionRequest::default().validate().is_ok());
+
+        let update_invalid = |update: UpdatePositionRequest, expected: &str| match update.validate() {
+            Err(AppError::SerializationError(msg)) => assert_eq!(msg, expected),
+            other => panic!("expected SerializationError, got {:?}", other),
+        };
+        let mut no_stop = trailing.clone();
+        no_stop.stop_level = None;
+        update_invalid(no_stop, "trailing stop requires a stop_level");
+        let mut no_distance = trailing.clone();
+        no_distance.trailing_stop_distance = None;
+        update_invalid(no_distance, "trailing stop requires a trailing_stop_distance");
+        let mut no_increment = trailing.clone();
+        no_increment.trailing_stop_increment = None;
+        update_invalid(no_increment, "trailing stop requires a trailing_stop_increment");
+        let mut disabled = trailing;
+        disabled.trailing_stop = Some(false);
+        update_invalid(
+            disabled,
+            "trailing_stop_distance and trailing_stop_increment require trailing_stop",
+        );
+    }
+
+    #[test]
+    fn test_close_request_offsets_position() {
+        let position: Position = serde_json::from_value(position_json(EPIC, "BUY", 2.5)).unwrap();
+
+        let close = ClosePositionRequest::close(&position);
+
+        assert_eq!(close.deal_id, "DIAAAA");
+        assert_eq!(close.direction, Direction::Sell);
+        assert_eq!(close.size, price!(2.5));
+        assert_eq!(close.order_type, OrderType::Market);
+        assert_eq!(close.level, None);
+    }
+}
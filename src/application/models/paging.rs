@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Cursor-based paging shared by IG's v3 history endpoints
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Paging {
+    /// Relative path of the next page, `None` on the last page
+    pub next: Option<String>,
+    /// Number of items on this page
+    pub size: i32,
+}
+
+/// One page of a cursor-paged endpoint. Responses convert into it with
+/// `From` so [`fetch_all_pages`](crate::application::services::paging::fetch_all_pages)
+/// can follow any of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Paged<T> {
+    pub items: Vec<T>,
+    pub paging: Paging,
+}
+
+impl<T> Paged<T> {
+    /// Next page to fetch, ignoring an empty cursor
+    pub fn next(&self) -> Option<&str> {
+        self.paging.next.as_deref().filter(|next| !next.is_empty())
+    }
+}
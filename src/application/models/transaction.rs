@@ -4,8 +4,10 @@
     Date: 12/5/25
  ******************************************************************************/
 use std::fmt;
-use chrono::{DateTime, NaiveDate, Utc};
+use std::str::FromStr;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 /// Raw JSON coming from IG’s transactions endpoint
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -57,16 +59,115 @@ impl fmt::Display for RawTransaction {
     }
 }
 
+/// Contract period of a transaction, from IG's `period` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    /// Daily funded bet (`DFB`)
+    DailyFunded,
+    /// Dated contract such as `JUN-24`, as the first day of its month
+    Dated(NaiveDate),
+    /// No period (`-`), or a value that could not be parsed
+    None,
+}
+
+impl Period {
+    /// Parses `DFB`, `MON-YY` or `MON-YYYY`; month names are case-insensitive.
+    /// Anything else becomes [`Period::None`].
+    pub fn parse(raw: &str) -> Self {
+        let raw = raw.trim();
+        if raw.eq_ignore_ascii_case("DFB") {
+            return Self::DailyFunded;
+        }
+        let dated = raw.split_once('-').and_then(|(month, year)| {
+            let month = chrono::Month::from_str(month.trim()).ok()?;
+            let year = year.trim();
+            let year = match year.len() {
+                2 => 2000 + year.parse::<i32>().ok()?,
+                4 => year.parse::<i32>().ok()?,
+                _ => return Option::None,
+            };
+            NaiveDate::from_ymd_opt(year, month.number_from_month(), 1)
+        });
+        dated.map_or(Self::None, Self::Dated)
+    }
+
+    /// First day of the expiry month of a dated contract
+    pub fn expiry(&self) -> Option<NaiveDate> {
+        match self {
+            Self::Dated(date) => Some(*date),
+            _ => Option::None,
+        }
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DailyFunded => f.write_str("DFB"),
+            Self::Dated(date) => write!(
+                f,
+                "{}-{:02}",
+                date.format("%b").to_string().to_uppercase(),
+                date.year() % 100
+            ),
+            Self::None => f.write_str("-"),
+        }
+    }
+}
+
 #[derive(Debug)]
+// The raw JSON, its hash and the fee flag are only read when storing transactions
+#[cfg_attr(not(feature = "storage"), allow(dead_code))]
 pub struct Transaction {
     pub(crate) deal_date: DateTime<Utc>,
     pub(crate) underlying: Option<String>,
     pub(crate) strike: Option<f64>,
     pub(crate) option_type: Option<String>,
     pub(crate) expiry: Option<NaiveDate>,
+    pub(crate) period: Period,
     pub(crate) transaction_type: String,
-    pub(crate) pnl_eur: f64,
+    /// Profit and loss in `currency`
+    pub(crate) pnl: f64,
+    /// Currency code of `pnl`, e.g. `EUR` or `GBP`
+    pub(crate) currency: String,
     pub(crate) reference: String,
     pub(crate) is_fee: bool,
     pub(crate) raw_json: String,
-}
\ No newline at end of file
+    /// Hex SHA-256 of `raw_json`, used to deduplicate rows in storage
+    pub(crate) raw_hash: String,
+}
+
+impl Transaction {
+    /// Contract period the transaction was dealt on
+    pub fn period(&self) -> Period {
+        self.period
+    }
+}
+
+/// Stable hex-encoded SHA-256 of a raw transaction's JSON
+pub(crate) fn hash_raw_json(raw_json: &str) -> String {
+    format!("{:x}", Sha256::digest(raw_json.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests_transaction {
+    use super::*;
+
+    #[test]
+    fn test_period_parse() {
+        assert_eq!(Period::parse("DFB"), Period::DailyFunded);
+        assert_eq!(Period::parse("dfb"), Period::DailyFunded);
+
+        let june_24 = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        assert_eq!(Period::parse("JUN-24"), Period::Dated(june_24));
+        assert_eq!(Period::parse("jun-24"), Period::Dated(june_24));
+        assert_eq!(Period::parse("Jun-2024"), Period::Dated(june_24));
+        assert_eq!(Period::parse("JUN-24").expiry(), Some(june_24));
+        assert_eq!(Period::parse("JUN-24").to_string(), "JUN-24");
+
+        for raw in ["-", "", "XYZ-24", "JUN-", "JUN-2", "JUN-abc"] {
+            assert_eq!(Period::parse(raw), Period::None, "{raw:?}");
+        }
+        assert_eq!(Period::None.expiry(), None);
+    }
+}
@@ -1,16 +1,23 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use reqwest::Method;
 use std::sync::Arc;
 use tracing::{debug, info};
 
 use crate::{
     application::models::account::{
-        AccountActivity, AccountInfo, Positions, TransactionHistory, WorkingOrders,
+        AccountActivity, AccountInfo, PortfolioSnapshot, Position, Positions,
+        TransactionHistory, WorkingOrders,
     },
+    application::services::paging::fetch_all_pages,
     config::Config,
+    constants::DEFAULT_MAX_ACTIVITY_PAGES,
     error::AppError,
     session::interface::IgSession,
     transport::http_client::IgHttpClient,
+    transport::query::QueryBuilder,
+    utils::dates::format_ig_datetime,
+    utils::finance::calculate_pnl,
 };
 
 /// Interfaz para el servicio de cuenta
@@ -22,23 +29,43 @@ pub trait AccountService: Send + Sync {
     /// Obtiene las posiciones abiertas
     async fn get_positions(&self, session: &IgSession) -> Result<Positions, AppError>;
 
+    /// Fetches a single open position by deal id. Returns `AppError::NotFound`
+    /// once the position has been closed.
+    async fn get_position(&self, session: &IgSession, deal_id: &str) -> Result<Position, AppError>;
+
+    /// Fetches the open positions with each `pnl` populated from the current market price
+    async fn get_positions_with_pnl(&self, session: &IgSession) -> Result<Positions, AppError>;
+
     /// Obtiene las órdenes de trabajo
     async fn get_working_orders(&self, session: &IgSession) -> Result<WorkingOrders, AppError>;
 
+    /// Fetches positions, working orders and accounts concurrently. A failure
+    /// is wrapped in `AppError::Context` naming the call that failed.
+    async fn get_portfolio_snapshot(&self, session: &IgSession) -> Result<PortfolioSnapshot, AppError>;
+
     /// Obtiene la actividad de la cuenta
     async fn get_activity(
         &self,
         session: &IgSession,
-        from: &str,
-        to: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<AccountActivity, AppError>;
+
+    /// Fetches the account activity for the whole range, following the v3
+    /// `metadata.paging.next` cursor and merging every page into one result
+    async fn get_all_activity(
+        &self,
+        session: &IgSession,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
     ) -> Result<AccountActivity, AppError>;
 
     /// Obtiene el historial de transacciones
     async fn get_transactions(
         &self,
         session: &IgSession,
-        from: &str,
-        to: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
         page_size: u32,
         page_number: u32,
     ) -> Result<TransactionHistory, AppError>;
@@ -48,12 +75,17 @@ pub trait AccountService: Send + Sync {
 pub struct AccountServiceImpl<T: IgHttpClient> {
     config: Arc<Config>,
     client: Arc<T>,
+    max_activity_pages: u32,
 }
 
 impl<T: IgHttpClient> AccountServiceImpl<T> {
     /// Crea una nueva instancia del servicio de cuenta
     pub fn new(config: Arc<Config>, client: Arc<T>) -> Self {
-        Self { config, client }
+        Self {
+            config,
+            client,
+            max_activity_pages: DEFAULT_MAX_ACTIVITY_PAGES,
+        }
     }
     
     pub fn get_config(&self) -> Arc<Config> {
@@ -63,6 +95,11 @@ impl<T: IgHttpClient> AccountServiceImpl<T> {
     pub fn set_config(&mut self, config: Arc<Config>) {
         self.config = config;
     }
+
+    /// Sets the maximum number of pages `get_all_activity` will follow
+    pub fn set_max_activity_pages(&mut self, max_pages: u32) {
+        self.max_activity_pages = max_pages;
+    }
 }
 
 #[async_trait]
@@ -97,6 +134,28 @@ impl<T: IgHttpClient + 'static> AccountService for AccountServiceImpl<T> {
         Ok(result)
     }
 
+    async fn get_position(&self, session: &IgSession, deal_id: &str) -> Result<Position, AppError> {
+        info!("Obteniendo posición {}", deal_id);
+
+        let path = format!("positions/{}", deal_id);
+        let result = self
+            .client
+            .request::<(), Position>(Method::GET, &path, session, None, "2")
+            .await?;
+
+        debug!("Posición obtenida: {}", result.position.deal_id);
+        Ok(result)
+    }
+
+    async fn get_positions_with_pnl(&self, session: &IgSession) -> Result<Positions, AppError> {
+        let mut result = self.get_positions(session).await?;
+        for position in result.positions.iter_mut() {
+            position.pnl = calculate_pnl(position);
+        }
+        debug!("P&L calculated, total: {}", result.total_pnl());
+        Ok(result)
+    }
+
     async fn get_working_orders(&self, session: &IgSession) -> Result<WorkingOrders, AppError> {
         info!("Obteniendo órdenes de trabajo");
 
@@ -112,13 +171,50 @@ impl<T: IgHttpClient + 'static> AccountService for AccountServiceImpl<T> {
         Ok(result)
     }
 
+    async fn get_portfolio_snapshot(&self, session: &IgSession) -> Result<PortfolioSnapshot, AppError> {
+        info!("Fetching portfolio snapshot");
+
+        let (positions, working_orders, accounts) = tokio::try_join!(
+            async {
+                self.get_positions(session)
+                    .await
+                    .map_err(|e| e.with_context("get_positions"))
+            },
+            async {
+                self.get_working_orders(session)
+                    .await
+                    .map_err(|e| e.with_context("get_working_orders"))
+            },
+            async {
+                self.get_accounts(session)
+                    .await
+                    .map_err(|e| e.with_context("get_accounts"))
+            },
+        )?;
+
+        debug!(
+            "Portfolio snapshot: {} positions, {} working orders, {} accounts",
+            positions.positions.len(),
+            working_orders.working_orders.len(),
+            accounts.accounts.len()
+        );
+        Ok(PortfolioSnapshot {
+            positions,
+            working_orders,
+            accounts,
+        })
+    }
+
     async fn get_activity(
         &self,
         session: &IgSession,
-        from: &str,
-        to: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
     ) -> Result<AccountActivity, AppError> {
-        let path = format!("history/activity?from={}&to={}", from, to);
+        let path = QueryBuilder::new("history/activity")
+            .param("from", format_ig_datetime(from))
+            .param("to", format_ig_datetime(to))
+            .build();
         info!("Obteniendo actividad de la cuenta");
 
         let result = self
@@ -133,18 +229,51 @@ impl<T: IgHttpClient + 'static> AccountService for AccountServiceImpl<T> {
         Ok(result)
     }
 
+    async fn get_all_activity(
+        &self,
+        session: &IgSession,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<AccountActivity, AppError> {
+        let path = QueryBuilder::new("history/activity")
+            .param("from", format_ig_datetime(from))
+            .param("to", format_ig_datetime(to))
+            .build();
+        info!("Obteniendo toda la actividad de la cuenta");
+
+        let activities = fetch_all_pages::<_, AccountActivity, _>(
+            self.client.as_ref(),
+            &path,
+            session,
+            "3",
+            self.max_activity_pages,
+        )
+        .await?;
+
+        debug!(
+            "Actividad de la cuenta obtenida: {} actividades",
+            activities.len()
+        );
+        Ok(AccountActivity {
+            activities,
+            metadata: None,
+        })
+    }
+
     async fn get_transactions(
         &self,
         session: &IgSession,
-        from: &str,
-        to: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
         page_size: u32,
         page_number: u32,
     ) -> Result<TransactionHistory, AppError> {
-        let path = format!(
-            "history/transactions?from={}&to={}&pageSize={}&pageNumber={}",
-            from, to, page_size, page_number
-        );
+        let path = QueryBuilder::new("history/transactions")
+            .param("from", format_ig_datetime(from))
+            .param("to", format_ig_datetime(to))
+            .param("pageSize", page_size)
+            .param("pageNumber", page_number)
+            .build();
         info!("Obteniendo historial de transacciones");
 
         let result = self
@@ -159,3 +288,165 @@ impl<T: IgHttpClient + 'static> AccountService for AccountServiceImpl<T> {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests_account_service {
+    use super::*;
+    use crate::utils::money::price;
+    use chrono::TimeZone;
+    use crate::transport::mock::{position_json, test_session, MockHttpClient};
+    use serde_json::json;
+
+    fn from() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 5, 1, 0, 0, 0).unwrap()
+    }
+
+    fn to() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 5, 13, 12, 30, 0).unwrap()
+    }
+
+    fn activity(deal_id: &str) -> serde_json::Value {
+        json!({
+            "date": "2025-05-13T10:00:00",
+            "dealId": deal_id,
+            "epic": "IX.D.DAX.IFMM.IP",
+            "period": "-",
+            "dealReference": format!("REF-{deal_id}"),
+            "activityType": "POSITION",
+            "status": "ACCEPTED",
+            "description": "Position opened",
+            "details": null
+        })
+    }
+
+    fn position(direction: &str, level: f64, size: f64, bid: f64, offer: f64) -> serde_json::Value {
+        let mut position = position_json("IX.D.DAX.IFMM.IP", direction, size);
+        position["position"]["level"] = json!(level);
+        position["market"]["bid"] = json!(bid);
+        position["market"]["offer"] = json!(offer);
+        position
+    }
+
+    #[tokio::test]
+    async fn test_get_positions_with_pnl() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({
+            "positions": [
+                position("BUY", 18000.0, 2.0, 18010.0, 18011.0),
+                position("SELL", 18050.0, 1.0, 18009.0, 18010.0)
+            ]
+        }));
+        let service = AccountServiceImpl::new(Arc::new(Config::new()), client);
+
+        let result = service.get_positions_with_pnl(&test_session()).await.unwrap();
+
+        let pnls: Vec<_> = result.positions.iter().map(|p| p.pnl).collect();
+        assert_eq!(pnls, vec![Some(price!(20.0)), Some(price!(40.0))]);
+        assert_eq!(result.total_pnl(), price!(60.0));
+    }
+
+    #[tokio::test]
+    async fn test_get_position_by_deal_id() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(position("BUY", 18000.0, 1.0, 18010.0, 18011.0));
+        client.push_error(AppError::NotFound);
+        let service = AccountServiceImpl::new(Arc::new(Config::new()), client.clone());
+
+        let result = service.get_position(&test_session(), "DIAAAA").await.unwrap();
+        assert_eq!(result.position.deal_id, "DIAAAA");
+        let closed = service.get_position(&test_session(), "DIBBBB").await;
+        assert!(matches!(closed, Err(AppError::NotFound)));
+
+        let requests = client.requests();
+        assert_eq!(requests[0].method, Method::GET);
+        assert_eq!(requests[0].path, "positions/DIAAAA");
+        assert_eq!(requests[0].version, "2");
+        assert_eq!(requests[1].path, "positions/DIBBBB");
+    }
+
+    #[tokio::test]
+    async fn test_get_all_activity_follows_next_cursor() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({
+            "activities": [activity("D1"), activity("D2")],
+            "metadata": {"paging": {"size": 2, "next": "/history/activity?from=a&to=b&page=2"}}
+        }));
+        client.push_json(json!({
+            "activities": [activity("D3")],
+            "metadata": {"paging": {"size": 1, "next": null}}
+        }));
+        let service = AccountServiceImpl::new(Arc::new(Config::new()), client.clone());
+
+        let result = service
+            .get_all_activity(&test_session(), from(), to())
+            .await
+            .unwrap();
+
+        let ids: Vec<_> = result.activities.iter().map(|a| a.deal_id.as_str()).collect();
+        assert_eq!(ids, vec!["D1", "D2", "D3"]);
+        let requests = client.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].path,
+            "history/activity?from=2025-05-01T00%3A00%3A00&to=2025-05-13T12%3A30%3A00"
+        );
+        assert_eq!(requests[1].path, "/history/activity?from=a&to=b&page=2");
+        assert_eq!(requests[1].version, "3");
+    }
+
+    #[tokio::test]
+    async fn test_get_all_activity_respects_page_cap() {
+        let client = Arc::new(MockHttpClient::new());
+        for i in 0..3 {
+            client.push_json(json!({
+                "activities": [activity(&format!("D{i}"))],
+                "metadata": {"paging": {"size": 1, "next": "/history/activity?more"}}
+            }));
+        }
+        let mut service = AccountServiceImpl::new(Arc::new(Config::new()), client.clone());
+        service.set_max_activity_pages(2);
+
+        let result = service
+            .get_all_activity(&test_session(), from(), to())
+            .await
+            .unwrap();
+
+        assert_eq!(result.activities.len(), 2);
+        assert_eq!(client.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_portfolio_snapshot_fetches_all_three() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({"positions": []}));
+        client.push_json(json!({"workingOrders": []}));
+        client.push_json(json!({"accounts": []}));
+        let service = AccountServiceImpl::new(Arc::new(Config::new()), client.clone());
+
+        let snapshot = service.get_portfolio_snapshot(&test_session()).await.unwrap();
+
+        assert!(snapshot.positions.positions.is_empty());
+        assert!(snapshot.working_orders.working_orders.is_empty());
+        assert!(snapshot.accounts.accounts.is_empty());
+        let mut paths: Vec<_> = client.requests().into_iter().map(|r| r.path).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["accounts", "positions", "workingorders"]);
+    }
+
+    #[tokio::test]
+    async fn test_portfolio_snapshot_names_failed_call() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({"positions": []}));
+        client.push_error(AppError::Unauthorized);
+        client.push_json(json!({"accounts": []}));
+        let service = AccountServiceImpl::new(Arc::new(Config::new()), client);
+
+        let error = service.get_portfolio_snapshot(&test_session()).await.unwrap_err();
+
+        assert_eq!(error.to_string(), "get_working_orders: unauthorized");
+        assert!(matches!(
+            error,
+            AppError::Context { source, .. } if matches!(*source, AppError::Unauthorized)
+        ));
+    }
+}
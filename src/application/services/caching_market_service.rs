@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tracing::debug;
+
+use crate::{
+    application::models::market::{
+        Epic, HistoricalPricesResponse, MarketDetails, MarketNavigation, MarketSearchResult,
+        Resolution,
+    },
+    application::services::market_service::MarketService,
+    constants::{DEFAULT_MARKET_DETAILS_CACHE_SIZE, DEFAULT_MARKET_DETAILS_TTL},
+    error::AppError,
+    session::interface::IgSession,
+};
+
+/// `MarketService` decorator that caches `get_market_details` per epic for a
+/// short TTL. Every other call goes straight to the wrapped service.
+///
+/// At most `max_entries` epics are kept; adding another evicts the least
+/// recently used one.
+pub struct CachingMarketService<S: MarketService> {
+    inner: S,
+    ttl: Duration,
+    max_entries: usize,
+    details: Mutex<DetailsCache>,
+}
+
+/// Cached details by epic, each with its fetch time and last use
+#[derive(Default)]
+struct DetailsCache {
+    entries: HashMap<String, CacheEntry>,
+    /// Incremented on every use, so a lower `used` is less recent
+    clock: u64,
+}
+
+struct CacheEntry {
+    fetched_at: Instant,
+    used: u64,
+    details: MarketDetails,
+}
+
+impl<S: MarketService> CachingMarketService<S> {
+    /// Wraps `inner` with the default TTL of 2 seconds
+    pub fn new(inner: S) -> Self {
+        Self::with_ttl(inner, DEFAULT_MARKET_DETAILS_TTL)
+    }
+
+    /// Wraps `inner`, keeping market details for `ttl`
+    pub fn with_ttl(inner: S, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            max_entries: DEFAULT_MARKET_DETAILS_CACHE_SIZE,
+            details: Mutex::new(DetailsCache::default()),
+        }
+    }
+
+    /// Keeps at most `max_entries` epics (at least 1) instead of the default 256
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// The wrapped service
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Drops the cached details for `epic`
+    pub fn invalidate(&self, epic: &str) {
+        self.details.lock().unwrap().entries.remove(epic);
+    }
+
+    /// Drops every cached entry
+    pub fn clear(&self) {
+        self.details.lock().unwrap().entries.clear();
+    }
+
+    fn cached(&self, epic: &str) -> Option<MarketDetails> {
+        let mut cache = self.details.lock().unwrap();
+        cache.clock += 1;
+        let now = cache.clock;
+        match cache.entries.get_mut(epic) {
+            Some(entry) if entry.fetched_at.elapsed() < self.ttl => {
+                entry.used = now;
+                Some(entry.details.clone())
+            }
+            Some(_) => {
+                cache.entries.remove(epic);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn store(&self, epic: String, details: MarketDetails) {
+        let mut cache = self.details.lock().unwrap();
+        cache.clock += 1;
+        let used = cache.clock;
+        if !cache.entries.contains_key(&epic) && cache.entries.len() >= self.max_entries {
+            let ttl = self.ttl;
+            cache.entries.retain(|_, entry| entry.fetched_at.elapsed() < ttl);
+            if cache.entries.len() >= self.max_entries
+                && let Some(oldest) = cache
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.used)
+                    .map(|(epic, _)| epic.clone())
+            {
+                debug!("Market details cache full, evicting: {}", oldest);
+                cache.entries.remove(&oldest);
+            }
+        }
+        let fetched_at = Instant::now();
+        cache.entries.insert(epic, CacheEntry { fetched_at, used, details });
+    }
+}
+
+#[async_trait]
+impl<S: MarketService> MarketService for CachingMarketService<S> {
+    async fn search_markets(&self, session: &IgSession, search_term: &str) -> Result<MarketSearchResult, AppError> {
+        self.inner.search_markets(session, search_term).await
+    }
+
+    async fn get_market_details(
+        &self,
+        session: &IgSession,
+        epic: impl Into<Epic> + Send,
+    ) -> Result<MarketDetails, AppError> {
+        let epic = epic.into();
+        if let Some(details) = self.cached(epic.as_str()) {
+            debug!("Market details cache hit for: {}", epic);
+            return Ok(details);
+        }
+
+        let details = self.inner.get_market_details(session, epic.clone()).await?;
+        self.store(epic.into(), details.clone());
+        Ok(details)
+    }
+
+    async fn get_historical_prices(
+        &self,
+        session: &IgSession,
+        epic: impl Into<Epic> + Send,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<HistoricalPricesResponse, AppError> {
+        self.inner
+            .get_historical_prices(session, epic, resolution, from, to)
+            .await
+    }
+
+    async fn get_historical_prices_by_points(
+        &self,
+        session: &IgSession,
+        epic: impl Into<Epic> + Send,
+        resolution: Resolution,
+        num_points: u32,
+    ) -> Result<HistoricalPricesResponse, AppError> {
+        self.inner
+            .get_historical_prices_by_points(session, epic, resolution, num_points)
+            .await
+    }
+
+    async fn get_market_navigation(
+        &self,
+        session: &IgSession,
+        node_id: Option<&str>,
+    ) -> Result<MarketNavigation, AppError> {
+        self.inner.get_market_navigation(session, node_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests_caching_market_service {
+    use super::*;
+    use crate::utils::money::price;
+    use crate::application::services::market_service::MarketServiceImpl;
+    use crate::config::Config;
+    use crate::transport::mock::{test_session, MockHttpClient};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    const EPIC: &str = "IX.D.DAX.IFMM.IP";
+
+    fn details(bid: f64) -> serde_json::Value {
+        json!({
+            "instrument": {
+                "epic": EPIC,
+                "name": "Germany 40",
+                "instrumentType": "INDICES",
+                "expiry": "-"
+            },
+            "snapshot": {
+                "marketStatus": "TRADEABLE",
+                "bid": bid,
+                "offer": bid + 1.0
+            }
+        })
+    }
+
+    fn service(
+        client: Arc<MockHttpClient>,
+        ttl: Duration,
+    ) -> CachingMarketService<MarketServiceImpl<MockHttpClient>> {
+        CachingMarketService::with_ttl(MarketServiceImpl::new(Arc::new(Config::new()), client), ttl)
+    }
+
+    #[tokio::test]
+    async fn test_details_cached_within_ttl() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(details(18000.0));
+        client.push_json(details(18010.0));
+        let service = service(client.clone(), Duration::from_millis(100));
+        let session = test_session();
+
+        let first = service.get_market_details(&session, EPIC).await.unwrap();
+        let second = service.get_market_details(&session, EPIC).await.unwrap();
+        assert_eq!(first.snapshot.bid, Some(price!(18000.0)));
+        assert_eq!(second.snapshot.bid, Some(price!(18000.0)));
+        assert_eq!(client.requests().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let third = service.get_market_details(&session, EPIC).await.unwrap();
+        assert_eq!(third.snapshot.bid, Some(price!(18010.0)));
+        assert_eq!(client.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_forces_refetch() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(details(18000.0));
+        client.push_json(details(18010.0));
+        let service = service(client.clone(), Duration::from_secs(60));
+        let session = test_session();
+
+        service.get_market_details(&session, EPIC).await.unwrap();
+        service.invalidate(EPIC);
+        let refreshed = service.get_market_details(&session, EPIC).await.unwrap();
+
+        assert_eq!(refreshed.snapshot.bid, Some(price!(18010.0)));
+        assert_eq!(client.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_epic_is_evicted() {
+        let client = Arc::new(MockHttpClient::new());
+        for _ in 0..4 {
+            client.push_json(details(18000.0));
+        }
+        let service = service(client.clone(), Duration::from_secs(60)).with_max_entries(2);
+        let session = test_session();
+
+        service.get_market_details(&session, "IX.D.DAX.IFMM.IP").await.unwrap();
+        service.get_market_details(&session, "IX.D.FTSE.IFMM.IP").await.unwrap();
+        service.get_market_details(&session, "IX.D.DAX.IFMM.IP").await.unwrap();
+        // Full: FTSE was used least recently and makes room
+        service.get_market_details(&session, "IX.D.DOW.IFMM.IP").await.unwrap();
+        service.get_market_details(&session, "IX.D.DAX.IFMM.IP").await.unwrap();
+        service.get_market_details(&session, "IX.D.FTSE.IFMM.IP").await.unwrap();
+
+        let paths: Vec<_> = client.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "markets/IX.D.DAX.IFMM.IP",
+                "markets/IX.D.FTSE.IFMM.IP",
+                "markets/IX.D.DOW.IFMM.IP",
+                "markets/IX.D.FTSE.IFMM.IP",
+            ]
+        );
+    }
+}
@@ -1,13 +1,21 @@
-use std::str::FromStr;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
 use async_trait::async_trait;
-use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
-use reqwest::{Client, StatusCode};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream};
+use reqwest::Method;
 use regex::Regex;
-use tracing::debug;
-use crate::application::models::transaction::{RawTransaction, Transaction};
+use tracing::{debug, info, warn};
+use crate::application::models::account::PageData;
+use crate::application::models::transaction::{hash_raw_json, Period, RawTransaction, Transaction};
 use crate::config::Config;
+use crate::constants::{DEFAULT_TX_PAGE_RETRIES, DEFAULT_TX_PAGE_RETRY_BACKOFF, TX_PAGE_SIZE};
 use crate::error::AppError;
 use crate::session::interface::IgSession;
+use crate::transport::http_client::{IgHttpClient, IgHttpClientImpl, RequestOptions};
+use crate::transport::query::QueryBuilder;
+use crate::utils::dates::{format_ig_datetime, parse_ig_datetime};
 
 #[async_trait]
 pub trait IgTxFetcher {
@@ -19,36 +27,309 @@ pub trait IgTxFetcher {
     ) -> Result<Vec<Transaction>, AppError>;
 }
 
-pub struct IgTxClient<'a> {
+/// Default pattern for option names such as `US 500 5200 PUT`
+pub const DEFAULT_INSTRUMENT_PATTERN: &str =
+    r"(?P<under>[\p{L}0-9 ]+?)\s+(?P<strike>\d+(?:\.\d+)?)\s+(?P<kind>PUT|CALL)";
+
+/// Maps the currency prefixes IG uses in transaction amounts to ISO codes.
+/// Unknown prefixes are returned upper-cased.
+fn currency_code(prefix: &str) -> String {
+    match prefix {
+        "E" | "€" => "EUR".to_string(),
+        "£" => "GBP".to_string(),
+        "$" | "US$" => "USD".to_string(),
+        "A$" => "AUD".to_string(),
+        "C$" => "CAD".to_string(),
+        "¥" => "JPY".to_string(),
+        "SF" => "CHF".to_string(),
+        other => other.to_uppercase(),
+    }
+}
+
+/// Splits an amount such as `£1,234.50` or `$-50.00` into its value and the
+/// currency of its prefix, if it has one. Unparseable amounts become 0.0.
+fn parse_pnl(raw: &str) -> (f64, Option<String>) {
+    let raw = raw.trim();
+    let (negative, raw) = match raw.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, raw),
+    };
+    let start = raw
+        .find(|c: char| c.is_ascii_digit() || c == '-' || c == '+' || c == '.')
+        .unwrap_or(raw.len());
+    let (prefix, amount) = raw.split_at(start);
+    let prefix = prefix.trim();
+
+    let value = amount.replace(',', "").parse::<f64>().unwrap_or(0.0);
+    let value = if negative { -value } else { value };
+    let currency = (!prefix.is_empty()).then(|| currency_code(prefix));
+    (value, currency)
+}
+
+/// Transactions fetched before pagination failed for good, and the error
+/// that stopped it
+#[derive(Debug)]
+pub struct PartialFetch {
+    /// Transactions of every page fetched successfully, in page order
+    pub transactions: Vec<Transaction>,
+    /// Error of the last attempt at the page that could not be fetched
+    pub error: AppError,
+}
+
+impl fmt::Display for PartialFetch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pagination stopped after {} transactions: {}",
+            self.transactions.len(),
+            self.error
+        )
+    }
+}
+
+pub struct IgTxClient<'a, T: IgHttpClient = IgHttpClientImpl> {
     cfg:   &'a Config,
-    http:  Client,
-    re:    Regex,
+    http:  Arc<T>,
+    patterns: Vec<Regex>,
+    page_retries: u32,
+    retry_backoff: Duration,
 }
 
-impl<'a> IgTxClient<'a> {
+impl<'a> IgTxClient<'a, IgHttpClientImpl> {
     pub fn new(cfg: &'a Config) -> Self {
-        let re = Regex::new(
-            r"(?P<under>[\p{L}0-9 ]+?)\s+(?P<strike>\d+(?:\.\d+)?)\s+(?P<kind>PUT|CALL)"
-        ).unwrap();
+        Self::with_instrument_regex(cfg, Vec::new())
+    }
+
+    /// Like [`new`](Self::new), with extra patterns for parsing option names.
+    ///
+    /// Patterns are tried in order before [`DEFAULT_INSTRUMENT_PATTERN`]; the
+    /// first match wins. Each should capture the named groups `under`,
+    /// `strike` and `kind` (`PUT` or `CALL`); missing groups become `None`.
+    pub fn with_instrument_regex(cfg: &'a Config, patterns: Vec<Regex>) -> Self {
+        let http = Arc::new(IgHttpClientImpl::new(Arc::new(cfg.clone())));
+        Self::with_http_client(cfg, http).with_instrument_patterns(patterns)
+    }
+}
 
+impl<'a, T: IgHttpClient> IgTxClient<'a, T> {
+    /// Builds the client on an existing HTTP client, so transaction requests
+    /// share its rate limiting, retries and metrics
+    pub fn with_http_client(cfg: &'a Config, http: Arc<T>) -> Self {
         Self {
             cfg,
-            http: Client::builder()
-                .user_agent("ig-rs/0.1")
-                .build()
-                .expect("reqwest"),
-            re,
+            http,
+            patterns: vec![Regex::new(DEFAULT_INSTRUMENT_PATTERN).unwrap()],
+            page_retries: DEFAULT_TX_PAGE_RETRIES,
+            retry_backoff: DEFAULT_TX_PAGE_RETRY_BACKOFF,
         }
     }
 
+    /// Adds patterns for parsing option names, tried before the ones already set
+    pub fn with_instrument_patterns(mut self, patterns: Vec<Regex>) -> Self {
+        self.patterns.splice(0..0, patterns);
+        self
+    }
+
+    /// Sets how often a failed page is retried during pagination, and the
+    /// delay before the first retry (doubled on every further attempt)
+    pub fn with_page_retries(mut self, retries: u32, initial_backoff: Duration) -> Self {
+        self.page_retries = retries;
+        self.retry_backoff = initial_backoff;
+        self
+    }
+
     #[allow(dead_code)]
     fn rest_url(&self, path: &str) -> String {
-        format!("{}/{}", self.cfg.rest_api.base_url.trim_end_matches('/'), path)
+        self.cfg.rest_api.endpoint(path)
+    }
+
+    /// Fetches a single page of transactions together with IG's paging
+    /// metadata, so callers can report progress or resume a backfill
+    pub async fn fetch_page(
+        &self,
+        sess: &IgSession,
+        from: DateTime<Utc>,
+        to:   DateTime<Utc>,
+        page: u32,
+        page_size: u32,
+    ) -> Result<(Vec<Transaction>, PageData), AppError> {
+        self.fetch_page_with_options(sess, from, to, page, page_size, &RequestOptions::default())
+            .await
+    }
+
+    async fn fetch_page_with_options(
+        &self,
+        sess: &IgSession,
+        from: DateTime<Utc>,
+        to:   DateTime<Utc>,
+        page: u32,
+        page_size: u32,
+        options: &RequestOptions,
+    ) -> Result<(Vec<Transaction>, PageData), AppError> {
+        let path = QueryBuilder::new("history/transactions")
+            .param("from", format_ig_datetime(from))
+            .param("to", format_ig_datetime(to))
+            .param("pageNumber", page)
+            .param("pageSize", page_size)
+            .build();
+        debug!("🔗 Fetching IG txs from: {}", path);
+
+        let json: serde_json::Value = self
+            .http
+            .request_with_options::<(), _>(Method::GET, &path, sess, None, "2", options)
+            .await?;
+        let raws: Vec<RawTransaction> = match &json["transactions"] {
+            serde_json::Value::Null => Vec::new(),
+            transactions => serde_json::from_value(transactions.clone())?,
+        };
+
+        let page_data: PageData = serde_json::from_value(json["metadata"]["pageData"].clone())
+            .unwrap_or(PageData {
+                page_number: page as i32,
+                page_size: page_size as i32,
+                total_pages: 1,
+            });
+        info!(
+            "Transactions page {}/{} ({} in total)",
+            page_data.page_number,
+            page_data.total_pages,
+            json["metadata"]["size"].as_u64().unwrap_or(raws.len() as u64)
+        );
+
+        Ok((raws.into_iter().map(|r| self.convert(r)).collect(), page_data))
+    }
+
+    /// Fetches the transactions dealt from `last_seen` until now, together
+    /// with the newest `deal_date` among them (or `last_seen` if there are
+    /// none) to persist as the next cursor.
+    ///
+    /// The range includes `last_seen` itself, so the newest transaction of the
+    /// previous run comes back again; storage deduplicates it by hash.
+    pub async fn fetch_since(
+        &self,
+        sess: &IgSession,
+        last_seen: DateTime<Utc>,
+    ) -> Result<(Vec<Transaction>, DateTime<Utc>), AppError> {
+        let txs = self.fetch_range(sess, last_seen, Utc::now()).await?;
+        let cursor = txs
+            .iter()
+            .map(|tx| tx.deal_date)
+            .max()
+            .map_or(last_seen, |newest| newest.max(last_seen));
+        debug!("Fetched {} transactions since {}, cursor now {}", txs.len(), last_seen, cursor);
+        Ok((txs, cursor))
+    }
+
+    /// Fetches every page of the range, retrying a failed page with backoff.
+    ///
+    /// If a page still fails after the configured retries, the transactions
+    /// of the pages before it are returned in the error.
+    pub async fn fetch_range_partial(
+        &self,
+        sess: &IgSession,
+        from: DateTime<Utc>,
+        to:   DateTime<Utc>,
+    ) -> Result<Vec<Transaction>, PartialFetch> {
+        let mut page = 1;
+        let mut out = Vec::new();
+
+        loop {
+            let (txs, page_data) = match self.fetch_page_with_retry(sess, from, to, page).await {
+                Ok(result) => result,
+                Err(error) => {
+                    return Err(PartialFetch {
+                        transactions: out,
+                        error,
+                    });
+                }
+            };
+
+            if txs.is_empty() { break; }
+
+            out.extend(txs);
+
+            if page >= page_data.total_pages.max(1) as u32 { break; }
+            page += 1;
+        }
+
+        Ok(out)
+    }
+
+    /// Streams the transactions of the range page by page, fetching the next
+    /// page only once the consumer has drained the current one. A page that
+    /// still fails after the configured retries is yielded as an error and
+    /// ends the stream.
+    pub fn fetch_range_stream<'s>(
+        &'s self,
+        sess: &'s IgSession,
+        from: DateTime<Utc>,
+        to:   DateTime<Utc>,
+    ) -> impl Stream<Item = Result<Transaction, AppError>> + 's {
+        struct State {
+            page: u32,
+            buffer: std::vec::IntoIter<Transaction>,
+            more: bool,
+        }
+
+        let start = State {
+            page: 1,
+            buffer: Vec::new().into_iter(),
+            more: true,
+        };
+        stream::unfold(start, move |mut state| async move {
+            loop {
+                if let Some(tx) = state.buffer.next() {
+                    return Some((Ok(tx), state));
+                }
+                if !state.more {
+                    return None;
+                }
+                match self.fetch_page_with_retry(sess, from, to, state.page).await {
+                    Ok((txs, page_data)) => {
+                        state.more = !txs.is_empty() && state.page < page_data.total_pages.max(1) as u32;
+                        state.page += 1;
+                        state.buffer = txs.into_iter();
+                    }
+                    Err(error) => {
+                        state.more = false;
+                        return Some((Err(error), state));
+                    }
+                }
+            }
+        })
+    }
+
+    async fn fetch_page_with_retry(
+        &self,
+        sess: &IgSession,
+        from: DateTime<Utc>,
+        to:   DateTime<Utc>,
+        page: u32,
+    ) -> Result<(Vec<Transaction>, PageData), AppError> {
+        // Retries happen here, so the HTTP client must not retry each attempt again
+        let options = RequestOptions::default().with_max_retries(0);
+        let mut backoff = self.retry_backoff;
+        let mut attempt = 0;
+        loop {
+            match self.fetch_page_with_options(sess, from, to, page, TX_PAGE_SIZE, &options).await {
+                Ok(result) => return Ok(result),
+                Err(error) if attempt < self.page_retries => {
+                    attempt += 1;
+                    warn!(
+                        "Transactions page {} failed ({}), retry {}/{} in {:?}",
+                        page, error, attempt, self.page_retries, backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(error) => return Err(error),
+            }
+        }
     }
 
     fn convert(&self, raw: RawTransaction) -> Transaction {
         // -------- regex -------------
-        let caps = self.re.captures(&raw.instrument_name);
+        let caps = self.patterns.iter().find_map(|re| re.captures(&raw.instrument_name));
 
         let (underlying, strike, option_type) = if let Some(c) = caps.as_ref() {
             let under  = c.name("under").map(|m| m.as_str().trim().to_uppercase());
@@ -60,86 +341,337 @@ impl<'a> IgTxClient<'a> {
             (None, None, None)
         };
         
-        let deal_date = NaiveDateTime::parse_from_str(&raw.date_utc, "%Y-%m-%dT%H:%M:%S")
-            .map(|naive| naive.and_utc())
-            .unwrap_or_else(|_| Utc::now());
+        let deal_date = parse_ig_datetime(&raw.date_utc).unwrap_or_else(|_| Utc::now());
 
-        let pnl_eur = raw.pnl_raw.trim_start_matches('E')
-            .parse::<f64>()
-            .unwrap_or(0.0);
+        let (pnl, currency) = parse_pnl(&raw.pnl_raw);
+        let currency = currency.unwrap_or_else(|| currency_code(&raw.currency));
 
-        let expiry = raw.period.split_once('-').and_then(|(mon, yy)| {
-            chrono::Month::from_str(mon).ok()
-                .and_then(|m| NaiveDate::from_ymd_opt(2000 + yy.parse::<i32>().ok()?, m.number_from_month(), 1))
-        });
+        let period = Period::parse(&raw.period);
 
-        let is_fee = raw.transaction_type == "WITH" && pnl_eur.abs() < 1.0;
+        let is_fee = raw.transaction_type == "WITH" && pnl.abs() < 1.0;
+        let raw_json = raw.to_string();
+        let raw_hash = hash_raw_json(&raw_json);
 
         Transaction {
             deal_date,
             underlying,
             strike,
             option_type,
-            expiry,
+            expiry: period.expiry(),
+            period,
             transaction_type: raw.transaction_type.clone(),
-            pnl_eur,
+            pnl,
+            currency,
             reference: raw.reference.clone(),
             is_fee,
-            raw_json: raw.to_string(),
+            raw_json,
+            raw_hash,
         }
     }
 }
 
 #[async_trait]
-impl IgTxFetcher for IgTxClient<'_> {
+impl<T: IgHttpClient> IgTxFetcher for IgTxClient<'_, T> {
     async fn fetch_range(
         &self,
         sess: &IgSession,
         from: DateTime<Utc>,
         to:   DateTime<Utc>,
     ) -> Result<Vec<Transaction>, AppError> {
+        self.fetch_range_partial(sess, from, to).await.map_err(|partial| {
+            warn!("Discarding {} fetched transactions: {}", partial.transactions.len(), partial.error);
+            partial.error
+        })
+    }
+}
 
-        let mut page = 1;
-        let mut out  = Vec::new();
+#[cfg(test)]
+mod tests_ig_tx_client {
+    use super::*;
+    use crate::transport::mock::{test_session, MockHttpClient, MockResponse, MockServer};
+    use reqwest::StatusCode;
+    use chrono::TimeZone;
+    use serde_json::json;
 
-        loop {
-            let url = format!(
-                "{}/history/transactions?from={}&to={}&pageNumber={}&pageSize=200",
-                self.cfg.rest_api.base_url,
-                from.format("%Y-%m-%dT%H:%M:%S"),
-                to  .format("%Y-%m-%dT%H:%M:%S"),
-                page
-            );
-            debug!("🔗 Fetching IG txs from URL: {}", url);
-
-            let resp = self.http
-                .get(&url)
-                .header("X-IG-API-KEY", &self.cfg.credentials.api_key)
-                .header("CST",             &sess.cst)
-                .header("X-SECURITY-TOKEN",&sess.token)
-                .header("Version","2")
-                .header("Accept","application/json; charset=UTF-8")
-                .send()
-                .await?;
-
-            if resp.status() != StatusCode::OK {
-                return Err(AppError::Unexpected(resp.status()));
+    fn raw(reference: &str) -> RawTransaction {
+        RawTransaction {
+            date: "13/05/25".to_string(),
+            date_utc: "2025-05-13T10:00:00".to_string(),
+            open_date_utc: "2025-05-12T09:00:00".to_string(),
+            instrument_name: "US 500 5200 PUT".to_string(),
+            period: "JUN-25".to_string(),
+            pnl_raw: "E12.50".to_string(),
+            transaction_type: "DEAL".to_string(),
+            reference: reference.to_string(),
+            open_level: "10".to_string(),
+            close_level: "12".to_string(),
+            size: "1".to_string(),
+            currency: "E".to_string(),
+            cash_transaction: false,
+        }
+    }
+
+    #[test]
+    fn test_convert_raw_hash_is_stable() {
+        let cfg = Config::new();
+        let client = IgTxClient::new(&cfg);
+
+        let a = client.convert(raw("REF1"));
+        let b = client.convert(raw("REF1"));
+        let c = client.convert(raw("REF2"));
+
+        assert_eq!(a.raw_hash, b.raw_hash);
+        assert_ne!(a.raw_hash, c.raw_hash);
+        assert_eq!(a.raw_hash.len(), 64);
+        assert_eq!(a.raw_hash, hash_raw_json(&a.raw_json));
+    }
+
+    /// One of two pages of transactions, as IG's JSON
+    fn page_json(references: &[&str], page_number: i32) -> serde_json::Value {
+        let transactions: Vec<_> = references
+            .iter()
+            .map(|r| serde_json::to_value(raw(r)).unwrap())
+            .collect();
+        json!({
+            "transactions": transactions,
+            "metadata": {
+                "size": 3,
+                "pageData": {"pageNumber": page_number, "pageSize": 2, "totalPages": 2}
             }
+        })
+    }
+
+    fn page(references: &[&str], page_number: i32) -> MockResponse {
+        MockResponse::json(200, page_json(references, page_number))
+    }
 
-            let json: serde_json::Value = resp.json().await?;
-            let raws: Vec<RawTransaction> =
-                serde_json::from_value(json["transactions"].clone()).unwrap_or_default();
+    #[tokio::test]
+    async fn test_fetch_page_returns_cursor() {
+        let server = MockServer::start(vec![page(&["REF1", "REF2"], 1), page(&["REF3"], 2)]);
+        let mut cfg = Config::new();
+        cfg.rest_api.base_url = server.url();
+        let client = IgTxClient::new(&cfg);
+        let from = Utc.with_ymd_and_hms(2025, 5, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2025, 5, 31, 0, 0, 0).unwrap();
 
-            if raws.is_empty() { break; }
+        let (txs, page_data) = client.fetch_page(&test_session(), from, to, 1, 2).await.unwrap();
+        assert_eq!(txs.len(), 2);
+        assert_eq!(page_data.page_number, 1);
+        assert_eq!(page_data.total_pages, 2);
 
-            out.extend(raws.into_iter().map(|r| self.convert(r)));
+        let (txs, page_data) = client.fetch_page(&test_session(), from, to, 2, 2).await.unwrap();
+        assert_eq!(txs[0].reference, "REF3");
+        assert_eq!(page_data.page_number, 2);
 
-            let meta = &json["metadata"]["pageData"];
-            let total_pages = meta["totalPages"].as_u64().unwrap_or(1);
-            if page >= total_pages { break; }
-            page += 1;
+        let paths: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/gateway/deal/history/transactions?from=2025-05-01T00%3A00%3A00&to=2025-05-31T00%3A00%3A00&pageNumber=1&pageSize=2",
+                "/gateway/deal/history/transactions?from=2025-05-01T00%3A00%3A00&to=2025-05-31T00%3A00%3A00&pageNumber=2&pageSize=2",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_range_stream_fetches_pages_lazily() {
+        use futures_util::StreamExt;
+
+        let server = MockServer::start(vec![page(&["REF1", "REF2"], 1), page(&["REF3"], 2)]);
+        let mut cfg = Config::new();
+        cfg.rest_api.base_url = server.url();
+        let client = IgTxClient::new(&cfg);
+        let from = Utc.with_ymd_and_hms(2025, 5, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2025, 5, 31, 0, 0, 0).unwrap();
+        let session = test_session();
+
+        let stream = client.fetch_range_stream(&session, from, to);
+        futures_util::pin_mut!(stream);
+
+        assert_eq!(stream.next().await.unwrap().unwrap().reference, "REF1");
+        assert_eq!(stream.next().await.unwrap().unwrap().reference, "REF2");
+        // The second page is only requested once the first is drained
+        assert_eq!(server.requests().len(), 1);
+
+        let mut count = 2;
+        while let Some(tx) = stream.next().await {
+            tx.unwrap();
+            count += 1;
         }
+        assert_eq!(count, 3);
+        assert_eq!(server.requests().len(), 2);
+    }
 
-        Ok(out)
+    #[tokio::test]
+    async fn test_fetch_since_starts_at_cursor() {
+        let mut later = raw("REF2");
+        later.date_utc = "2025-05-14T08:30:00".to_string();
+        let body = json!({
+            "transactions": [serde_json::to_value(raw("REF1")).unwrap(), serde_json::to_value(later).unwrap()],
+            "metadata": {"pageData": {"pageNumber": 1, "pageSize": 200, "totalPages": 1}}
+        });
+        let server = MockServer::start(vec![MockResponse::json(200, body)]);
+        let mut cfg = Config::new();
+        cfg.rest_api.base_url = server.url();
+        let client = IgTxClient::new(&cfg);
+        let last_seen = Utc.with_ymd_and_hms(2025, 5, 13, 9, 15, 0).unwrap();
+
+        let (txs, cursor) = client.fetch_since(&test_session(), last_seen).await.unwrap();
+
+        assert_eq!(txs.len(), 2);
+        assert_eq!(cursor, Utc.with_ymd_and_hms(2025, 5, 14, 8, 30, 0).unwrap());
+        let path = &server.requests()[0].path;
+        assert!(
+            path.starts_with("/gateway/deal/history/transactions?from=2025-05-13T09%3A15%3A00&to="),
+            "unexpected path {}",
+            path
+        );
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_fetch_range_retries_page_and_keeps_partial_result() {
+        let page = |references: &[&str], page_number: i32| {
+            let transactions: Vec<_> = references
+                .iter()
+                .map(|r| serde_json::to_value(raw(r)).unwrap())
+                .collect();
+            MockResponse::json(
+                200,
+                json!({
+                    "transactions": transactions,
+                    "metadata": {"pageData": {"pageNumber": page_number, "pageSize": 2, "totalPages": 3}}
+                }),
+            )
+        };
+        let failure = || MockResponse::new(500, "");
+        let server = MockServer::start(vec![
+            page(&["REF1", "REF2"], 1),
+            failure(),
+            page(&["REF3", "REF4"], 2),
+            failure(),
+            failure(),
+        ]);
+        let mut cfg = Config::new();
+        cfg.rest_api.base_url = server.url();
+        let client = IgTxClient::new(&cfg).with_page_retries(1, Duration::from_millis(1));
+        let from = Utc.with_ymd_and_hms(2025, 5, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2025, 5, 31, 0, 0, 0).unwrap();
+
+        let partial = client
+            .fetch_range_partial(&test_session(), from, to)
+            .await
+            .unwrap_err();
+
+        let references: Vec<_> = partial.transactions.iter().map(|t| t.reference.as_str()).collect();
+        assert_eq!(references, vec!["REF1", "REF2", "REF3", "REF4"]);
+        assert!(matches!(partial.error, AppError::Unexpected(status) if status == StatusCode::INTERNAL_SERVER_ERROR));
+        let pages: Vec<_> = server
+            .requests()
+            .into_iter()
+            .map(|r| r.path.split("pageNumber=").nth(1).unwrap()[..1].to_string())
+            .collect();
+        assert_eq!(pages, vec!["1", "2", "2", "3", "3"]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_range_uses_injected_http_client() {
+        let mock = Arc::new(MockHttpClient::new());
+        mock.push_json(page_json(&["REF1", "REF2"], 1));
+        mock.push_json(page_json(&["REF3"], 2));
+        let cfg = Config::new();
+        let client = IgTxClient::with_http_client(&cfg, mock.clone());
+        let from = Utc.with_ymd_and_hms(2025, 5, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2025, 5, 31, 0, 0, 0).unwrap();
+
+        let txs = client.fetch_range(&test_session(), from, to).await.unwrap();
+
+        let references: Vec<_> = txs.iter().map(|t| t.reference.as_str()).collect();
+        assert_eq!(references, vec!["REF1", "REF2", "REF3"]);
+        let requests: Vec<_> = mock
+            .requests()
+            .into_iter()
+            .map(|r| (r.method, r.path, r.version, r.max_retries))
+            .collect();
+        let path = |page: u32| {
+            format!(
+                "history/transactions?from=2025-05-01T00%3A00%3A00&to=2025-05-31T00%3A00%3A00&pageNumber={}&pageSize=200",
+                page
+            )
+        };
+        assert_eq!(
+            requests,
+            vec![
+                // Pages are retried as a whole, never by the HTTP client too
+                (Method::GET, path(1), "2".to_string(), Some(0)),
+                (Method::GET, path(2), "2".to_string(), Some(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_instrument_patterns() {
+        let cfg = Config::new();
+        let weekly = Regex::new(
+            r"^Weekly (?P<under>.+?) \(\w+\) (?P<strike>\d+(?:\.\d+)?) (?P<kind>PUT|CALL)$",
+        )
+        .unwrap();
+        let fx = Regex::new(r"^(?P<under>[A-Z]{3}/[A-Z]{3}) (?P<strike>\d+(?:\.\d+)?) (?P<kind>PUT|CALL)$")
+            .unwrap();
+        let convert = |client: &IgTxClient, name: &str| {
+            let mut tx = raw("REF1");
+            tx.instrument_name = name.to_string();
+            let tx = client.convert(tx);
+            (tx.underlying, tx.strike, tx.option_type)
+        };
+
+        // The default pattern cannot read either name correctly
+        let default = IgTxClient::new(&cfg);
+        assert_eq!(convert(&default, "Weekly Germany 40 (Wed) 18200 CALL").0, None);
+        assert_eq!(convert(&default, "EUR/USD 1.0850 PUT").0, Some("USD".to_string()));
+
+        let client = IgTxClient::with_instrument_regex(&cfg, vec![weekly, fx]);
+        assert_eq!(
+            convert(&client, "Weekly Germany 40 (Wed) 18200 CALL"),
+            (Some("GERMANY 40".to_string()), Some(18200.0), Some("CALL".to_string()))
+        );
+        assert_eq!(
+            convert(&client, "EUR/USD 1.0850 PUT"),
+            (Some("EUR/USD".to_string()), Some(1.085), Some("PUT".to_string()))
+        );
+        // Names the custom patterns do not match still fall back to the default
+        assert_eq!(
+            convert(&client, "US 500 5200 PUT"),
+            (Some("US 500".to_string()), Some(5200.0), Some("PUT".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_pnl() {
+        assert_eq!(parse_pnl("£1,234.50"), (1234.5, Some("GBP".to_string())));
+        assert_eq!(parse_pnl("$-50.00"), (-50.0, Some("USD".to_string())));
+        assert_eq!(parse_pnl("E99.99"), (99.99, Some("EUR".to_string())));
+        assert_eq!(parse_pnl("-E12.50"), (-12.5, Some("EUR".to_string())));
+        assert_eq!(parse_pnl("CHF 10"), (10.0, Some("CHF".to_string())));
+        assert_eq!(parse_pnl("7.25"), (7.25, None));
+        assert_eq!(parse_pnl("n/a").0, 0.0);
+    }
+
+    #[test]
+    fn test_convert_records_currency() {
+        let cfg = Config::new();
+        let client = IgTxClient::new(&cfg);
+
+        let mut gbp = raw("REF1");
+        gbp.pnl_raw = "£1,234.50".to_string();
+        let tx = client.convert(gbp);
+        assert_eq!(tx.pnl, 1234.5);
+        assert_eq!(tx.currency, "GBP");
+
+        // Without a prefix the transaction's own currency is used
+        let mut bare = raw("REF2");
+        bare.pnl_raw = "-3.10".to_string();
+        let tx = client.convert(bare);
+        assert_eq!(tx.pnl, -3.1);
+        assert_eq!(tx.currency, "EUR");
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use futures_util::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::{
+    application::models::market::{Epic, Instrument},
+    application::services::market_service::MarketService,
+    error::AppError,
+    session::interface::IgSession,
+};
+
+/// Static metadata kept for one epic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CatalogEntry {
+    instrument: Instrument,
+    /// From the market snapshot, which is otherwise not kept
+    scaling_factor: Option<i64>,
+}
+
+/// Instrument metadata (names, types, lot sizes, scaling factors) for a
+/// fixed watchlist of epics, fetched once instead of on every lookup.
+///
+/// The catalog can be saved to and loaded from a JSON file so a restart does
+/// not have to fetch it again; call [`refresh`](Self::refresh) to update it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstrumentCatalog {
+    epics: Vec<Epic>,
+    entries: HashMap<String, CatalogEntry>,
+}
+
+impl InstrumentCatalog {
+    /// Fetches the market details of every epic, concurrently
+    pub async fn build<S: MarketService>(
+        service: &S,
+        session: &IgSession,
+        epics: impl IntoIterator<Item = impl Into<Epic>>,
+    ) -> Result<Self, AppError> {
+        let mut catalog = Self {
+            epics: epics.into_iter().map(Into::into).collect(),
+            entries: HashMap::new(),
+        };
+        catalog.refresh(service, session).await?;
+        Ok(catalog)
+    }
+
+    /// Fetches the market details of the watchlist again. On error the
+    /// catalog keeps its previous contents.
+    pub async fn refresh<S: MarketService>(
+        &mut self,
+        service: &S,
+        session: &IgSession,
+    ) -> Result<(), AppError> {
+        info!("Loading instrument catalog for {} epics", self.epics.len());
+        let details = try_join_all(
+            self.epics
+                .iter()
+                .map(|epic| service.get_market_details(session, epic.clone())),
+        )
+        .await?;
+
+        self.entries = self
+            .epics
+            .iter()
+            .zip(details)
+            .map(|(epic, details)| {
+                let entry = CatalogEntry {
+                    instrument: details.instrument,
+                    scaling_factor: details.snapshot.scaling_factor,
+                };
+                (epic.as_str().to_string(), entry)
+            })
+            .collect();
+        debug!("Instrument catalog holds {} instruments", self.entries.len());
+        Ok(())
+    }
+
+    /// Epics of the watchlist, in the order given
+    pub fn epics(&self) -> &[Epic] {
+        &self.epics
+    }
+
+    /// Instrument of `epic`, if it is in the catalog
+    pub fn lookup(&self, epic: &str) -> Option<&Instrument> {
+        self.entries.get(epic).map(|entry| &entry.instrument)
+    }
+
+    /// Lot size of `epic`, if it is in the catalog and IG reports one
+    pub fn lot_size(&self, epic: &str) -> Option<f64> {
+        self.lookup(epic)?.lot_size
+    }
+
+    /// Scaling factor of `epic`'s prices, if it is in the catalog and IG reports one
+    pub fn scaling_factor(&self, epic: &str) -> Option<i64> {
+        self.entries.get(epic)?.scaling_factor
+    }
+
+    /// Writes the catalog to `path` as JSON
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AppError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Reads a catalog written by [`save`](Self::save)
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AppError> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests_instrument_catalog {
+    use super::*;
+    use crate::application::services::market_service::MarketServiceImpl;
+    use crate::config::Config;
+    use crate::transport::mock::{test_session, MockHttpClient};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn details(epic: &str, lot_size: f64, scaling_factor: i64) -> serde_json::Value {
+        json!({
+            "instrument": {
+                "epic": epic,
+                "name": epic,
+                "instrumentType": "INDICES",
+                "expiry": "-",
+                "lotSize": lot_size
+            },
+            "snapshot": {
+                "marketStatus": "TRADEABLE",
+                "scalingFactor": scaling_factor
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_catalog_from_two_epics() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(details("IX.D.DAX.IFMM.IP", 1.0, 1));
+        client.push_json(details("CS.D.GBPUSD.TODAY.IP", 10.0, 10000));
+        let service = MarketServiceImpl::new(Arc::new(Config::new()), client.clone());
+
+        let catalog = InstrumentCatalog::build(
+            &service,
+            &test_session(),
+            ["IX.D.DAX.IFMM.IP", "CS.D.GBPUSD.TODAY.IP"],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(catalog.lot_size("IX.D.DAX.IFMM.IP"), Some(1.0));
+        assert_eq!(catalog.lot_size("CS.D.GBPUSD.TODAY.IP"), Some(10.0));
+        assert_eq!(catalog.scaling_factor("CS.D.GBPUSD.TODAY.IP"), Some(10000));
+        assert_eq!(catalog.lookup("IX.D.DAX.IFMM.IP").unwrap().name, "IX.D.DAX.IFMM.IP");
+        assert!(catalog.lookup("IX.D.FTSE.IFMM.IP").is_none());
+        assert_eq!(client.requests().len(), 2);
+
+        let path = std::env::temp_dir().join(format!("ig_client_{}_catalog.json", std::process::id()));
+        catalog.save(&path).unwrap();
+        let loaded = InstrumentCatalog::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.epics(), catalog.epics());
+        assert_eq!(loaded.lot_size("CS.D.GBPUSD.TODAY.IP"), Some(10.0));
+    }
+}
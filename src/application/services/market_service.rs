@@ -1,16 +1,20 @@
 use std::sync::Arc;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use reqwest::Method;
 use tracing::{debug, info};
 
 use crate::{
     application::models::market::{
-        HistoricalPricesResponse, MarketDetails, MarketSearchResult,
+        Epic, HistoricalPricesResponse, MarketDetails, MarketNavigation, MarketSearchResult,
+        PriceAllowance, Resolution,
     },
     config::Config,
     error::AppError,
     session::interface::IgSession,
     transport::http_client::IgHttpClient,
+    transport::query::{encode_query_value, QueryBuilder},
+    utils::dates::format_ig_datetime,
 };
 
 /// Interfaz para el servicio de mercado
@@ -20,29 +24,74 @@ pub trait MarketService: Send + Sync {
     async fn search_markets(&self, session: &IgSession, search_term: &str) -> Result<MarketSearchResult, AppError>;
     
     /// Obtiene detalles de un mercado específico por su EPIC
-    async fn get_market_details(&self, session: &IgSession, epic: &str) -> Result<MarketDetails, AppError>;
+    async fn get_market_details(
+        &self,
+        session: &IgSession,
+        epic: impl Into<Epic> + Send,
+    ) -> Result<MarketDetails, AppError>;
     
-    /// Obtiene precios históricos para un mercado
+    /// Obtiene precios históricos para un mercado entre `from` y `to` (UTC)
     async fn get_historical_prices(
         &self,
         session: &IgSession,
-        epic: &str,
-        resolution: &str,
-        from: &str,
-        to: &str,
+        epic: impl Into<Epic> + Send,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
     ) -> Result<HistoricalPricesResponse, AppError>;
+
+    /// Fetches the last `num_points` bars for a market, without a date range.
+    ///
+    /// Every bar returned counts against IG's weekly historical-data quota;
+    /// check `allowance.remaining_allowance` on the response before issuing
+    /// further requests.
+    async fn get_historical_prices_by_points(
+        &self,
+        session: &IgSession,
+        epic: impl Into<Epic> + Send,
+        resolution: Resolution,
+        num_points: u32,
+    ) -> Result<HistoricalPricesResponse, AppError>;
+
+    /// Browses the market tree: the top-level nodes when `node_id` is `None`,
+    /// otherwise the child nodes and markets of that node
+    async fn get_market_navigation(
+        &self,
+        session: &IgSession,
+        node_id: Option<&str>,
+    ) -> Result<MarketNavigation, AppError>;
 }
 
+/// Callback invoked with the price allowance reported by every historical-price fetch
+pub type AllowanceHook = Arc<dyn Fn(&PriceAllowance) + Send + Sync>;
+
 /// Implementación del servicio de mercado
 pub struct MarketServiceImpl<T: IgHttpClient> {
     config: Arc<Config>,
     client: Arc<T>,
+    on_allowance: Option<AllowanceHook>,
 }
 
 impl<T: IgHttpClient> MarketServiceImpl<T> {
     /// Crea una nueva instancia del servicio de mercado
     pub fn new(config: Arc<Config>, client: Arc<T>) -> Self {
-        Self { config, client }
+        Self {
+            config,
+            client,
+            on_allowance: None,
+        }
+    }
+
+    /// Registers a hook called after each historical-price fetch, e.g. to log
+    /// or throttle when `remaining_allowance` runs low
+    pub fn set_allowance_hook(&mut self, hook: AllowanceHook) {
+        self.on_allowance = Some(hook);
+    }
+
+    fn report_allowance(&self, allowance: &PriceAllowance) {
+        if let Some(hook) = &self.on_allowance {
+            hook(allowance);
+        }
     }
     
     pub fn get_config(&self) -> &Config {
@@ -57,7 +106,9 @@ impl<T: IgHttpClient> MarketServiceImpl<T> {
 #[async_trait]
 impl<T: IgHttpClient + 'static> MarketService for MarketServiceImpl<T> {
     async fn search_markets(&self, session: &IgSession, search_term: &str) -> Result<MarketSearchResult, AppError> {
-        let path = format!("markets?searchTerm={}", search_term);
+        let path = QueryBuilder::new("markets")
+            .param("searchTerm", search_term)
+            .build();
         info!("Buscando mercados con término: {}", search_term);
         
         let result = self.client
@@ -74,7 +125,13 @@ impl<T: IgHttpClient + 'static> MarketService for MarketServiceImpl<T> {
         Ok(result)
     }
     
-    async fn get_market_details(&self, session: &IgSession, epic: &str) -> Result<MarketDetails, AppError> {
+    async fn get_market_details(
+        &self,
+        session: &IgSession,
+        epic: impl Into<Epic> + Send,
+    ) -> Result<MarketDetails, AppError> {
+        let epic = epic.into();
+        epic.validate()?;
         let path = format!("markets/{}", epic);
         info!("Obteniendo detalles del mercado: {}", epic);
         
@@ -95,15 +152,17 @@ impl<T: IgHttpClient + 'static> MarketService for MarketServiceImpl<T> {
     async fn get_historical_prices(
         &self,
         session: &IgSession,
-        epic: &str,
-        resolution: &str,
-        from: &str,
-        to: &str,
+        epic: impl Into<Epic> + Send,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
     ) -> Result<HistoricalPricesResponse, AppError> {
-        let path = format!(
-            "prices/{}/{}?from={}&to={}",
-            epic, resolution, from, to
-        );
+        let epic = epic.into();
+        epic.validate()?;
+        let path = QueryBuilder::new(&format!("prices/{}/{}", epic, resolution.as_str()))
+            .param("from", format_ig_datetime(from))
+            .param("to", format_ig_datetime(to))
+            .build();
         info!("Obteniendo precios históricos para: {}", epic);
         
         let result = self.client
@@ -116,8 +175,193 @@ impl<T: IgHttpClient + 'static> MarketService for MarketServiceImpl<T> {
             )
             .await?;
         
+        self.report_allowance(&result.allowance);
         debug!("Precios históricos obtenidos para: {}", epic);
         Ok(result)
     }
+
+    async fn get_historical_prices_by_points(
+        &self,
+        session: &IgSession,
+        epic: impl Into<Epic> + Send,
+        resolution: Resolution,
+        num_points: u32,
+    ) -> Result<HistoricalPricesResponse, AppError> {
+        let epic = epic.into();
+        epic.validate()?;
+        let path = format!("prices/{}/{}/{}", epic, resolution.as_str(), num_points);
+        info!("Fetching last {} {} prices for: {}", num_points, resolution, epic);
+
+        let result = self.client
+            .request::<(), HistoricalPricesResponse>(
+                Method::GET,
+                &path,
+                session,
+                None,
+                "2",
+            )
+            .await?;
+
+        self.report_allowance(&result.allowance);
+        debug!(
+            "Historical prices fetched for {}, remaining allowance: {}",
+            epic, result.allowance.remaining_allowance
+        );
+        Ok(result)
+    }
+
+    async fn get_market_navigation(
+        &self,
+        session: &IgSession,
+        node_id: Option<&str>,
+    ) -> Result<MarketNavigation, AppError> {
+        let path = match node_id {
+            Some(id) => format!("marketnavigation/{}", encode_query_value(id)),
+            None => "marketnavigation".to_string(),
+        };
+        info!("Fetching market navigation for node: {}", node_id.unwrap_or("root"));
+
+        let result = self.client
+            .request::<(), MarketNavigation>(Method::GET, &path, session, None, "1")
+            .await?;
+
+        debug!(
+            "Market navigation fetched: {} nodes, {} markets",
+            result.nodes.len(),
+            result.markets.len()
+        );
+        Ok(result)
+    }
 }
 
+#[cfg(test)]
+mod tests_market_service {
+    use super::*;
+    use crate::transport::mock::{test_session, MockHttpClient};
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    use std::sync::Mutex;
+
+    fn prices_response() -> serde_json::Value {
+        json!({
+            "prices": [],
+            "instrumentType": "INDICES",
+            "allowance": {
+                "remainingAllowance": 9900,
+                "totalAllowance": 10000,
+                "allowanceExpiry": 604800
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_malformed_epic_rejected_before_request() {
+        let client = Arc::new(MockHttpClient::new());
+        let service = MarketServiceImpl::new(Arc::new(Config::new()), client.clone());
+
+        let result = service.get_market_details(&test_session(), "IX.D.DAX IFMM").await;
+
+        assert!(matches!(result, Err(AppError::SerializationError(_))));
+        assert!(client.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_historical_prices_by_points_path() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(prices_response());
+        let service = MarketServiceImpl::new(Arc::new(Config::new()), client.clone());
+
+        let result = service
+            .get_historical_prices_by_points(&test_session(), "IX.D.DAX.IFMM.IP", Resolution::Minute5, 100)
+            .await
+            .unwrap();
+
+        assert_eq!(result.allowance.remaining_allowance, 9900);
+        let requests = client.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, Method::GET);
+        assert_eq!(requests[0].path, "prices/IX.D.DAX.IFMM.IP/MINUTE_5/100");
+        assert_eq!(requests[0].version, "2");
+    }
+
+    #[tokio::test]
+    async fn test_market_navigation_root_and_leaf() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({
+            "nodes": [
+                {"id": "97601", "name": "Indices"},
+                {"id": "195235", "name": "Forex"}
+            ],
+            "markets": null
+        }));
+        client.push_json(json!({
+            "nodes": null,
+            "markets": [{
+                "epic": "IX.D.DAX.IFMM.IP",
+                "instrumentName": "Germany 40",
+                "instrumentType": "INDICES",
+                "expiry": "-",
+                "highLimitPrice": null,
+                "lowLimitPrice": null,
+                "marketStatus": "TRADEABLE",
+                "netChange": 12.5,
+                "percentageChange": 0.07,
+                "updateTime": "12:00:00",
+                "bid": 18000.5,
+                "offer": 18001.5,
+                "streamingPricesAvailable": true
+            }]
+        }));
+        let service = MarketServiceImpl::new(Arc::new(Config::new()), client.clone());
+
+        let root = service.get_market_navigation(&test_session(), None).await.unwrap();
+        let names: Vec<_> = root.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["Indices", "Forex"]);
+        assert!(root.markets.is_empty());
+
+        let leaf = service
+            .get_market_navigation(&test_session(), Some("97601"))
+            .await
+            .unwrap();
+        assert!(leaf.nodes.is_empty());
+        assert_eq!(leaf.markets[0].epic, "IX.D.DAX.IFMM.IP");
+        assert_eq!(leaf.markets[0].bid, Some(18000.5));
+
+        let requests = client.requests();
+        assert_eq!(requests[0].path, "marketnavigation");
+        assert_eq!(requests[1].path, "marketnavigation/97601");
+        assert_eq!(requests[1].version, "1");
+    }
+
+    #[tokio::test]
+    async fn test_allowance_hook_fires() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(prices_response());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut service = MarketServiceImpl::new(Arc::new(Config::new()), client.clone());
+        let sink = seen.clone();
+        service.set_allowance_hook(Arc::new(move |a: &PriceAllowance| {
+            sink.lock()
+                .unwrap()
+                .push((a.remaining_allowance, a.total_allowance, a.allowance_expiry));
+        }));
+
+        service
+            .get_historical_prices(
+                &test_session(),
+                "IX.D.DAX.IFMM.IP",
+                Resolution::Hour,
+                Utc.with_ymd_and_hms(2025, 5, 1, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2025, 5, 2, 0, 0, 0).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(*seen.lock().unwrap(), vec![(9900, 10000, 604800)]);
+        assert_eq!(
+            client.requests()[0].path,
+            "prices/IX.D.DAX.IFMM.IP/HOUR?from=2025-05-01T00%3A00%3A00&to=2025-05-02T00%3A00%3A00"
+        );
+    }
+}
@@ -1,4 +1,9 @@
 pub mod ig_tx_client;
 pub mod market_service;
+pub mod caching_market_service;
+pub mod instrument_catalog;
 pub mod order_service;
-pub mod account_service;
\ No newline at end of file
+pub mod account_service;
+pub mod operations_service;
+pub mod paging;
+pub mod session_service;
\ No newline at end of file
@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use reqwest::Method;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+use crate::{
+    application::models::operations::ApplicationDetails,
+    config::Config,
+    error::AppError,
+    session::interface::IgSession,
+    transport::http_client::IgHttpClient,
+};
+
+/// Interface for the `/operations` endpoints
+#[async_trait]
+pub trait OperationsService: Send + Sync {
+    /// Returns the details and request allowances of the configured API key,
+    /// lowering the client's rate limits to the account allowances if they
+    /// are stricter. Fails with `AppError::NotFound` if IG lists no application.
+    async fn get_application_details(
+        &self,
+        session: &IgSession,
+    ) -> Result<ApplicationDetails, AppError>;
+}
+
+/// Implementation of the operations service
+pub struct OperationsServiceImpl<T: IgHttpClient> {
+    config: Arc<Config>,
+    client: Arc<T>,
+}
+
+impl<T: IgHttpClient> OperationsServiceImpl<T> {
+    /// Creates a new operations service
+    pub fn new(config: Arc<Config>, client: Arc<T>) -> Self {
+        Self { config, client }
+    }
+
+    pub fn get_config(&self) -> Arc<Config> {
+        self.config.clone()
+    }
+
+    pub fn set_config(&mut self, config: Arc<Config>) {
+        self.config = config;
+    }
+}
+
+#[async_trait]
+impl<T: IgHttpClient + 'static> OperationsService for OperationsServiceImpl<T> {
+    async fn get_application_details(
+        &self,
+        session: &IgSession,
+    ) -> Result<ApplicationDetails, AppError> {
+        info!("Getting application details");
+
+        let applications = self
+            .client
+            .request::<(), Vec<ApplicationDetails>>(
+                Method::GET,
+                "operations/application",
+                session,
+                None,
+                "1",
+            )
+            .await?;
+
+        // IG lists every application of the client; prefer the one in use
+        let api_key = &self.config.credentials.api_key;
+        let mut applications = applications.into_iter();
+        let first = applications.next().ok_or(AppError::NotFound)?;
+        let details = if first.api_key == *api_key {
+            first
+        } else {
+            applications.find(|app| app.api_key == *api_key).unwrap_or(first)
+        };
+
+        // A missing allowance is passed as 0, which leaves that limit as configured
+        self.client.apply_rate_allowance(
+            details.allowance_account_trading.unwrap_or(0),
+            details.allowance_account_overall.unwrap_or(0),
+        );
+        debug!(
+            "Application {} allows {:?} trading / {:?} overall requests per minute",
+            details.api_key, details.allowance_account_trading, details.allowance_account_overall
+        );
+        Ok(details)
+    }
+}
+
+#[cfg(test)]
+mod tests_operations_service {
+    use super::*;
+    use crate::transport::mock::{test_session, MockHttpClient};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_get_application_details_picks_configured_key() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!([
+            {"apiKey": "other", "allowanceAccountOverall": 60},
+            {"apiKey": "mine", "allowanceAccountOverall": 30, "allowanceAccountTrading": 100}
+        ]));
+        let mut config = Config::new();
+        config.credentials.api_key = "mine".to_string();
+        let service = OperationsServiceImpl::new(Arc::new(config), client.clone());
+
+        let details = service.get_application_details(&test_session()).await.unwrap();
+
+        assert_eq!(details.api_key, "mine");
+        assert_eq!(details.allowance_account_overall, Some(30));
+        let requests = client.requests();
+        assert_eq!(requests[0].method, Method::GET);
+        assert_eq!(requests[0].path, "operations/application");
+        assert_eq!(requests[0].version, "1");
+    }
+
+    #[tokio::test]
+    async fn test_get_application_details_not_found() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!([]));
+        let service = OperationsServiceImpl::new(Arc::new(Config::new()), client);
+
+        let result = service.get_application_details(&test_session()).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
@@ -1,23 +1,44 @@
 use std::sync::Arc;
+use std::time::Duration;
 use async_trait::async_trait;
 use reqwest::Method;
-use tracing::{debug, info};
+use tokio::time::Instant;
+use tracing::{debug, info, instrument, warn, Span};
 
 use crate::{
+    application::models::account::{Position, Positions},
     application::models::order::{
         ClosePositionRequest, ClosePositionResponse, CreateOrderRequest, CreateOrderResponse,
-        OrderConfirmation, UpdatePositionRequest,
+        CreateWorkingOrderRequest, OrderConfirmation, UpdatePositionRequest,
+        UpdateWorkingOrderRequest, WorkingOrderResponse,
     },
     config::Config,
     error::AppError,
     session::interface::IgSession,
-    transport::http_client::IgHttpClient,
+    transport::http_client::{IgHttpClient, RequestOptions},
+    utils::dealref::generate_deal_reference,
+    utils::retry::{retry_async, RetryPolicy},
+    utils::money::Price,
 };
 
+/// First delay between confirmation polls; doubled after every miss
+const CONFIRMATION_POLL_INITIAL_DELAY: Duration = Duration::from_millis(100);
+/// Upper bound for the delay between confirmation polls
+const CONFIRMATION_POLL_MAX_DELAY: Duration = Duration::from_secs(1);
+/// Prefix of the synthetic deal references returned in dry-run mode
+const DRY_RUN_PREFIX: &str = "DRY-RUN";
+/// Prefix of the deal references generated for orders that carry none
+const DEAL_REFERENCE_PREFIX: &str = "IGC";
+
 /// Interfaz para el servicio de órdenes
 #[async_trait]
 pub trait OrderService: Send + Sync {
-    /// Crea una nueva orden
+    /// Crea una nueva orden.
+    ///
+    /// IG deduplicates orders on their deal reference, so one is generated
+    /// when the order has none, and every retry of the POST sends the same
+    /// body. If the call still fails, the error names the reference so the
+    /// caller can check `get_order_confirmation` before submitting again.
     async fn create_order(
         &self,
         session: &IgSession,
@@ -30,6 +51,15 @@ pub trait OrderService: Send + Sync {
         session: &IgSession,
         deal_reference: &str,
     ) -> Result<OrderConfirmation, AppError>;
+
+    /// Creates an order and polls `confirms/{dealReference}` until IG
+    /// publishes the confirmation or `timeout` elapses
+    async fn create_order_and_confirm(
+        &self,
+        session: &IgSession,
+        order: &CreateOrderRequest,
+        timeout: Duration,
+    ) -> Result<OrderConfirmation, AppError>;
     
     /// Actualiza una posición existente
     async fn update_position(
@@ -38,6 +68,24 @@ pub trait OrderService: Send + Sync {
         deal_id: &str,
         update: &UpdatePositionRequest,
     ) -> Result<(), AppError>;
+
+    /// Moves the stop of a position to `stop_level`, keeping its current
+    /// limit. A trailing stop is replaced by the fixed one.
+    async fn set_stop_loss(
+        &self,
+        session: &IgSession,
+        deal_id: &str,
+        stop_level: Price,
+    ) -> Result<(), AppError>;
+
+    /// Moves the limit of a position to `limit_level`, keeping its current
+    /// stop or trailing stop
+    async fn set_take_profit(
+        &self,
+        session: &IgSession,
+        deal_id: &str,
+        limit_level: Price,
+    ) -> Result<(), AppError>;
     
     /// Cierra una posición existente
     async fn close_position(
@@ -45,20 +93,90 @@ pub trait OrderService: Send + Sync {
         session: &IgSession,
         close_request: &ClosePositionRequest,
     ) -> Result<ClosePositionResponse, AppError>;
+
+    /// Closes every open position at market, one request per position.
+    /// Returns each deal id with the outcome of its close, so partial
+    /// failures are visible; only failing to list the positions is an error.
+    async fn close_all_positions(
+        &self,
+        session: &IgSession,
+    ) -> Result<Vec<(String, Result<ClosePositionResponse, AppError>)>, AppError>;
+
+    /// Creates a working order
+    async fn create_working_order(
+        &self,
+        session: &IgSession,
+        order: &CreateWorkingOrderRequest,
+    ) -> Result<WorkingOrderResponse, AppError>;
+
+    /// Amends an existing working order
+    async fn update_working_order(
+        &self,
+        session: &IgSession,
+        deal_id: &str,
+        update: &UpdateWorkingOrderRequest,
+    ) -> Result<WorkingOrderResponse, AppError>;
+
+    /// Deletes an existing working order
+    async fn delete_working_order(
+        &self,
+        session: &IgSession,
+        deal_id: &str,
+    ) -> Result<WorkingOrderResponse, AppError>;
 }
 
 /// Implementación del servicio de órdenes
 pub struct OrderServiceImpl<T: IgHttpClient> {
     config: Arc<Config>,
     client: Arc<T>,
+    dry_run: bool,
+    retry_policy: RetryPolicy,
 }
 
 impl<T: IgHttpClient> OrderServiceImpl<T> {
     /// Crea una nueva instancia del servicio de órdenes
     pub fn new(config: Arc<Config>, client: Arc<T>) -> Self {
-        Self { config, client }
+        Self {
+            config,
+            client,
+            dry_run: false,
+            retry_policy: RetryPolicy::new(1, Duration::ZERO),
+        }
+    }
+
+    /// Retries failed `create_order` calls under `policy`; by default an
+    /// order is sent once
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// In dry-run mode `create_order` validates and logs the order without
+    /// sending it, returning a synthetic `DRY-RUN-...` deal reference, and
+    /// confirmations for those references are answered locally as accepted
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    fn is_dry_run_reference(&self, deal_reference: &str) -> bool {
+        self.dry_run && deal_reference.starts_with(DRY_RUN_PREFIX)
     }
     
+    /// Fetches the position so an update can carry its unchanged levels
+    async fn current_levels(
+        &self,
+        session: &IgSession,
+        deal_id: &str,
+    ) -> Result<UpdatePositionRequest, AppError> {
+        let path = format!("positions/{}", deal_id);
+        let position = self
+            .client
+            .request::<(), Position>(Method::GET, &path, session, None, "2")
+            .await?;
+        Ok(UpdatePositionRequest::preserving(&position))
+    }
+
     pub fn get_config(&self) -> Arc<Config> {
         self.config.clone()
     }
@@ -70,27 +188,49 @@ impl<T: IgHttpClient> OrderServiceImpl<T> {
 
 #[async_trait]
 impl<T: IgHttpClient + 'static> OrderService for OrderServiceImpl<T> {
+    #[instrument(skip_all, fields(epic = %order.epic, deal_reference = tracing::field::Empty))]
     async fn create_order(
         &self,
         session: &IgSession,
         order: &CreateOrderRequest,
     ) -> Result<CreateOrderResponse, AppError> {
         info!("Creando orden para: {}", order.epic);
+        order.validate()?;
+        let mut order = order.clone();
+        // A dry run always gets a `DRY-RUN-...` reference so its confirmation is answered locally
+        if self.dry_run {
+            order.deal_reference = Some(generate_deal_reference(DRY_RUN_PREFIX));
+        }
+        let deal_reference = order
+            .deal_reference
+            .get_or_insert_with(|| generate_deal_reference(DEAL_REFERENCE_PREFIX))
+            .clone();
+
+        if self.dry_run {
+            let body = serde_json::to_string(&order)?;
+            Span::current().record("deal_reference", deal_reference.as_str());
+            info!("Dry run, not sending order {}: {}", deal_reference, body);
+            return Ok(CreateOrderResponse { deal_reference });
+        }
         
-        let result = self.client
-            .request::<CreateOrderRequest, CreateOrderResponse>(
+        let result = retry_async(self.retry_policy, || {
+            self.client.request::<CreateOrderRequest, CreateOrderResponse>(
                 Method::POST,
                 "positions/otc",
                 session,
-                Some(order),
+                Some(&order),
                 "2",
             )
-            .await?;
+        })
+        .await
+        .map_err(|e| e.with_context(format!("create_order {}", deal_reference)))?;
         
+        Span::current().record("deal_reference", result.deal_reference.as_str());
         debug!("Orden creada con referencia: {}", result.deal_reference);
         Ok(result)
     }
     
+    #[instrument(skip(self, session), fields(epic = tracing::field::Empty, deal_id = tracing::field::Empty))]
     async fn get_order_confirmation(
         &self,
         session: &IgSession,
@@ -98,6 +238,11 @@ impl<T: IgHttpClient + 'static> OrderService for OrderServiceImpl<T> {
     ) -> Result<OrderConfirmation, AppError> {
         let path = format!("confirms/{}", deal_reference);
         info!("Obteniendo confirmación para la orden: {}", deal_reference);
+
+        if self.is_dry_run_reference(deal_reference) {
+            debug!("Dry run, returning canned confirmation for {}", deal_reference);
+            return Ok(OrderConfirmation::dry_run(deal_reference));
+        }
         
         let result = self.client
             .request::<(), OrderConfirmation>(
@@ -109,10 +254,44 @@ impl<T: IgHttpClient + 'static> OrderService for OrderServiceImpl<T> {
             )
             .await?;
         
+        let span = Span::current();
+        if let Some(epic) = &result.epic {
+            span.record("epic", epic.as_str());
+        }
+        if let Some(deal_id) = &result.deal_id {
+            span.record("deal_id", deal_id.as_str());
+        }
         debug!("Confirmación obtenida para la orden: {}", deal_reference);
         Ok(result)
     }
+
+    async fn create_order_and_confirm(
+        &self,
+        session: &IgSession,
+        order: &CreateOrderRequest,
+        timeout: Duration,
+    ) -> Result<OrderConfirmation, AppError> {
+        let created = self.create_order(session, order).await?;
+        let deadline = Instant::now() + timeout;
+        let mut delay = CONFIRMATION_POLL_INITIAL_DELAY;
+
+        loop {
+            match self.get_order_confirmation(session, &created.deal_reference).await {
+                // IG answers 404 until the deal has been processed
+                Err(AppError::NotFound) if Instant::now() + delay < deadline => {
+                    debug!(
+                        "Confirmation for {} not available yet, retrying in {:?}",
+                        created.deal_reference, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(CONFIRMATION_POLL_MAX_DELAY);
+                }
+                result => return result,
+            }
+        }
+    }
     
+    #[instrument(skip(self, session, update))]
     async fn update_position(
         &self,
         session: &IgSession,
@@ -121,6 +300,7 @@ impl<T: IgHttpClient + 'static> OrderService for OrderServiceImpl<T> {
     ) -> Result<(), AppError> {
         let path = format!("positions/otc/{}", deal_id);
         info!("Actualizando posición: {}", deal_id);
+        update.validate()?;
         
         self.client
             .request::<UpdatePositionRequest, ()>(
@@ -135,7 +315,38 @@ impl<T: IgHttpClient + 'static> OrderService for OrderServiceImpl<T> {
         debug!("Posición actualizada: {}", deal_id);
         Ok(())
     }
+
+    async fn set_stop_loss(
+        &self,
+        session: &IgSession,
+        deal_id: &str,
+        stop_level: Price,
+    ) -> Result<(), AppError> {
+        info!("Setting stop of {} to {}", deal_id, stop_level);
+        let mut update = self.current_levels(session, deal_id).await?;
+        update.stop_level = Some(stop_level);
+        update.trailing_stop = update.trailing_stop.map(|_| false);
+        update.trailing_stop_distance = None;
+        update.trailing_stop_increment = None;
+        self.update_position(session, deal_id, &update).await
+    }
+
+    async fn set_take_profit(
+        &self,
+        session: &IgSession,
+        deal_id: &str,
+        limit_level: Price,
+    ) -> Result<(), AppError> {
+        info!("Setting limit of {} to {}", deal_id, limit_level);
+        let mut update = self.current_levels(session, deal_id).await?;
+        update.limit_level = Some(limit_level);
+        self.update_position(session, deal_id, &update).await
+    }
     
+    #[instrument(
+        skip_all,
+        fields(deal_id = %close_request.deal_id, deal_reference = tracing::field::Empty)
+    )]
     async fn close_position(
         &self,
         session: &IgSession,
@@ -143,17 +354,612 @@ impl<T: IgHttpClient + 'static> OrderService for OrderServiceImpl<T> {
     ) -> Result<ClosePositionResponse, AppError> {
         info!("Cerrando posición: {}", close_request.deal_id);
         
+        // IG closes OTC positions with a POST carrying `_method: DELETE`;
+        // without it the request is read as a new deal
+        let options = RequestOptions::default().with_method_override(Method::DELETE);
         let result = self.client
-            .request::<ClosePositionRequest, ClosePositionResponse>(
+            .request_with_options::<ClosePositionRequest, ClosePositionResponse>(
                 Method::POST,
                 "positions/otc",
                 session,
                 Some(close_request),
                 "1",
+                &options,
             )
             .await?;
         
+        Span::current().record("deal_reference", result.deal_reference.as_str());
         debug!("Posición cerrada con referencia: {}", result.deal_reference);
         Ok(result)
     }
+
+    async fn close_all_positions(
+        &self,
+        session: &IgSession,
+    ) -> Result<Vec<(String, Result<ClosePositionResponse, AppError>)>, AppError> {
+        info!("Closing all open positions");
+
+        let open = self
+            .client
+            .request::<(), Positions>(Method::GET, "positions", session, None, "2")
+            .await?;
+
+        // Closes go through the client one at a time, so the trading rate limiter spaces them
+        let mut results = Vec::with_capacity(open.positions.len());
+        for position in open.positions {
+            let request = ClosePositionRequest::close(&position);
+            let result = self.close_position(session, &request).await;
+            if let Err(e) = &result {
+                warn!("Failed to close position {}: {}", request.deal_id, e);
+            }
+            results.push((request.deal_id, result));
+        }
+
+        debug!(
+            "Closed {} of {} positions",
+            results.iter().filter(|(_, r)| r.is_ok()).count(),
+            results.len()
+        );
+        Ok(results)
+    }
+
+    async fn create_working_order(
+        &self,
+        session: &IgSession,
+        order: &CreateWorkingOrderRequest,
+    ) -> Result<WorkingOrderResponse, AppError> {
+        info!("Creating working order for: {}", order.epic);
+
+        let result = self.client
+            .request::<CreateWorkingOrderRequest, WorkingOrderResponse>(
+                Method::POST,
+                "workingorders/otc",
+                session,
+                Some(order),
+                "2",
+            )
+            .await?;
+
+        debug!("Working order created with reference: {}", result.deal_reference);
+        Ok(result)
+    }
+
+    async fn update_working_order(
+        &self,
+        session: &IgSession,
+        deal_id: &str,
+        update: &UpdateWorkingOrderRequest,
+    ) -> Result<WorkingOrderResponse, AppError> {
+        let path = format!("workingorders/otc/{}", deal_id);
+        info!("Updating working order: {}", deal_id);
+
+        let result = self.client
+            .request::<UpdateWorkingOrderRequest, WorkingOrderResponse>(
+                Method::PUT,
+                &path,
+                session,
+                Some(update),
+                "2",
+            )
+            .await?;
+
+        debug!("Working order updated with reference: {}", result.deal_reference);
+        Ok(result)
+    }
+
+    async fn delete_working_order(
+        &self,
+        session: &IgSession,
+        deal_id: &str,
+    ) -> Result<WorkingOrderResponse, AppError> {
+        let path = format!("workingorders/otc/{}", deal_id);
+        info!("Deleting working order: {}", deal_id);
+
+        let result = self.client
+            .request::<(), WorkingOrderResponse>(
+                Method::DELETE,
+                &path,
+                session,
+                None,
+                "2",
+            )
+            .await?;
+
+        debug!("Working order deleted with reference: {}", result.deal_reference);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests_order_service {
+    use super::*;
+    use crate::utils::money::price;
+    use crate::application::models::order::{Direction, OrderStatus, OrderType, TimeInForce};
+    use crate::transport::mock::{position_json, test_session, MockHttpClient};
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    fn service(client: Arc<MockHttpClient>) -> OrderServiceImpl<MockHttpClient> {
+        OrderServiceImpl::new(Arc::new(Config::new()), client)
+    }
+
+    fn confirmation(deal_reference: &str) -> serde_json::Value {
+        json!({
+            "date": "2025-05-13T10:00:00.000",
+            "status": "ACCEPTED",
+            "reason": "SUCCESS",
+            "dealId": "DIAAAA",
+            "dealReference": deal_reference,
+            "dealStatus": "ACCEPTED",
+            "epic": "IX.D.DAX.IFMM.IP",
+            "level": 18000.0,
+            "size": 1.0,
+            "direction": "BUY"
+        })
+    }
+
+    /// Field name and value pairs, in the order they were recorded
+    type Fields = Vec<(String, String)>;
+
+    /// Collects the fields of every span by span name, including values
+    /// recorded later, and the fields of events under `"events"`
+    #[derive(Clone, Default)]
+    struct SpanFields(Arc<std::sync::Mutex<std::collections::HashMap<String, Fields>>>);
+
+    struct FieldVisitor<'a>(&'a mut Vec<(String, String)>);
+
+    impl tracing::field::Visit for FieldVisitor<'_> {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.push((field.name().to_string(), value.to_string()));
+        }
+
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+
+    impl<S> tracing_subscriber::Layer<S> for SpanFields
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let name = ctx.span(id).unwrap().name().to_string();
+            let mut spans = self.0.lock().unwrap();
+            attrs.record(&mut FieldVisitor(spans.entry(name).or_default()));
+        }
+
+        fn on_record(
+            &self,
+            id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let name = ctx.span(id).unwrap().name().to_string();
+            let mut spans = self.0.lock().unwrap();
+            values.record(&mut FieldVisitor(spans.entry(name).or_default()));
+        }
+
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+            let mut spans = self.0.lock().unwrap();
+            event.record(&mut FieldVisitor(spans.entry("events".to_string()).or_default()));
+        }
+    }
+
+    impl SpanFields {
+        fn get(&self, span: &str, field: &str) -> Option<String> {
+            let spans = self.0.lock().unwrap();
+            spans.get(span)?.iter().find(|(name, _)| name == field).map(|(_, v)| v.clone())
+        }
+
+        /// Messages of every event logged so far
+        fn messages(&self) -> Vec<String> {
+            let spans = self.0.lock().unwrap();
+            let events = spans.get("events").into_iter().flatten();
+            events.filter(|(name, _)| name == "message").map(|(_, v)| v.clone()).collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_order_spans_carry_deal_fields() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let fields = SpanFields::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(fields.clone()));
+
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({"dealReference": "REF1"}));
+        client.push_json(confirmation("REF1"));
+        client.push_json(json!({"dealReference": "REF2"}));
+        let service = service(client);
+
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(1.0));
+        service.create_order(&test_session(), &order).await.unwrap();
+        service.get_order_confirmation(&test_session(), "REF1").await.unwrap();
+        let close = ClosePositionRequest {
+            deal_id: "DIAAAA".to_string(),
+            direction: Direction::Sell,
+            size: price!(1.0),
+            order_type: OrderType::Market,
+            time_in_force: TimeInForce::FillOrKill,
+            level: None,
+        };
+        service.close_position(&test_session(), &close).await.unwrap();
+
+        assert_eq!(fields.get("create_order", "epic").as_deref(), Some("IX.D.DAX.IFMM.IP"));
+        assert_eq!(fields.get("create_order", "deal_reference").as_deref(), Some("REF1"));
+        assert_eq!(fields.get("get_order_confirmation", "deal_reference").as_deref(), Some("REF1"));
+        assert_eq!(fields.get("get_order_confirmation", "deal_id").as_deref(), Some("DIAAAA"));
+        assert_eq!(fields.get("get_order_confirmation", "epic").as_deref(), Some("IX.D.DAX.IFMM.IP"));
+        assert_eq!(fields.get("close_position", "deal_id").as_deref(), Some("DIAAAA"));
+        assert_eq!(fields.get("close_position", "deal_reference").as_deref(), Some("REF2"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_makes_no_requests() {
+        let client = Arc::new(MockHttpClient::new());
+        let service = service(client.clone()).with_dry_run(true);
+
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(1.0));
+        let created = service.create_order(&test_session(), &order).await.unwrap();
+        assert!(created.deal_reference.starts_with("DRY-RUN-"));
+        let mut referenced = order.clone();
+        referenced.deal_reference = Some("IGC-MINE".to_string());
+        let created = service.create_order(&test_session(), &referenced).await.unwrap();
+        assert!(created.deal_reference.starts_with("DRY-RUN-"));
+
+        let confirmation = service
+            .create_order_and_confirm(&test_session(), &order, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(confirmation.status, OrderStatus::Accepted);
+        assert!(confirmation.deal_reference.starts_with("DRY-RUN-"));
+
+        // Invalid orders are still rejected
+        let mut invalid = order.clone();
+        invalid.level = Some(price!(18000.0));
+        assert!(service.create_order(&test_session(), &invalid).await.is_err());
+
+        assert!(client.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_records_the_returned_reference() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let fields = SpanFields::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(fields.clone()));
+        let service = service(Arc::new(MockHttpClient::new())).with_dry_run(true);
+
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(1.0));
+        let created = service.create_order(&test_session(), &order).await.unwrap();
+
+        // The logged body carries the same reference as the span and the response
+        let logged = format!("\"dealReference\":\"{}\"", created.deal_reference);
+        assert!(fields.messages().iter().any(|m| m.contains(&logged)), "{:?}", fields.messages());
+        assert_eq!(fields.get("create_order", "deal_reference"), Some(created.deal_reference));
+    }
+
+    #[tokio::test]
+    async fn test_create_order_and_confirm_retries_not_found() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({"dealReference": "REF1"}));
+        client.push_error(AppError::NotFound);
+        client.push_error(AppError::NotFound);
+        client.push_json(confirmation("REF1"));
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(1.0));
+
+        let result = service(client.clone())
+            .create_order_and_confirm(&test_session(), &order, Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert_eq!(result.status, OrderStatus::Accepted);
+        assert_eq!(result.deal_reference, "REF1");
+        let paths: Vec<_> = client.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec!["positions/otc", "confirms/REF1", "confirms/REF1", "confirms/REF1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_order_and_confirm_gives_up_after_timeout() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({"dealReference": "REF1"}));
+        client.push_error(AppError::NotFound);
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(1.0));
+
+        let result = service(client)
+            .create_order_and_confirm(&test_session(), &order, Duration::from_millis(50))
+            .await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_close_all_positions_reports_partial_failure() {
+        let client = Arc::new(MockHttpClient::new());
+        let mut second = position_json("IX.D.DAX.IFMM.IP", "SELL", 2.5);
+        second["position"]["dealId"] = json!("DIBBBB");
+        client.push_json(json!({"positions": [position_json("IX.D.DAX.IFMM.IP", "BUY", 1.0), second]}));
+        client.push_json(json!({"dealReference": "CLOSE1"}));
+        client.push_error(AppError::Unexpected(StatusCode::BAD_REQUEST));
+
+        let results = service(client.clone())
+            .close_all_positions(&test_session())
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "DIAAAA");
+        assert_eq!(results[0].1.as_ref().unwrap().deal_reference, "CLOSE1");
+        assert_eq!(results[1].0, "DIBBBB");
+        assert!(matches!(results[1].1, Err(AppError::Unexpected(StatusCode::BAD_REQUEST))));
+
+        let requests = client.requests();
+        assert_eq!(requests[0].path, "positions");
+        assert_eq!(requests[1].path, "positions/otc");
+        let bodies: Vec<_> = requests[1..].iter().map(|r| r.body.clone().unwrap()).collect();
+        assert_eq!(bodies[0]["direction"], "SELL");
+        assert_eq!(bodies[0]["size"], 1.0);
+        assert_eq!(bodies[1]["dealId"], "DIBBBB");
+        assert_eq!(bodies[1]["direction"], "BUY");
+        assert_eq!(bodies[1]["size"], 2.5);
+        assert_eq!(bodies[1]["orderType"], "MARKET");
+    }
+
+    #[tokio::test]
+    async fn test_set_stop_and_limit_keep_the_other_level() {
+        let mut current = position_json("IX.D.DAX.IFMM.IP", "BUY", 1.0);
+        current["position"]["stopLevel"] = json!(17800.0);
+        current["position"]["limitLevel"] = json!(18400.0);
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(current.clone());
+        client.push_json(json!(null));
+        client.push_json(current);
+        client.push_json(json!(null));
+        let service = service(client.clone());
+
+        service.set_stop_loss(&test_session(), "DIAAAA", price!(17900.0)).await.unwrap();
+        service.set_take_profit(&test_session(), "DIAAAA", price!(18500.0)).await.unwrap();
+
+        let requests = client.requests();
+        let calls: Vec<_> = requests.iter().map(|r| (r.method.clone(), r.path.as_str())).collect();
+        assert_eq!(
+            calls,
+            vec![
+                (Method::GET, "positions/DIAAAA"),
+                (Method::PUT, "positions/otc/DIAAAA"),
+                (Method::GET, "positions/DIAAAA"),
+                (Method::PUT, "positions/otc/DIAAAA"),
+            ]
+        );
+        assert_eq!(
+            requests[1].body.clone().unwrap(),
+            json!({"stopLevel": price!(17900.0), "limitLevel": 18400.0})
+        );
+        assert_eq!(
+            requests[3].body.clone().unwrap(),
+            json!({"stopLevel": 17800.0, "limitLevel": price!(18500.0)})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_take_profit_keeps_trailing_stop() {
+        let mut current = position_json("IX.D.DAX.IFMM.IP", "SELL", 1.0);
+        current["position"]["stopLevel"] = json!(18025.0);
+        current["position"]["trailingStopDistance"] = json!(25.0);
+        current["position"]["trailingStep"] = json!(5.0);
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(current);
+        client.push_json(json!(null));
+
+        service(client.clone())
+            .set_take_profit(&test_session(), "DIAAAA", price!(17500.0))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            client.requests()[1].body.clone().unwrap(),
+            json!({
+                "stopLevel": 18025.0,
+                "limitLevel": price!(17500.0),
+                "trailingStop": true,
+                "trailingStopDistance": 25.0,
+                "trailingStopIncrement": 5.0
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_close_sends_delete_override_but_create_does_not() {
+        use crate::transport::http_client::IgHttpClientImpl;
+        use crate::transport::mock::{MockResponse, MockServer};
+
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!({"dealReference": "OPEN1"})),
+            MockResponse::json(200, json!({"dealReference": "CLOSE1"})),
+        ]);
+        let mut config = Config::new();
+        config.rest_api.base_url = server.url();
+        let config = Arc::new(config);
+        let service = OrderServiceImpl::new(config.clone(), Arc::new(IgHttpClientImpl::new(config)));
+
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(1.0));
+        service.create_order(&test_session(), &order).await.unwrap();
+        let close = ClosePositionRequest::market("DIAAAA".to_string(), Direction::Sell, price!(1.0));
+        service.close_position(&test_session(), &close).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|r| r.method == "POST" && r.path == "/gateway/deal/positions/otc"));
+        assert_eq!(requests[0].header("_method"), None);
+        assert_eq!(requests[1].header("_method"), Some("DELETE"));
+    }
+
+    #[tokio::test]
+    async fn test_update_position_accepts_empty_success() {
+        use crate::transport::http_client::IgHttpClientImpl;
+        use crate::transport::mock::{MockResponse, MockServer};
+
+        let update = UpdatePositionRequest {
+            stop_level: Some(price!(17900.0)),
+            ..Default::default()
+        };
+        for status in [200, 204] {
+            let server = MockServer::start(vec![MockResponse::new(status, "")]);
+            let mut config = Config::new();
+            config.rest_api.base_url = server.url();
+            let config = Arc::new(config);
+            let service = OrderServiceImpl::new(config.clone(), Arc::new(IgHttpClientImpl::new(config)));
+
+            service.update_position(&test_session(), "DIAAAA", &update).await.unwrap();
+
+            let request = &server.requests()[0];
+            assert_eq!(request.method, "PUT");
+            let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+            assert_eq!(body["stopLevel"], json!(price!(17900.0)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_working_order() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({"dealReference": "WO1"}));
+        let order = CreateWorkingOrderRequest::limit(
+            "IX.D.DAX.IFMM.IP".to_string(),
+            Direction::Sell,
+            price!(2.0),
+            price!(18500.0),
+        )
+        .with_stop_loss(price!(18600.0));
+
+        let result = service(client.clone())
+            .create_working_order(&test_session(), &order)
+            .await
+            .unwrap();
+
+        assert_eq!(result.deal_reference, "WO1");
+        let requests = client.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, Method::POST);
+        assert_eq!(requests[0].path, "workingorders/otc");
+        assert_eq!(requests[0].version, "2");
+        assert_eq!(
+            requests[0].body,
+            Some(json!({
+                "epic": "IX.D.DAX.IFMM.IP",
+                "direction": "SELL",
+                "size": price!(2.0),
+                "level": price!(18500.0),
+                "type": "LIMIT",
+                "timeInForce": "GOOD_TILL_CANCELLED",
+                "guaranteedStop": false,
+                "expiry": "-",
+                "stopLevel": price!(18600.0)
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_working_order() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({"dealReference": "WO2"}));
+        let update = UpdateWorkingOrderRequest {
+            level: price!(18450.0),
+            order_type: OrderType::Stop,
+            time_in_force: TimeInForce::GoodTillCancelled,
+            good_till_date: None,
+            stop_level: None,
+            stop_distance: None,
+            limit_level: None,
+            limit_distance: Some(price!(50.0)),
+        };
+
+        let result = service(client.clone())
+            .update_working_order(&test_session(), "DIAAAA", &update)
+            .await
+            .unwrap();
+
+        assert_eq!(result.deal_reference, "WO2");
+        let requests = client.requests();
+        assert_eq!(requests[0].method, Method::PUT);
+        assert_eq!(requests[0].path, "workingorders/otc/DIAAAA");
+        assert_eq!(requests[0].version, "2");
+        assert_eq!(
+            requests[0].body,
+            Some(json!({
+                "level": price!(18450.0),
+                "type": "STOP",
+                "timeInForce": "GOOD_TILL_CANCELLED",
+                "limitDistance": price!(50.0)
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_working_order() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({"dealReference": "WO3"}));
+
+        let result = service(client.clone())
+            .delete_working_order(&test_session(), "DIAAAA")
+            .await
+            .unwrap();
+
+        assert_eq!(result.deal_reference, "WO3");
+        let requests = client.requests();
+        assert_eq!(requests[0].method, Method::DELETE);
+        assert_eq!(requests[0].path, "workingorders/otc/DIAAAA");
+        assert_eq!(requests[0].version, "2");
+        assert_eq!(requests[0].body, None);
+    }
+
+    #[tokio::test]
+    async fn test_create_order_rejects_invalid_order_without_request() {
+        let client = Arc::new(MockHttpClient::new());
+        let mut order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(1.0));
+        order.level = Some(price!(18000.0));
+
+        let result = service(client.clone()).create_order(&test_session(), &order).await;
+
+        assert!(matches!(result, Err(AppError::SerializationError(_))));
+        assert!(client.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retried_create_order_reuses_deal_reference() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_error(AppError::Unexpected(StatusCode::SERVICE_UNAVAILABLE));
+        client.push_json(json!({"dealReference": "ignored"}));
+        let service = service(client.clone())
+            .with_retry_policy(RetryPolicy::new(3, Duration::from_millis(1)));
+
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(1.0));
+        service.create_order(&test_session(), &order).await.unwrap();
+
+        let requests = client.requests();
+        assert_eq!(requests.len(), 2);
+        let reference = requests[0].body.as_ref().unwrap()["dealReference"].clone();
+        assert!(reference.as_str().unwrap().starts_with("IGC-"));
+        assert_eq!(requests[1].body.as_ref().unwrap()["dealReference"], reference);
+    }
+
+    #[tokio::test]
+    async fn test_failed_create_order_names_deal_reference() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_error(AppError::Unexpected(StatusCode::SERVICE_UNAVAILABLE));
+
+        let order = CreateOrderRequest::market("IX.D.DAX.IFMM.IP".to_string(), Direction::Buy, price!(1.0))
+            .with_reference("MYREF".to_string());
+        let result = service(client.clone()).create_order(&test_session(), &order).await;
+
+        assert!(matches!(result, Err(AppError::Context { context, .. }) if context == "create_order MYREF"));
+        assert_eq!(client.requests().len(), 1);
+    }
 }
@@ -0,0 +1,124 @@
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use tracing::{debug, warn};
+
+use crate::{
+    application::models::paging::Paged,
+    error::AppError,
+    session::interface::IgSession,
+    transport::http_client::IgHttpClient,
+};
+
+/// GETs `initial_path` and then every page its `paging.next` cursor points
+/// to, returning the items of all pages in order. Each response is decoded as
+/// `R` and converted into a [`Paged`]. Stops with a warning after `max_pages`
+/// pages even if more remain.
+pub async fn fetch_all_pages<T, R, C>(
+    client: &C,
+    initial_path: &str,
+    session: &IgSession,
+    version: &str,
+    max_pages: u32,
+) -> Result<Vec<T>, AppError>
+where
+    C: IgHttpClient + ?Sized,
+    R: DeserializeOwned + Into<Paged<T>> + 'static,
+{
+    let mut path = initial_path.to_string();
+    let mut items = Vec::new();
+    let mut pages = 0;
+
+    loop {
+        let page: Paged<T> = client
+            .request::<(), R>(Method::GET, &path, session, None, version)
+            .await?
+            .into();
+        pages += 1;
+        let next = page.next().map(str::to_string);
+        items.extend(page.items);
+
+        match next {
+            Some(next) if pages < max_pages => path = next,
+            Some(_) => {
+                warn!("Stopped following pages of {} after {} pages", initial_path, max_pages);
+                break;
+            }
+            None => break,
+        }
+    }
+
+    debug!("Fetched {} items in {} pages from {}", items.len(), pages, initial_path);
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests_paging {
+    use super::*;
+    use crate::application::models::account::{AccountActivity, Activity};
+    use crate::transport::mock::{test_session, MockHttpClient};
+    use serde_json::json;
+
+    fn page(deal_ids: &[&str], next: Option<&str>) -> serde_json::Value {
+        let activities: Vec<_> = deal_ids
+            .iter()
+            .map(|id| {
+                json!({
+                    "date": "2025-05-13T10:00:00",
+                    "dealId": id,
+                    "epic": "IX.D.DAX.IFMM.IP",
+                    "period": "-",
+                    "dealReference": format!("REF-{id}"),
+                    "activityType": "POSITION",
+                    "status": "ACCEPTED",
+                    "description": "Position opened",
+                    "details": null
+                })
+            })
+            .collect();
+        json!({
+            "activities": activities,
+            "metadata": {"paging": {"size": deal_ids.len(), "next": next}}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_follows_cursor_across_two_pages() {
+        let client = MockHttpClient::new();
+        client.push_json(page(&["D1", "D2"], Some("/history/activity?page=2")));
+        client.push_json(page(&["D3"], None));
+
+        let items: Vec<Activity> = fetch_all_pages::<_, AccountActivity, _>(
+            &client,
+            "history/activity",
+            &test_session(),
+            "3",
+            10,
+        )
+        .await
+        .unwrap();
+
+        let ids: Vec<_> = items.iter().map(|a| a.deal_id.as_str()).collect();
+        assert_eq!(ids, vec!["D1", "D2", "D3"]);
+        let paths: Vec<_> = client.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["history/activity", "/history/activity?page=2"]);
+    }
+
+    #[tokio::test]
+    async fn test_empty_cursor_ends_paging() {
+        let client = MockHttpClient::new();
+        client.push_json(page(&["D1"], Some("")));
+
+        let items: Vec<Activity> = fetch_all_pages::<_, AccountActivity, _>(
+            &client,
+            "history/activity",
+            &test_session(),
+            "3",
+            10,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(client.requests().len(), 1);
+    }
+}
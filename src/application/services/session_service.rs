@@ -0,0 +1,220 @@
+use async_trait::async_trait;
+use reqwest::Method;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+use crate::{
+    application::models::account::{Account, AccountInfo},
+    config::Config,
+    error::AppError,
+    session::account::{AccountSwitchRequest, AccountSwitchResponse},
+    session::interface::IgSession,
+    transport::http_client::IgHttpClient,
+};
+
+/// Interface for session-level operations
+#[async_trait]
+pub trait SessionService: Send + Sync {
+    /// Switches the active account and updates `session.account_id` on success
+    async fn switch_account(
+        &self,
+        session: &mut IgSession,
+        account_id: &str,
+        set_default: bool,
+    ) -> Result<AccountSwitchResponse, AppError>;
+
+    /// Lists the accounts available to the logged-in client
+    async fn get_accounts(&self, session: &IgSession) -> Result<Vec<Account>, AppError>;
+
+    /// Returns the account flagged as preferred, switching `session` to it
+    /// first if it is not already the active account. Fails with
+    /// `AppError::NotFound` if no account is preferred.
+    async fn preferred_account(&self, session: &mut IgSession) -> Result<Account, AppError>;
+}
+
+/// Implementation of the session service
+pub struct SessionServiceImpl<T: IgHttpClient> {
+    config: Arc<Config>,
+    client: Arc<T>,
+}
+
+impl<T: IgHttpClient> SessionServiceImpl<T> {
+    /// Creates a new session service
+    pub fn new(config: Arc<Config>, client: Arc<T>) -> Self {
+        Self { config, client }
+    }
+
+    pub fn get_config(&self) -> Arc<Config> {
+        self.config.clone()
+    }
+
+    pub fn set_config(&mut self, config: Arc<Config>) {
+        self.config = config;
+    }
+}
+
+#[async_trait]
+impl<T: IgHttpClient + 'static> SessionService for SessionServiceImpl<T> {
+    async fn switch_account(
+        &self,
+        session: &mut IgSession,
+        account_id: &str,
+        set_default: bool,
+    ) -> Result<AccountSwitchResponse, AppError> {
+        info!("Switching to account: {}", account_id);
+
+        let request = AccountSwitchRequest {
+            account_id: account_id.to_string(),
+            default_account: Some(set_default),
+        };
+        let result = self
+            .client
+            .request::<AccountSwitchRequest, AccountSwitchResponse>(
+                Method::PUT,
+                "session",
+                session,
+                Some(&request),
+                "1",
+            )
+            .await?;
+
+        session.account_id = account_id.to_string();
+        debug!("Active account is now: {}", account_id);
+        Ok(result)
+    }
+
+    async fn get_accounts(&self, session: &IgSession) -> Result<Vec<Account>, AppError> {
+        info!("Getting accounts");
+
+        let result = self
+            .client
+            .request::<(), AccountInfo>(Method::GET, "accounts", session, None, "1")
+            .await?;
+
+        debug!("Found {} accounts", result.accounts.len());
+        Ok(result.accounts)
+    }
+
+    async fn preferred_account(&self, session: &mut IgSession) -> Result<Account, AppError> {
+        info!("Getting preferred account");
+
+        let account = self
+            .get_accounts(session)
+            .await?
+            .into_iter()
+            .find(|account| account.preferred)
+            .ok_or(AppError::NotFound)?;
+        if account.account_id != session.account_id {
+            self.switch_account(session, &account.account_id, false).await?;
+        }
+
+        debug!("Preferred account: {}", account.account_id);
+        Ok(account)
+    }
+}
+
+#[cfg(test)]
+mod tests_session_service {
+    use super::*;
+    use crate::transport::mock::{test_session, MockHttpClient};
+    use serde_json::json;
+
+    #[test]
+    fn test_account_switch_response_decodes_ig_payload() {
+        let body = r#"{
+            "trailingStopsEnabled": false,
+            "dealingEnabled": true,
+            "hasActiveDemoAccounts": true,
+            "hasActiveLiveAccounts": false
+        }"#;
+
+        let response: AccountSwitchResponse = serde_json::from_str(body).unwrap();
+
+        assert_eq!(response.dealing_enabled, Some(true));
+        assert_eq!(response.has_active_demo_accounts, Some(true));
+        assert_eq!(response.has_active_live_accounts, Some(false));
+        assert_eq!(response.trailing_stops_enabled, Some(false));
+    }
+
+    fn accounts() -> serde_json::Value {
+        let account = |id: &str, preferred: bool| {
+            json!({
+                "accountId": id,
+                "accountName": format!("{} account", id),
+                "accountType": "SPREADBET",
+                "balance": {"balance": 1000.0, "deposit": 0.0, "profitLoss": 0.0, "available": 1000.0},
+                "currency": "GBP",
+                "status": "ENABLED",
+                "preferred": preferred
+            })
+        };
+        json!({"accounts": [account("ACC1", false), account("ACC2", true), account("ACC3", false)]})
+    }
+
+    #[tokio::test]
+    async fn test_get_accounts_decodes_every_account() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(accounts());
+        let service = SessionServiceImpl::new(Arc::new(Config::new()), client.clone());
+
+        let accounts = service.get_accounts(&test_session()).await.unwrap();
+
+        let ids: Vec<_> = accounts.iter().map(|a| a.account_id.as_str()).collect();
+        assert_eq!(ids, vec!["ACC1", "ACC2", "ACC3"]);
+        assert!(accounts[1].preferred);
+        assert_eq!(client.requests()[0].path, "accounts");
+        assert_eq!(client.requests()[0].version, "1");
+    }
+
+    #[tokio::test]
+    async fn test_preferred_account_switches_session() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(accounts());
+        client.push_json(json!({"dealingEnabled": true}));
+        let service = SessionServiceImpl::new(Arc::new(Config::new()), client.clone());
+        let mut session = test_session();
+
+        let account = service.preferred_account(&mut session).await.unwrap();
+
+        assert_eq!(account.account_id, "ACC2");
+        assert_eq!(session.account_id, "ACC2");
+        let requests = client.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!((requests[1].method.clone(), requests[1].path.as_str()), (Method::PUT, "session"));
+
+        // Already on the preferred account: no switch
+        client.push_json(accounts());
+        service.preferred_account(&mut session).await.unwrap();
+        assert_eq!(client.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_preferred_account_not_found() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({"accounts": []}));
+        let service = SessionServiceImpl::new(Arc::new(Config::new()), client);
+
+        let result = service.preferred_account(&mut test_session()).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_switch_account_updates_session() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({"dealingEnabled": true, "hasActiveLiveAccounts": true}));
+        let service = SessionServiceImpl::new(Arc::new(Config::new()), client.clone());
+        let mut session = test_session();
+
+        service.switch_account(&mut session, "ACC2", true).await.unwrap();
+
+        assert_eq!(session.account_id, "ACC2");
+        let requests = client.requests();
+        assert_eq!(requests[0].method, Method::PUT);
+        assert_eq!(requests[0].path, "session");
+        assert_eq!(requests[0].version, "1");
+        assert_eq!(
+            requests[0].body,
+            Some(json!({"accountId": "ACC2", "defaultAccount": true}))
+        );
+    }
+}
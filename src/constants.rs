@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+/// Maximum number of pages `get_all_activity` will follow before giving up
+pub(crate) const DEFAULT_MAX_ACTIVITY_PAGES: u32 = 100;
+
+/// Seconds before expiry at which a v3 access token is refreshed
+pub(crate) const DEFAULT_SESSION_V3_REFRESH: i64 = 10;
+
+/// Retries of a failed page while `IgTxClient` paginates transactions
+pub(crate) const DEFAULT_TX_PAGE_RETRIES: u32 = 3;
+
+/// Transactions requested per page while paginating a range
+pub(crate) const TX_PAGE_SIZE: u32 = 200;
+
+/// Delay before the first retry of a failed transactions page; doubled on every further attempt
+pub(crate) const DEFAULT_TX_PAGE_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Retries for idempotent GET requests that fail with a transient network error
+pub(crate) const DEFAULT_HTTP_MAX_RETRIES: u32 = 2;
+
+/// How long `CachingMarketService` keeps market details before refetching
+pub(crate) const DEFAULT_MARKET_DETAILS_TTL: Duration = Duration::from_secs(2);
+
+/// Epics `CachingMarketService` keeps market details for before evicting one
+pub(crate) const DEFAULT_MARKET_DETAILS_CACHE_SIZE: usize = 256;
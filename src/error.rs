@@ -4,12 +4,16 @@
     Date: 12/5/25
  ******************************************************************************/
 use std::{fmt, io};
+use std::time::Duration;
 use std::fmt::{Display, Formatter};
 use reqwest::StatusCode;
 
+use crate::transport::lightstreamer::LightstreamerError;
+
 #[derive(Debug)]
 pub enum FetchError {
     Reqwest(reqwest::Error),
+    #[cfg(feature = "storage")]
     Sqlx(sqlx::Error),
     Parser(String),
 }
@@ -18,6 +22,7 @@ impl Display for FetchError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             FetchError::Reqwest(e) => write!(f, "network error: {e}"),
+            #[cfg(feature = "storage")]
             FetchError::Sqlx(e) => write!(f, "db error: {e}"),
             FetchError::Parser(msg) => write!(f, "parser error: {msg}"),
         }
@@ -32,6 +37,7 @@ impl From<reqwest::Error> for FetchError {
     }
 }
 
+#[cfg(feature = "storage")]
 impl From<sqlx::Error> for FetchError {
     fn from(err: sqlx::Error) -> Self {
         FetchError::Sqlx(err)
@@ -46,6 +52,14 @@ pub enum AuthError {
     Json(serde_json::Error),
     Other(String),
     BadCredentials,
+    /// `error.security.account-token-invalid`: the account token is not valid for this session
+    AccountTokenInvalid,
+    /// `error.security.client-token-invalid`: the client (CST) token is not valid
+    ClientTokenInvalid,
+    /// `error.public-api.failure.encryption.required`: the password must be sent encrypted
+    EncryptionRequired,
+    /// `error.security.api-key-invalid`: the API key is unknown or disabled
+    InvalidApiKey,
     Unexpected(StatusCode),
 }
 
@@ -57,6 +71,10 @@ impl Display for AuthError {
             AuthError::Json(e)    => write!(f, "json error: {e}"),
             AuthError::Other(msg) => write!(f, "other error: {msg}"),
             AuthError::BadCredentials => write!(f, "bad credentials"),
+            AuthError::AccountTokenInvalid => write!(f, "account token invalid"),
+            AuthError::ClientTokenInvalid => write!(f, "client token invalid"),
+            AuthError::EncryptionRequired => write!(f, "encrypted password required"),
+            AuthError::InvalidApiKey => write!(f, "invalid api key"),
             AuthError::Unexpected(s) => write!(f, "unexpected http status: {s}"),
         }
     }
@@ -99,12 +117,34 @@ pub enum AppError {
     Io(io::Error),
     Json(serde_json::Error),
     Unexpected(StatusCode),
+    /// Database error; only with the `storage` feature
+    #[cfg(feature = "storage")]
     Db(sqlx::Error),
     Unauthorized,
     NotFound,
-    RateLimitExceeded,
+    /// HTTP 429; `retry_after` comes from `Retry-After` or `X-RateLimit-Reset` when IG sends one
+    RateLimited { retry_after: Option<Duration> },
     SerializationError(String),
     WebSocketError(String),
+    /// The Lightstreamer server refused the streaming session
+    Lightstreamer(LightstreamerError),
+    /// Invalid configuration, detected by `Config::validate`
+    ConfigError(String),
+    /// `source` annotated with the operation that failed
+    Context {
+        context: String,
+        source: Box<AppError>,
+    },
+}
+
+impl AppError {
+    /// Wraps the error with the name of the operation that failed
+    pub fn with_context(self, context: impl Into<String>) -> Self {
+        AppError::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
 }
 
 impl Display for AppError {
@@ -114,17 +154,31 @@ impl Display for AppError {
             AppError::Io(e)        => write!(f, "io error: {e}"),
             AppError::Json(e)      => write!(f, "json error: {e}"),
             AppError::Unexpected(s)=> write!(f, "unexpected http status: {s}"),
+            #[cfg(feature = "storage")]
             AppError::Db(e)        => write!(f, "db error: {e}"),
             AppError::Unauthorized  => write!(f, "unauthorized"),
             AppError::NotFound      => write!(f, "not found"),
-            AppError::RateLimitExceeded => write!(f, "rate limit exceeded"),
+            AppError::RateLimited { retry_after: Some(d) } => {
+                write!(f, "rate limit exceeded, retry after {}s", d.as_secs())
+            }
+            AppError::RateLimited { retry_after: None } => write!(f, "rate limit exceeded"),
             AppError::SerializationError(s) => write!(f, "serialization error: {s}"),
             AppError::WebSocketError(s) => write!(f, "websocket error: {s}"),
+            AppError::Lightstreamer(e) => write!(f, "lightstreamer error: {e}"),
+            AppError::ConfigError(s) => write!(f, "config error: {s}"),
+            AppError::Context { context, source } => write!(f, "{context}: {source}"),
         }
     }
 }
 
-impl std::error::Error for AppError {}
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl From<reqwest::Error> for AppError {
     fn from(e: reqwest::Error) -> Self { AppError::Network(e) }
@@ -135,6 +189,7 @@ impl From<io::Error> for AppError {
 impl From<serde_json::Error> for AppError {
     fn from(e: serde_json::Error) -> Self { AppError::Json(e) }
 }
+#[cfg(feature = "storage")]
 impl From<sqlx::Error> for AppError {
     fn from(e: sqlx::Error) -> Self {
         AppError::Db(e)
@@ -146,7 +201,11 @@ impl From<AuthError> for AppError {
             AuthError::Network(e) => AppError::Network(e),
             AuthError::Io(e)      => AppError::Io(e),
             AuthError::Json(e)    => AppError::Json(e),
-            AuthError::BadCredentials => AppError::Unauthorized,
+            AuthError::BadCredentials
+            | AuthError::AccountTokenInvalid
+            | AuthError::ClientTokenInvalid
+            | AuthError::EncryptionRequired
+            | AuthError::InvalidApiKey => AppError::Unauthorized,
             AuthError::Unexpected(s) => AppError::Unexpected(s),
             _ => AppError::Unexpected(StatusCode::INTERNAL_SERVER_ERROR),
         }
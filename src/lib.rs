@@ -19,3 +19,6 @@ pub(crate) mod constants;
 pub mod utils;
 pub mod error;
 pub mod storage;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod test_support;
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+/// Body of `PUT /session` used to switch the active account
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountSwitchRequest {
+    #[serde(rename = "accountId")]
+    pub account_id: String,
+    #[serde(rename = "defaultAccount", skip_serializing_if = "Option::is_none")]
+    pub default_account: Option<bool>,
+}
+
+/// Response to an account switch
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountSwitchResponse {
+    #[serde(rename = "dealingEnabled")]
+    pub dealing_enabled: Option<bool>,
+    #[serde(rename = "hasActiveDemoAccounts")]
+    pub has_active_demo_accounts: Option<bool>,
+    #[serde(rename = "hasActiveLiveAccounts")]
+    pub has_active_live_accounts: Option<bool>,
+    #[serde(rename = "trailingStopsEnabled")]
+    pub trailing_stops_enabled: Option<bool>,
+}
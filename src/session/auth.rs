@@ -8,8 +8,25 @@ use crate::{
     error::AuthError,                    // mismo enum/impl que ya usas
     session::interface::{IgAuthenticator, IgSession},
     session::response::SessionResp,
+    transport::http::build_client,
 };
 
+/// Maps the `errorCode` of a rejected login to an [`AuthError`]. Unknown
+/// codes fall back to `BadCredentials` on 401 and `Unexpected` otherwise.
+fn login_error(status: StatusCode, body: &str) -> AuthError {
+    let code = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| json["errorCode"].as_str().map(str::to_owned));
+    match code.as_deref() {
+        Some("error.security.account-token-invalid") => AuthError::AccountTokenInvalid,
+        Some("error.security.client-token-invalid") => AuthError::ClientTokenInvalid,
+        Some("error.public-api.failure.encryption.required") => AuthError::EncryptionRequired,
+        Some("error.security.api-key-invalid") => AuthError::InvalidApiKey,
+        _ if status == StatusCode::UNAUTHORIZED => AuthError::BadCredentials,
+        _ => AuthError::Unexpected(status),
+    }
+}
+
 /// Mantiene una referencia a la Config global
 pub struct IgAuth<'a> {
     cfg:   &'a Config,
@@ -17,19 +34,22 @@ pub struct IgAuth<'a> {
 }
 
 impl<'a> IgAuth<'a> {
+    /// Creates an authenticator for `cfg`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the proxy URL or CA bundle cannot be used; call
+    /// [`Config::validate`] first to get these as an error instead.
     pub fn new(cfg: &'a Config) -> Self {
         Self {
             cfg,
-            http: Client::builder()
-                .user_agent("ig-rs/0.1")
-                .build()
-                .expect("reqwest client"),
+            http: build_client(cfg),
         }
     }
 
     /// Devuelve la URL base correcta (demo vs live) según la config
     fn rest_url(&self, path: &str) -> String {
-        format!("{}/{}", self.cfg.rest_api.base_url.trim_end_matches('/'), path.trim_start_matches('/'))
+        self.cfg.rest_api.endpoint(path)
     }
 }
 
@@ -65,9 +85,17 @@ impl IgAuthenticator for IgAuth<'_> {
                     .ok_or(AuthError::Unexpected(StatusCode::OK))?
                     .to_owned();
                 let json: SessionResp = resp.json().await?;
-                Ok(IgSession { cst, token, account_id: json.account_id })
+                Ok(IgSession {
+                    cst,
+                    token,
+                    account_id: json.account_id,
+                    lightstreamer_endpoint: json.lightstreamer_endpoint,
+                })
+            }
+            status @ (StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => {
+                let body = resp.text().await?;
+                Err(login_error(status, &body))
             }
-            StatusCode::UNAUTHORIZED => Err(AuthError::BadCredentials),
             other                     => Err(AuthError::Unexpected(other)),
         }
     }
@@ -85,12 +113,122 @@ impl IgAuthenticator for IgAuth<'_> {
             .await?;
 
         if resp.status() == StatusCode::OK {
-            let cst   = resp.headers().get("CST").unwrap().to_str().unwrap().into();
-            let token = resp.headers().get("X-SECURITY-TOKEN").unwrap().to_str().unwrap().into();
+            let cst   = resp.headers()
+                .get("CST")
+                .and_then(|v| v.to_str().ok())
+                .ok_or(AuthError::Unexpected(StatusCode::OK))?
+                .to_owned();
+            let token = resp.headers()
+                .get("X-SECURITY-TOKEN")
+                .and_then(|v| v.to_str().ok())
+                .ok_or(AuthError::Unexpected(StatusCode::OK))?
+                .to_owned();
             let json: SessionResp = resp.json().await?;
-            Ok(IgSession { cst, token, account_id: json.account_id })
+            Ok(IgSession {
+                cst,
+                token,
+                account_id: json.account_id,
+                lightstreamer_endpoint: json.lightstreamer_endpoint,
+            })
         } else {
             Err(AuthError::Unexpected(resp.status()))
         }
     }
-}
\ No newline at end of file
+
+    async fn logout(&self, sess: &IgSession) -> Result<(), AuthError> {
+        let url = self.rest_url("session");
+
+        let resp = self.http
+            .delete(url)
+            .header("X-IG-API-KEY", &self.cfg.credentials.api_key)
+            .header("CST",             &sess.cst)
+            .header("X-SECURITY-TOKEN",&sess.token)
+            .header("Version",         "1")
+            .send()
+            .await?;
+
+        match resp.status() {
+            StatusCode::OK | StatusCode::NO_CONTENT => Ok(()),
+            other => Err(AuthError::Unexpected(other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_auth {
+    use super::*;
+    use crate::transport::mock::{test_session, MockResponse, MockServer};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_refresh_without_cst_header_is_an_error() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!({"accountId": "ACC1"}))
+                .with_header("X-SECURITY-TOKEN", "XST2"),
+        ]);
+        let mut config = Config::new();
+        config.rest_api.base_url = server.url();
+
+        let result = IgAuth::new(&config).refresh(&test_session()).await;
+
+        assert!(matches!(result, Err(AuthError::Unexpected(StatusCode::OK))));
+        assert_eq!(server.requests()[0].path, "/gateway/deal/session/refresh-token");
+    }
+
+    #[tokio::test]
+    async fn test_login_maps_ig_error_codes() {
+        let rejection = |status: u16, code: &str| MockResponse::json(status, json!({"errorCode": code}));
+        let server = MockServer::start(vec![
+            rejection(401, "error.security.invalid-details"),
+            rejection(403, "error.public-api.failure.encryption.required"),
+            rejection(401, "error.security.account-token-invalid"),
+            rejection(403, "error.security.api-key-invalid"),
+            rejection(403, "error.public-api.exceeded-api-key-allowance"),
+            MockResponse::new(401, "not json"),
+        ]);
+        let mut config = Config::new();
+        config.rest_api.base_url = server.url();
+        let auth = IgAuth::new(&config);
+
+        assert!(matches!(auth.login().await, Err(AuthError::BadCredentials)));
+        assert!(matches!(auth.login().await, Err(AuthError::EncryptionRequired)));
+        assert!(matches!(auth.login().await, Err(AuthError::AccountTokenInvalid)));
+        assert!(matches!(auth.login().await, Err(AuthError::InvalidApiKey)));
+        assert!(matches!(
+            auth.login().await,
+            Err(AuthError::Unexpected(StatusCode::FORBIDDEN))
+        ));
+        assert!(matches!(auth.login().await, Err(AuthError::BadCredentials)));
+    }
+
+    #[test]
+    fn test_login_error_for_client_token() {
+        let body = r#"{"errorCode":"error.security.client-token-invalid"}"#;
+        assert!(matches!(
+            login_error(StatusCode::UNAUTHORIZED, body),
+            AuthError::ClientTokenInvalid
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_logout_deletes_session() {
+        let server = MockServer::start(vec![
+            MockResponse::new(204, ""),
+            MockResponse::new(401, ""),
+        ]);
+        let mut config = Config::new();
+        config.rest_api.base_url = server.url();
+        let auth = IgAuth::new(&config);
+
+        auth.logout(&test_session()).await.unwrap();
+        let result = auth.logout(&test_session()).await;
+        assert!(matches!(result, Err(AuthError::Unexpected(StatusCode::UNAUTHORIZED))));
+
+        let request = &server.requests()[0];
+        assert_eq!(request.method, "DELETE");
+        assert_eq!(request.path, "/gateway/deal/session");
+        assert_eq!(request.header("CST"), Some("CST"));
+        assert_eq!(request.header("X-SECURITY-TOKEN"), Some("XST"));
+        assert_eq!(request.header("Version"), Some("1"));
+    }
+}
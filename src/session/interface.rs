@@ -6,10 +6,28 @@ pub struct IgSession {
     pub cst: String,
     pub token: String,
     pub account_id: String,
+    /// Lightstreamer server assigned to this account at login, if IG sent one
+    pub lightstreamer_endpoint: Option<String>,
 }
 
 #[async_trait::async_trait]
 pub trait IgAuthenticator: Send + Sync {
     async fn login(&self) -> Result<IgSession, AuthError>;
     async fn refresh(&self, session: &IgSession) -> Result<IgSession, AuthError>;
-}
\ No newline at end of file
+
+    /// Invalidates the session on the server
+    async fn logout(&self, _session: &IgSession) -> Result<(), AuthError> {
+        Err(AuthError::Other("logout is not supported by this authenticator".to_string()))
+    }
+}
+
+/// Session obtained through the v3 (OAuth) login flow
+#[derive(Debug, Clone)]
+pub struct IgSessionV3 {
+    pub account_id: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    /// Lifetime of the access token in seconds
+    pub expires_in: i64,
+}
@@ -0,0 +1,232 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+
+use crate::{
+    constants::DEFAULT_SESSION_V3_REFRESH,
+    error::AppError,
+    session::interface::IgSessionV3,
+    session::response::OAuthToken,
+    transport::http_client::IgHttpClient,
+    utils::clock::{Clock, SystemClock},
+};
+
+/// Delay before retrying after a failed background refresh
+const REFRESH_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+struct ManagedSession {
+    session: IgSessionV3,
+    expires_at: DateTime<Utc>,
+}
+
+/// Keeps a v3 (OAuth) session fresh by refreshing the access token shortly
+/// before it expires
+pub struct SessionManager<T: IgHttpClient> {
+    client: Arc<T>,
+    state: Mutex<ManagedSession>,
+    refresh_threshold: i64,
+    clock: Arc<dyn Clock>,
+}
+
+impl<T: IgHttpClient + 'static> SessionManager<T> {
+    /// Creates a manager for a session that was just obtained
+    pub fn new(client: Arc<T>, session: IgSessionV3) -> Self {
+        Self::with_clock(client, session, Arc::new(SystemClock))
+    }
+
+    /// Like [`new`](Self::new), reading time from `clock`
+    pub fn with_clock(client: Arc<T>, session: IgSessionV3, clock: Arc<dyn Clock>) -> Self {
+        let obtained_at = clock.now();
+        Self::from_parts(client, session, obtained_at, clock)
+    }
+
+    fn from_parts(
+        client: Arc<T>,
+        session: IgSessionV3,
+        obtained_at: DateTime<Utc>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let expires_at = obtained_at + chrono::Duration::seconds(session.expires_in);
+        Self {
+            client,
+            state: Mutex::new(ManagedSession {
+                session,
+                expires_at,
+            }),
+            refresh_threshold: DEFAULT_SESSION_V3_REFRESH,
+            clock,
+        }
+    }
+
+    /// Returns a copy of the current session
+    pub fn session(&self) -> IgSessionV3 {
+        self.state.lock().unwrap().session.clone()
+    }
+
+    /// Value for the `Authorization` header, e.g. `Bearer <access_token>`
+    pub fn current_auth_header(&self) -> String {
+        let state = self.state.lock().unwrap();
+        format!("{} {}", state.session.token_type, state.session.access_token)
+    }
+
+    /// Value for the `IG-ACCOUNT-ID` header
+    pub fn current_account_id(&self) -> String {
+        self.state.lock().unwrap().session.account_id.clone()
+    }
+
+    /// Instant at which the current access token expires
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.state.lock().unwrap().expires_at
+    }
+
+    fn needs_refresh_at(&self, now: DateTime<Utc>) -> bool {
+        let remaining = self.expires_at() - now;
+        remaining.num_seconds() < self.refresh_threshold
+    }
+
+    /// Refreshes the access token if it expires within the refresh threshold.
+    /// Returns `true` when a refresh was performed.
+    pub async fn refresh_if_needed(&self) -> Result<bool, AppError> {
+        self.refresh_if_needed_at(self.clock.now()).await
+    }
+
+    async fn refresh_if_needed_at(&self, now: DateTime<Utc>) -> Result<bool, AppError> {
+        if !self.needs_refresh_at(now) {
+            return Ok(false);
+        }
+        self.refresh_at(now).await?;
+        Ok(true)
+    }
+
+    /// Unconditionally exchanges the refresh token for a new access token
+    pub async fn refresh(&self) -> Result<(), AppError> {
+        self.refresh_at(self.clock.now()).await
+    }
+
+    async fn refresh_at(&self, now: DateTime<Utc>) -> Result<(), AppError> {
+        let refresh_token = self.state.lock().unwrap().session.refresh_token.clone();
+        let body = serde_json::json!({ "refresh_token": refresh_token });
+
+        debug!("Refreshing v3 access token");
+        let token = self
+            .client
+            .request_no_auth::<serde_json::Value, OAuthToken>(
+                Method::POST,
+                "session/refresh-token",
+                Some(&body),
+                "1",
+            )
+            .await?;
+
+        let mut state = self.state.lock().unwrap();
+        state.expires_at = now + chrono::Duration::seconds(token.expires_in);
+        state.session.access_token = token.access_token;
+        state.session.refresh_token = token.refresh_token;
+        state.session.token_type = token.token_type;
+        state.session.expires_in = token.expires_in;
+        info!("v3 access token refreshed, expires at {}", state.expires_at);
+        Ok(())
+    }
+
+    /// Spawns a task that keeps the session fresh until the returned handle is aborted
+    pub fn spawn_refresh_loop(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let due = self.expires_at() - chrono::Duration::seconds(self.refresh_threshold);
+                let wait = (due - self.clock.now()).to_std().unwrap_or(Duration::ZERO);
+                self.clock.sleep(wait).await;
+
+                if let Err(e) = self.refresh_if_needed().await {
+                    error!("Failed to refresh v3 session: {}", e);
+                    self.clock.sleep(REFRESH_RETRY_DELAY).await;
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests_session_manager {
+    use super::*;
+    use crate::transport::mock::MockHttpClient;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn session() -> IgSessionV3 {
+        IgSessionV3 {
+            account_id: "ACC1".to_string(),
+            access_token: "access-1".to_string(),
+            refresh_token: "refresh-1".to_string(),
+            token_type: "Bearer".to_string(),
+            expires_in: 60,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_triggers_at_threshold() {
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({
+            "access_token": "access-2",
+            "refresh_token": "refresh-2",
+            "scope": "profile",
+            "token_type": "Bearer",
+            "expires_in": "60"
+        }));
+        let t0 = Utc.with_ymd_and_hms(2025, 5, 13, 12, 0, 0).unwrap();
+        let manager = SessionManager::from_parts(client.clone(), session(), t0, Arc::new(SystemClock));
+        assert_eq!(manager.current_auth_header(), "Bearer access-1");
+        assert_eq!(manager.current_account_id(), "ACC1");
+
+        // Still outside the refresh window
+        let before = t0 + chrono::Duration::seconds(60 - DEFAULT_SESSION_V3_REFRESH);
+        assert!(!manager.refresh_if_needed_at(before).await.unwrap());
+        assert!(client.requests().is_empty());
+
+        // One second later the remaining lifetime drops below the threshold
+        let at = before + chrono::Duration::seconds(1);
+        assert!(manager.refresh_if_needed_at(at).await.unwrap());
+
+        let requests = client.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "session/refresh-token");
+        assert_eq!(requests[0].body, Some(json!({"refresh_token": "refresh-1"})));
+        assert_eq!(manager.current_auth_header(), "Bearer access-2");
+        assert_eq!(manager.session().refresh_token, "refresh-2");
+        assert_eq!(manager.expires_at(), at + chrono::Duration::seconds(60));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_loop_follows_clock() {
+        use crate::utils::clock::MockClock;
+
+        let client = Arc::new(MockHttpClient::new());
+        client.push_json(json!({
+            "access_token": "access-2",
+            "refresh_token": "refresh-2",
+            "scope": "profile",
+            "token_type": "Bearer",
+            "expires_in": "60"
+        }));
+        let clock = Arc::new(MockClock::new(Utc.with_ymd_and_hms(2025, 5, 13, 12, 0, 0).unwrap()));
+        let manager = Arc::new(SessionManager::with_clock(client.clone(), session(), clock.clone()));
+        let handle = manager.clone().spawn_refresh_loop();
+        tokio::task::yield_now().await;
+
+        // Nothing happens until the remaining lifetime drops below the threshold
+        clock.advance(Duration::from_secs((60 - DEFAULT_SESSION_V3_REFRESH) as u64 - 1));
+        tokio::task::yield_now().await;
+        assert!(client.requests().is_empty());
+
+        clock.advance(Duration::from_secs(2));
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        handle.abort();
+        assert_eq!(client.requests().len(), 1);
+        assert_eq!(manager.current_auth_header(), "Bearer access-2");
+    }
+}
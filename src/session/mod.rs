@@ -1,3 +1,6 @@
 pub mod response;
 pub mod auth;
-pub mod interface;
\ No newline at end of file
+pub mod interface;
+pub mod manager;
+pub mod account;
+pub mod refresh;
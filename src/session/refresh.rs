@@ -0,0 +1,94 @@
+//! Recovering from an expired session by refreshing it and retrying once.
+
+use std::future::Future;
+
+use tracing::{info, warn};
+
+use crate::error::AppError;
+use crate::session::interface::{IgAuthenticator, IgSession};
+
+/// Whether an error means the session tokens were rejected
+pub fn is_unauthorized(error: &AppError) -> bool {
+    match error {
+        AppError::Unauthorized => true,
+        AppError::Context { source, .. } => is_unauthorized(source),
+        _ => false,
+    }
+}
+
+/// Runs `op` with a copy of `session`. If it fails because the session was
+/// rejected, the session is refreshed through `auth` (logging in again if the
+/// refresh itself fails), stored back into `session`, and `op` is run once more.
+pub async fn with_refresh<A, F, Fut, T>(
+    auth: &A,
+    session: &mut IgSession,
+    mut op: F,
+) -> Result<T, AppError>
+where
+    A: IgAuthenticator + ?Sized,
+    F: FnMut(IgSession) -> Fut,
+    Fut: Future<Output = Result<T, AppError>>,
+{
+    let error = match op(session.clone()).await {
+        Ok(value) => return Ok(value),
+        Err(error) if is_unauthorized(&error) => error,
+        Err(error) => return Err(error),
+    };
+
+    info!("Session rejected ({}), refreshing", error);
+    *session = match auth.refresh(session).await {
+        Ok(refreshed) => refreshed,
+        Err(e) => {
+            warn!("Session refresh failed ({}), logging in again", e);
+            auth.login().await?
+        }
+    };
+    op(session.clone()).await
+}
+
+#[cfg(test)]
+mod tests_refresh {
+    use super::*;
+    use crate::test_support::MockAuthenticator;
+
+    #[tokio::test]
+    async fn test_retries_once_with_rotated_token() {
+        let auth = MockAuthenticator::new();
+        let mut session = auth.login().await.unwrap();
+
+        let mut tokens = Vec::new();
+        let result = with_refresh(&auth, &mut session, |session| {
+            tokens.push(session.token.clone());
+            let expired = tokens.len() == 1;
+            async move {
+                if expired {
+                    Err(AppError::Unauthorized.with_context("get_positions"))
+                } else {
+                    Ok(session.account_id)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), "ACC1");
+        assert_eq!(tokens, vec!["XST", "XST-1"]);
+        assert_eq!(session.token, "XST-1");
+        assert_eq!(auth.refresh_count(), 1);
+        assert_eq!(auth.login_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_are_not_retried() {
+        let auth = MockAuthenticator::new();
+        let mut session = auth.login().await.unwrap();
+
+        let result: Result<(), _> = with_refresh(&auth, &mut session, |_| async {
+            Err(AppError::NotFound)
+        })
+        .await;
+
+        assert!(matches!(result, Err(AppError::NotFound)));
+        assert_eq!(auth.refresh_count(), 0);
+        assert_eq!(session.token, "XST");
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(serde::Deserialize)]
+use serde::Deserialize;
+
+#[derive(Deserialize)]
 pub struct SessionResp {
     #[serde(alias = "accountId")]
     #[serde(alias = "currentAccountId")]
@@ -8,4 +10,33 @@ pub struct SessionResp {
     pub client_id: Option<String>,
     #[serde(alias = "timezoneOffset")]
     pub timezone_offset: Option<i32>,
-}
\ No newline at end of file
+    #[serde(alias = "lightstreamerEndpoint")]
+    pub lightstreamer_endpoint: Option<String>,
+}
+
+/// OAuth token returned by the v3 login and `session/refresh-token` endpoints
+#[derive(Debug, Clone, Deserialize)]
+pub struct OAuthToken {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub scope: Option<String>,
+    pub token_type: String,
+    /// IG sends this as a string (e.g. `"60"`)
+    #[serde(deserialize_with = "de_seconds")]
+    pub expires_in: i64,
+}
+
+fn de_seconds<'de, D>(deserializer: D) -> Result<i64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .ok_or_else(|| serde::de::Error::custom("expires_in out of range")),
+        serde_json::Value::String(s) => s.trim().parse().map_err(serde::de::Error::custom),
+        other => Err(serde::de::Error::custom(format!(
+            "invalid expires_in: {other}"
+        ))),
+    }
+}
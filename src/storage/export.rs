@@ -0,0 +1,159 @@
+//! Exporting fetched transactions to CSV or JSON lines, e.g. for tax reporting.
+
+use std::io::Write;
+
+use chrono::SecondsFormat;
+use serde::Serialize;
+
+use crate::application::models::transaction::Transaction;
+use crate::error::AppError;
+
+/// Header row written by [`write_transactions_csv`]
+pub const CSV_HEADER: &str = "deal_date,underlying,strike,option_type,expiry,type,pnl,currency,reference";
+
+/// The exported columns of one transaction
+#[derive(Serialize)]
+struct ExportRow<'a> {
+    deal_date: String,
+    underlying: Option<&'a str>,
+    strike: Option<f64>,
+    option_type: Option<&'a str>,
+    expiry: Option<String>,
+    #[serde(rename = "type")]
+    transaction_type: &'a str,
+    pnl: f64,
+    currency: &'a str,
+    reference: &'a str,
+}
+
+impl<'a> From<&'a Transaction> for ExportRow<'a> {
+    fn from(tx: &'a Transaction) -> Self {
+        Self {
+            deal_date: tx.deal_date.to_rfc3339_opts(SecondsFormat::Secs, true),
+            underlying: tx.underlying.as_deref(),
+            strike: tx.strike,
+            option_type: tx.option_type.as_deref(),
+            expiry: tx.expiry.map(|date| date.format("%Y-%m-%d").to_string()),
+            transaction_type: &tx.transaction_type,
+            pnl: tx.pnl,
+            currency: &tx.currency,
+            reference: &tx.reference,
+        }
+    }
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes a [`CSV_HEADER`] row and then one row per transaction, in order.
+/// Missing values are written as empty fields; dates are RFC 3339 in UTC.
+pub fn write_transactions_csv<W: Write>(mut w: W, txs: &[Transaction]) -> Result<(), AppError> {
+    writeln!(w, "{}", CSV_HEADER)?;
+    for tx in txs {
+        let row = ExportRow::from(tx);
+        let fields = [
+            row.deal_date,
+            row.underlying.map(csv_field).unwrap_or_default(),
+            row.strike.map(|strike| strike.to_string()).unwrap_or_default(),
+            row.option_type.map(csv_field).unwrap_or_default(),
+            row.expiry.unwrap_or_default(),
+            csv_field(row.transaction_type),
+            row.pnl.to_string(),
+            csv_field(row.currency),
+            csv_field(row.reference),
+        ];
+        writeln!(w, "{}", fields.join(","))?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+/// Writes one JSON object per line and transaction, with the same fields as
+/// [`write_transactions_csv`]; missing values are `null`.
+pub fn write_transactions_jsonl<W: Write>(mut w: W, txs: &[Transaction]) -> Result<(), AppError> {
+    for tx in txs {
+        serde_json::to_writer(&mut w, &ExportRow::from(tx))?;
+        w.write_all(b"\n")?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests_export {
+    use super::*;
+    use crate::application::models::transaction::Period;
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    fn transactions() -> Vec<Transaction> {
+        let expiry = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        vec![
+            Transaction {
+                deal_date: Utc.with_ymd_and_hms(2025, 5, 13, 10, 0, 0).unwrap(),
+                underlying: Some("US 500".to_string()),
+                strike: Some(5200.0),
+                option_type: Some("PUT".to_string()),
+                expiry: Some(expiry),
+                period: Period::Dated(expiry),
+                transaction_type: "DEAL".to_string(),
+                pnl: 12.5,
+                currency: "EUR".to_string(),
+                reference: "REF1".to_string(),
+                is_fee: false,
+                raw_json: "{}".to_string(),
+                raw_hash: String::new(),
+            },
+            Transaction {
+                deal_date: Utc.with_ymd_and_hms(2025, 5, 14, 0, 0, 0).unwrap(),
+                underlying: None,
+                strike: None,
+                option_type: None,
+                expiry: None,
+                period: Period::None,
+                transaction_type: "WITH".to_string(),
+                pnl: -3.0,
+                currency: "EUR".to_string(),
+                reference: "REF,2".to_string(),
+                is_fee: true,
+                raw_json: "{}".to_string(),
+                raw_hash: String::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_write_transactions_csv() {
+        let mut out = Vec::new();
+        write_transactions_csv(&mut out, &transactions()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "deal_date,underlying,strike,option_type,expiry,type,pnl,currency,reference\n\
+             2025-05-13T10:00:00Z,US 500,5200,PUT,2025-06-01,DEAL,12.5,EUR,REF1\n\
+             2025-05-14T00:00:00Z,,,,,WITH,-3,EUR,\"REF,2\"\n"
+        );
+    }
+
+    #[test]
+    fn test_write_transactions_jsonl() {
+        let mut out = Vec::new();
+        write_transactions_jsonl(&mut out, &transactions()).unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["strike"], 5200.0);
+        assert_eq!(lines[0]["type"], "DEAL");
+        assert!(lines[1]["underlying"].is_null());
+        assert_eq!(lines[1]["reference"], "REF,2");
+    }
+}
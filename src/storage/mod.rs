@@ -1,2 +1,4 @@
+#[cfg(feature = "storage")]
 pub mod utils;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod export;
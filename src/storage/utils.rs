@@ -1,8 +1,41 @@
 
+use crate::application::models::market::MarketSnapshot;
 use crate::application::models::transaction::Transaction;
+use crate::utils::money::price_to_f64;
 use crate::error::AppError;
+use chrono::{DateTime, Utc};
 use sqlx::Executor;  
 
+/// Brings an `ig_options` table created for EUR-only P&L up to date:
+/// renames `pnl_eur` to `pnl` and adds the `currency` column, set to `EUR`
+/// for existing rows. Safe to run on a table that is already up to date.
+const MIGRATE_IG_OPTIONS: &str = r#"
+    DO $$
+    BEGIN
+        IF EXISTS (
+            SELECT 1 FROM information_schema.columns
+            WHERE table_name = 'ig_options' AND column_name = 'pnl_eur'
+        ) THEN
+            ALTER TABLE ig_options RENAME COLUMN pnl_eur TO pnl;
+        END IF;
+    END $$;
+    ALTER TABLE ig_options ADD COLUMN IF NOT EXISTS currency TEXT NOT NULL DEFAULT 'EUR';
+"#;
+
+/// Migrates `ig_options` to the columns [`store_transactions`] writes
+/// (`pnl` and `currency` instead of `pnl_eur`). Run it once before storing
+/// into a database created by an earlier version; it does nothing on an
+/// up-to-date table.
+pub async fn migrate_ig_options(pool: &sqlx::PgPool) -> Result<(), AppError> {
+    pool.execute(MIGRATE_IG_OPTIONS).await?;
+    Ok(())
+}
+
+/// Inserts transactions into `ig_options`, skipping rows whose `raw_hash` is
+/// already stored. Expects the `pnl` and `currency` columns; see
+/// [`migrate_ig_options`].
+///
+/// Returns the number of rows inserted.
 pub async fn store_transactions(
     pool: &sqlx::PgPool,
     txs: &[Transaction],
@@ -17,9 +50,10 @@ pub async fn store_transactions(
                     r#"
                     INSERT INTO ig_options (
                         reference, deal_date, underlying, strike,
-                        option_type, expiry, transaction_type, pnl_eur, is_fee, raw
+                        option_type, expiry, transaction_type, pnl, currency, is_fee, raw,
+                        raw_hash
                     )
-                    VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)
+                    VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12)
                     ON CONFLICT (raw_hash) DO NOTHING
                     "#
                 )
@@ -30,9 +64,69 @@ pub async fn store_transactions(
                     .bind(&t.option_type)
                     .bind(t.expiry)
                     .bind(&t.transaction_type)
-                    .bind(t.pnl_eur)
+                    .bind(t.pnl)
+                    .bind(&t.currency)
                     .bind(t.is_fee)
-                    .bind(&t.raw_json),
+                    .bind(&t.raw_json)
+                    .bind(&t.raw_hash),
+            )
+            .await?;
+
+        inserted += result.rows_affected() as usize;
+    }
+
+    tx.commit().await?;
+    Ok(inserted)
+}
+
+/// Most recent `deal_date` stored in `ig_options`, or `None` if the table is
+/// empty. Used as the cursor for incremental transaction fetching.
+pub async fn latest_transaction_date(
+    pool: &sqlx::PgPool,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+    let latest: Option<DateTime<Utc>> = sqlx::query_scalar("SELECT MAX(deal_date) FROM ig_options")
+        .fetch_one(pool)
+        .await?;
+    Ok(latest)
+}
+
+/// Upserts market snapshots for `epic` into `ig_market_snapshots`, keyed on
+/// `(epic, update_time)`, inside a single transaction.
+///
+/// Returns the number of rows inserted or updated.
+pub async fn store_market_snapshots(
+    pool: &sqlx::PgPool,
+    epic: &str,
+    snapshots: &[MarketSnapshot],
+) -> Result<usize, AppError> {
+    let mut tx = pool.begin().await?;
+    let mut inserted = 0;
+
+    for s in snapshots {
+        let result = tx
+            .execute(
+                sqlx::query(
+                    r#"
+                    INSERT INTO ig_market_snapshots (
+                        epic, bid, offer, update_time, market_status,
+                        net_change, percentage_change
+                    )
+                    VALUES ($1,$2,$3,$4,$5,$6,$7)
+                    ON CONFLICT (epic, update_time) DO UPDATE SET
+                        bid = EXCLUDED.bid,
+                        offer = EXCLUDED.offer,
+                        market_status = EXCLUDED.market_status,
+                        net_change = EXCLUDED.net_change,
+                        percentage_change = EXCLUDED.percentage_change
+                    "#
+                )
+                    .bind(epic)
+                    .bind(s.bid.map(price_to_f64))
+                    .bind(s.offer.map(price_to_f64))
+                    .bind(&s.update_time)
+                    .bind(s.market_status.as_str())
+                    .bind(s.net_change)
+                    .bind(s.percentage_change),
             )
             .await?;
 
@@ -41,4 +135,4 @@ pub async fn store_transactions(
 
     tx.commit().await?;
     Ok(inserted)
-}
\ No newline at end of file
+}
@@ -0,0 +1,75 @@
+//! Test doubles for code built on this crate, available with the `testing`
+//! feature so tests can run without IG credentials or network access.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use async_trait::async_trait;
+
+use crate::error::AuthError;
+use crate::session::interface::{IgAuthenticator, IgSession};
+
+/// In-memory [`IgAuthenticator`]: `login` returns a canned session and
+/// `refresh` returns it with a rotated security token (`XST-1`, `XST-2`...).
+#[derive(Debug)]
+pub struct MockAuthenticator {
+    session: IgSession,
+    logins: AtomicU32,
+    refreshes: AtomicU32,
+}
+
+impl Default for MockAuthenticator {
+    fn default() -> Self {
+        Self::with_session(IgSession {
+            cst: "CST".to_string(),
+            token: "XST".to_string(),
+            account_id: "ACC1".to_string(),
+            lightstreamer_endpoint: None,
+        })
+    }
+}
+
+impl MockAuthenticator {
+    /// Authenticator for account `ACC1` with tokens `CST` / `XST`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Authenticator whose `login` returns `session`
+    pub fn with_session(session: IgSession) -> Self {
+        Self {
+            session,
+            logins: AtomicU32::new(0),
+            refreshes: AtomicU32::new(0),
+        }
+    }
+
+    /// Number of `login` calls so far
+    pub fn login_count(&self) -> u32 {
+        self.logins.load(Ordering::SeqCst)
+    }
+
+    /// Number of `refresh` calls so far
+    pub fn refresh_count(&self) -> u32 {
+        self.refreshes.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl IgAuthenticator for MockAuthenticator {
+    async fn login(&self) -> Result<IgSession, AuthError> {
+        self.logins.fetch_add(1, Ordering::SeqCst);
+        Ok(self.session.clone())
+    }
+
+    async fn refresh(&self, session: &IgSession) -> Result<IgSession, AuthError> {
+        let n = self.refreshes.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(IgSession {
+            token: format!("{}-{}", self.session.token, n),
+            ..session.clone()
+        })
+    }
+
+    async fn logout(&self, _session: &IgSession) -> Result<(), AuthError> {
+        Ok(())
+    }
+}
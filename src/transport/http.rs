@@ -0,0 +1,155 @@
+//! Construction of the `reqwest` clients used across the crate, so that the
+//! REST services, the authenticator and the transaction client all share the
+//! same user agent, timeout, proxy and TLS settings.
+
+use std::time::Duration;
+
+use reqwest::{Certificate, Client, ClientBuilder, NoProxy, Proxy};
+
+use crate::config::{Config, RestApiConfig};
+use crate::error::AppError;
+
+/// `User-Agent` sent with every request unless `rest_api.user_agent` overrides it
+pub const USER_AGENT: &str = concat!("ig-client/", env!("CARGO_PKG_VERSION"));
+
+/// Builds the HTTP client for `config`.
+///
+/// # Panics
+///
+/// Panics if the proxy URL or CA bundle in `config.rest_api` cannot be used;
+/// see [`try_build_client`] for a fallible version.
+pub fn build_client(config: &Config) -> Client {
+    try_build_client(config).expect("Failed to create HTTP client")
+}
+
+/// Builds the HTTP client for `config`, reporting an unusable proxy URL or CA bundle
+pub fn try_build_client(config: &Config) -> Result<Client, AppError> {
+    let builder = Client::builder()
+        .user_agent(config.rest_api.user_agent.as_deref().unwrap_or(USER_AGENT))
+        .timeout(Duration::from_secs(config.rest_api.timeout))
+        .connect_timeout(Duration::from_secs(config.rest_api.connect_timeout));
+    Ok(configure_client(builder, &config.rest_api)?.build()?)
+}
+
+/// Applies the proxy in `config.proxy_url` (minus the hosts in
+/// `config.no_proxy`) and the extra root certificates in `config.ca_bundle`
+fn configure_client(builder: ClientBuilder, config: &RestApiConfig) -> Result<ClientBuilder, AppError> {
+    let mut builder = builder;
+    if let Some(proxy_url) = config.proxy_url.as_deref() {
+        let proxy = Proxy::all(proxy_url)?
+            .no_proxy(config.no_proxy.as_deref().and_then(NoProxy::from_string));
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = config.ca_bundle.as_deref() {
+        let pem = std::fs::read(path)?;
+        for certificate in Certificate::from_pem_bundle(&pem)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests_http {
+    use super::*;
+    use crate::transport::mock::{MockResponse, MockServer};
+
+    #[tokio::test]
+    async fn test_user_agent_and_timeout_are_applied() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, "ok"),
+            MockResponse::new(200, "late").with_delay(Duration::from_millis(1500)),
+        ]);
+        let mut config = Config::new();
+        config.rest_api.timeout = 1;
+        let client = build_client(&config);
+
+        client.get(server.url()).send().await.unwrap();
+        assert_eq!(server.requests()[0].header("User-Agent"), Some(USER_AGENT));
+        assert!(USER_AGENT.starts_with("ig-client/"));
+
+        let err = client.get(server.url()).send().await.unwrap_err();
+        assert!(err.is_timeout());
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_applies_to_unanswered_connect() {
+        // A listener whose accept queue is full leaves further handshakes unanswered
+        let socket = tokio::net::TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener = socket.listen(1).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut queued = Vec::new();
+        while let Ok(Ok(stream)) =
+            tokio::time::timeout(Duration::from_millis(200), tokio::net::TcpStream::connect(addr)).await
+        {
+            queued.push(stream);
+        }
+        let mut config = Config::new();
+        config.rest_api.timeout = 30;
+        config.rest_api.connect_timeout = 1;
+        let client = build_client(&config);
+        let started = std::time::Instant::now();
+
+        let err = client.get(format!("http://{}", addr)).send().await.unwrap_err();
+
+        assert!(err.is_connect() || err.is_timeout(), "{:?}", err);
+        // Well under the 30s overall timeout
+        assert!(started.elapsed() < Duration::from_secs(10), "{:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn test_custom_user_agent_is_applied() {
+        use crate::transport::http_client::{IgHttpClient, IgHttpClientImpl};
+        use crate::transport::mock::test_session;
+        use reqwest::Method;
+        use std::sync::Arc;
+
+        let server = MockServer::start(vec![MockResponse::json(200, serde_json::json!({"accounts": []}))]);
+        let mut config = Config::new();
+        config.rest_api.base_url = server.url();
+        config.rest_api.user_agent = Some("my-bot/2.0 (support@example.com)".to_string());
+        let client = IgHttpClientImpl::new(Arc::new(config));
+
+        let _: serde_json::Value = client
+            .request::<(), _>(Method::GET, "accounts", &test_session(), None, "1")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            server.requests()[0].header("User-Agent"),
+            Some("my-bot/2.0 (support@example.com)")
+        );
+    }
+
+    #[test]
+    fn test_no_proxy_list_is_accepted() {
+        let mut config = Config::new();
+        config.rest_api.proxy_url = Some("http://proxy.internal:3128".to_string());
+        config.rest_api.no_proxy = Some("localhost,.internal".to_string());
+        assert!(configure_client(Client::builder(), &config.rest_api).unwrap().build().is_ok());
+
+        config.rest_api.proxy_url = Some("not a url".to_string());
+        assert!(configure_client(Client::builder(), &config.rest_api).is_err());
+    }
+
+    const TEST_CA_PEM: &str = "-----BEGIN CERTIFICATE-----\nMIIBkDCCATWgAwIBAgIUSE4IHAcuCHp6hpnoEa6Y6AxXAn8wCgYIKoZIzj0EAwIw\nHDEaMBgGA1UEAwwRaWctY2xpZW50IHRlc3QgQ0EwIBcNMjYxMDE2MTU1MTA5WhgP\nMjEyNjA5MjIxNTUxMDlaMBwxGjAYBgNVBAMMEWlnLWNsaWVudCB0ZXN0IENBMFkw\nEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEBLXnBKX+zz10QqizAujVI7E1RVVHfEjC\nLzg44lsb0jnMYSWGio6/J5FS9t+lxkp4ffbAHlqI6FwBF8LtVM6BRKNTMFEwHQYD\nVR0OBBYEFDzylBpQGTm83PjpO46UaEOPw49XMB8GA1UdIwQYMBaAFDzylBpQGTm8\n3PjpO46UaEOPw49XMA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSQAwRgIh\nAOZdnwjzozh8EAjN3tzZzvuo3Q3Itwd2RxJ4C9takroRAiEAx7AxN2Jb5QakB5Ic\ni79+LxIwNJzIVs2iL6r4QQ5Sbg4=\n-----END CERTIFICATE-----";
+
+    #[test]
+    fn test_ca_bundle_is_loaded() {
+        let path = std::env::temp_dir().join(format!("ig_client_{}_ca.pem", std::process::id()));
+        std::fs::write(&path, TEST_CA_PEM).unwrap();
+        let mut config = Config::new();
+        config.rest_api.ca_bundle = Some(path.to_string_lossy().into_owned());
+
+        let builder = configure_client(Client::builder(), &config.rest_api);
+        std::fs::remove_file(&path).unwrap();
+        assert!(builder.unwrap().build().is_ok());
+
+        config.rest_api.ca_bundle = Some("/nonexistent/ca.pem".to_string());
+        assert!(matches!(
+            configure_client(Client::builder(), &config.rest_api),
+            Err(AppError::Io(_))
+        ));
+    }
+}
@@ -1,15 +1,112 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
 use serde::{de::DeserializeOwned, Serialize};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
     config::Config,
+    constants::DEFAULT_HTTP_MAX_RETRIES,
     error::AppError,
     session::interface::IgSession,
+    transport::http::build_client,
+    transport::metrics::{Metrics, NoopMetrics},
+    transport::rate_limiter::RateLimiter,
+    utils::json::decode_json,
 };
 
+/// Delay before the first retry of a failed GET; doubled on every further attempt
+const RETRY_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Longest `Retry-After` a rate-limited GET waits out before retrying; a
+/// longer one is returned to the caller as `AppError::RateLimited`
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Per-request overrides for [`IgHttpClient::request_with_options`]
+#[derive(Debug, Clone)]
+pub struct RequestOptions {
+    /// Overrides the client-wide timeout from `config.rest_api.timeout`
+    pub timeout: Option<Duration>,
+    /// Retries on transient network errors. Only applied to GET requests;
+    /// POST/PUT/DELETE are never retried.
+    pub max_retries: u32,
+    /// Sent as the `_method` header, for operations IG only accepts as a
+    /// POST carrying the real verb, such as closing an OTC position
+    pub method_override: Option<Method>,
+    /// Headers set on top of the standard and session headers, replacing
+    /// any of them with the same name
+    pub extra_headers: HashMap<String, String>,
+    /// Lets `extra_headers` replace `X-IG-API-KEY`
+    pub allow_api_key_override: bool,
+}
+
+impl Default for RequestOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            max_retries: DEFAULT_HTTP_MAX_RETRIES,
+            method_override: None,
+            extra_headers: HashMap::new(),
+            allow_api_key_override: false,
+        }
+    }
+}
+
+impl RequestOptions {
+    /// Sets a timeout for this request only
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the number of retries for idempotent requests
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sends `method` in the `_method` header
+    pub fn with_method_override(mut self, method: Method) -> Self {
+        self.method_override = Some(method);
+        self
+    }
+
+    /// Adds a header, e.g. `X-DEVICE-USER-AGENT`, replacing a standard one of the same name
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extra_headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Allows an extra header to replace the configured API key
+    pub fn with_api_key_override(mut self) -> Self {
+        self.allow_api_key_override = true;
+        self
+    }
+
+    /// The extra headers, checked for validity and for an unapproved API key override
+    fn header_map(&self) -> Result<HeaderMap, AppError> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.extra_headers {
+            let invalid = |e: &dyn std::fmt::Display| {
+                AppError::ConfigError(format!("invalid header {}: {}", name, e))
+            };
+            let header_name = HeaderName::try_from(name.as_str()).map_err(|e| invalid(&e))?;
+            if header_name == "x-ig-api-key" && !self.allow_api_key_override {
+                return Err(AppError::ConfigError(
+                    "extra headers may not replace X-IG-API-KEY unless the override is allowed".to_string(),
+                ));
+            }
+            let header_value = HeaderValue::try_from(value.as_str()).map_err(|e| invalid(&e))?;
+            headers.insert(header_name, header_value);
+        }
+        Ok(headers)
+    }
+}
+
 /// Interface for the IG HTTP client
 #[async_trait]
 pub trait IgHttpClient: Send + Sync {
@@ -26,6 +123,29 @@ pub trait IgHttpClient: Send + Sync {
         for<'de> R: DeserializeOwned + 'static,
         T: Serialize + Send + Sync + 'static;
 
+    /// Makes an HTTP request to the IG API with per-request options.
+    ///
+    /// The default implementation ignores the options and calls [`request`](Self::request).
+    async fn request_with_options<T, R>(
+        &self,
+        method: Method,
+        path: &str,
+        session: &IgSession,
+        body: Option<&T>,
+        version: &str,
+        _options: &RequestOptions,
+    ) -> Result<R, AppError>
+    where
+        for<'de> R: DeserializeOwned + 'static,
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.request(method, path, session, body, version).await
+    }
+
+    /// Lowers the client's rate limits to these requests-per-minute
+    /// allowances where they are stricter. The default implementation does nothing.
+    fn apply_rate_allowance(&self, _trading_per_minute: u32, _non_trading_per_minute: u32) {}
+
     /// Makes an unauthenticated HTTP request (for login)
     async fn request_no_auth<T, R>(
         &self,
@@ -39,31 +159,74 @@ pub trait IgHttpClient: Send + Sync {
         T: Serialize + Send + Sync + 'static;
 }
 
+/// Sink used when no metrics are configured
+static NOOP_METRICS: NoopMetrics = NoopMetrics;
+
 /// Implementación del cliente HTTP para IG
 pub struct IgHttpClientImpl {
     config: Arc<Config>,
     client: Client,
+    limiter: RateLimiter,
+    metrics: Option<Arc<dyn Metrics>>,
 }
 
 impl IgHttpClientImpl {
     /// Crea una nueva instancia del cliente HTTP
+    ///
+    /// # Panics
+    ///
+    /// Panics if the proxy URL or CA bundle cannot be used; call
+    /// [`Config::validate`] first to get these as an error instead.
     pub fn new(config: Arc<Config>) -> Self {
-        let client = Client::builder()
-            .user_agent("ig-client/0.1.0")
-            .timeout(std::time::Duration::from_secs(config.rest_api.timeout))
-            .build()
-            .expect("Failed to create HTTP client");
+        let client = build_client(&config);
+        let limiter = RateLimiter::new(&config.rest_api);
 
-        Self { config, client }
+        Self {
+            config,
+            client,
+            limiter,
+            metrics: None,
+        }
+    }
+
+    /// Reports every request, response and error to `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    fn metrics(&self) -> &dyn Metrics {
+        self.metrics.as_deref().unwrap_or(&NOOP_METRICS)
+    }
+
+    /// Sends the request and decodes the response, reporting both to the metrics hook
+    async fn send_measured<R>(
+        &self,
+        method: &Method,
+        path: &str,
+        builder: RequestBuilder,
+    ) -> Result<R, AppError>
+    where
+        R: DeserializeOwned,
+    {
+        self.metrics().on_request(method, path);
+        let started = Instant::now();
+        let result = match builder.send().await {
+            Ok(response) => {
+                self.metrics().on_response(response.status(), started.elapsed());
+                self.process_response::<R>(response).await
+            }
+            Err(e) => Err(AppError::from(e)),
+        };
+        if let Err(e) = &result {
+            self.metrics().on_error(e);
+        }
+        result
     }
 
     /// Construye la URL completa para una petición
     fn build_url(&self, path: &str) -> String {
-        format!(
-            "{}/{}",
-            self.config.rest_api.base_url.trim_end_matches('/'),
-            path.trim_start_matches('/')
-        )
+        self.config.rest_api.endpoint(path)
     }
 
     /// Añade los headers comunes a todas las peticiones
@@ -82,6 +245,36 @@ impl IgHttpClientImpl {
             .header("X-SECURITY-TOKEN", &session.token)
     }
 
+    /// Network failures worth retrying for idempotent requests
+    fn is_transient(error: &AppError) -> bool {
+        matches!(error, AppError::Network(e) if e.is_timeout() || e.is_connect())
+    }
+
+    /// Reads how long to back off after a 429 from `Retry-After` (seconds or
+    /// HTTP date) or, failing that, `X-RateLimit-Reset` (seconds, or a Unix
+    /// timestamp in seconds or milliseconds)
+    fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+        let now = Utc::now();
+
+        if let Some(value) = header("Retry-After") {
+            if let Ok(secs) = value.parse::<u64>() {
+                return Some(Duration::from_secs(secs));
+            }
+            if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+                return Some((date.with_timezone(&Utc) - now).to_std().unwrap_or(Duration::ZERO));
+            }
+        }
+
+        let reset = header("X-RateLimit-Reset")?.parse::<i64>().ok()?;
+        let reset_at = match reset {
+            r if r >= 1_000_000_000_000 => chrono::DateTime::from_timestamp_millis(r)?,
+            r if r >= 1_000_000_000 => chrono::DateTime::from_timestamp(r, 0)?,
+            r => return Some(Duration::from_secs(r.max(0) as u64)),
+        };
+        Some((reset_at - now).to_std().unwrap_or(Duration::ZERO))
+    }
+
     /// Procesa la respuesta HTTP
     async fn process_response<R>(&self, response: Response) -> Result<R, AppError>
     where
@@ -91,8 +284,10 @@ impl IgHttpClientImpl {
         let url = response.url().to_string();
 
         match status {
-            StatusCode::OK | StatusCode::CREATED | StatusCode::ACCEPTED => {
-                let json = response.json::<R>().await?;
+            status if status.is_success() => {
+                let body = response.text().await?;
+                let json = decode_json::<R>(&body)
+                    .inspect_err(|e| error!("Could not decode response from {}: {}", url, e))?;
                 debug!("Request to {} successful", url);
                 Ok(json)
             }
@@ -105,8 +300,9 @@ impl IgHttpClientImpl {
                 Err(AppError::NotFound)
             }
             StatusCode::TOO_MANY_REQUESTS => {
-                error!("Rate limit exceeded for {}", url);
-                Err(AppError::RateLimitExceeded)
+                let retry_after = Self::retry_after(response.headers());
+                error!("Rate limit exceeded for {}, retry after {:?}", url, retry_after);
+                Err(AppError::RateLimited { retry_after })
             }
             _ => {
                 let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
@@ -119,6 +315,10 @@ impl IgHttpClientImpl {
 
 #[async_trait]
 impl IgHttpClient for IgHttpClientImpl {
+    fn apply_rate_allowance(&self, trading_per_minute: u32, non_trading_per_minute: u32) {
+        self.limiter.tighten(trading_per_minute, non_trading_per_minute);
+    }
+
     async fn request<T, R>(
         &self,
         method: Method,
@@ -127,23 +327,84 @@ impl IgHttpClient for IgHttpClientImpl {
         body: Option<&T>,
         version: &str,
     ) -> Result<R, AppError>
+    where
+        for<'de> R: DeserializeOwned + 'static,
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.request_with_options(method, path, session, body, version, &RequestOptions::default())
+            .await
+    }
+
+    async fn request_with_options<T, R>(
+        &self,
+        method: Method,
+        path: &str,
+        session: &IgSession,
+        body: Option<&T>,
+        version: &str,
+        options: &RequestOptions,
+    ) -> Result<R, AppError>
     where
         for<'de> R: DeserializeOwned + 'static,
         T: Serialize + Send + Sync + 'static,
     {
         let url = self.build_url(path);
-        info!("Making {} request to {}", method, url);
+        let extra_headers = options.header_map()?;
+        let max_retries = if method == Method::GET { options.max_retries } else { 0 };
+        let mut backoff = RETRY_INITIAL_BACKOFF;
+        let mut attempt = 0;
+        let mut rate_limit_retried = false;
 
-        let mut builder = self.client.request(method, &url);
-        builder = self.add_common_headers(builder, version);
-        builder = self.add_auth_headers(builder, session);
+        loop {
+            self.limiter.acquire(&method, path).await;
+            info!("Making {} request to {}", method, url);
 
-        if let Some(data) = body {
-            builder = builder.json(data);
-        }
+            let mut builder = self.client.request(method.clone(), &url);
+            builder = self.add_common_headers(builder, version);
+            builder = self.add_auth_headers(builder, session);
 
-        let response = builder.send().await?;
-        self.process_response::<R>(response).await
+            if let Some(timeout) = options.timeout {
+                builder = builder.timeout(timeout);
+            }
+            if let Some(method_override) = &options.method_override {
+                builder = builder.header("_method", method_override.as_str());
+            }
+            if !extra_headers.is_empty() {
+                // Replaces, rather than appends to, headers already set
+                builder = builder.headers(extra_headers.clone());
+            }
+            if let Some(data) = body {
+                builder = builder.json(data);
+            }
+
+            let error = match self.send_measured::<R>(&method, path, builder).await {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+            // A GET that hit the rate limit is retried once, if IG says when
+            // within reason; other methods are left to the caller
+            if let AppError::RateLimited { retry_after: Some(wait) } = error
+                && method == Method::GET
+                && wait <= MAX_RATE_LIMIT_WAIT
+                && !rate_limit_retried
+            {
+                rate_limit_retried = true;
+                warn!("{} {} rate limited, retrying in {:?}", method, url, wait);
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+            if attempt >= max_retries || !Self::is_transient(&error) {
+                return Err(error);
+            }
+
+            attempt += 1;
+            warn!(
+                "{} {} failed ({}), retry {}/{} in {:?}",
+                method, url, error, attempt, max_retries, backoff
+            );
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
     }
 
     async fn request_no_auth<T, R>(
@@ -158,16 +419,279 @@ impl IgHttpClient for IgHttpClientImpl {
         T: Serialize + Send + Sync + 'static,
     {
         let url = self.build_url(path);
+        self.limiter.acquire(&method, path).await;
         info!("Making unauthenticated {} request to {}", method, url);
 
-        let mut builder = self.client.request(method, &url);
+        let mut builder = self.client.request(method.clone(), &url);
         builder = self.add_common_headers(builder, version);
 
         if let Some(data) = body {
             builder = builder.json(data);
         }
 
-        let response = builder.send().await?;
-        self.process_response::<R>(response).await
+        self.send_measured::<R>(&method, path, builder).await
+    }
+}
+
+#[cfg(test)]
+mod tests_http_client {
+    use super::*;
+    use crate::transport::mock::{test_session, MockResponse, MockServer};
+    use serde_json::json;
+
+    fn client_for(server: &MockServer) -> IgHttpClientImpl {
+        let mut config = Config::new();
+        config.rest_api.base_url = server.url();
+        IgHttpClientImpl::new(Arc::new(config))
+    }
+
+    #[tokio::test]
+    async fn test_extra_headers_merge_over_standard_ones() {
+        let server = MockServer::start(vec![MockResponse::json(200, json!({}))]);
+        let client = client_for(&server);
+        let options = RequestOptions::default()
+            .with_header("X-DEVICE-USER-AGENT", "my-bot")
+            .with_header("X-SECURITY-TOKEN", "MIGRATED");
+
+        let _: serde_json::Value = client
+            .request_with_options::<(), _>(Method::GET, "accounts", &test_session(), None, "1", &options)
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(request.header("X-DEVICE-USER-AGENT"), Some("my-bot"));
+        assert_eq!(request.header("X-SECURITY-TOKEN"), Some("MIGRATED"));
+        assert_eq!(request.header("CST"), Some("CST"));
+        assert_eq!(request.header("Version"), Some("1"));
+        assert_eq!(
+            request.headers.iter().filter(|(name, _)| name == "x-security-token").count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_key_override_needs_opt_in() {
+        let server = MockServer::start(vec![MockResponse::json(200, json!({}))]);
+        let client = client_for(&server);
+        let options = RequestOptions::default().with_header("x-ig-api-key", "OTHER");
+
+        let result = client
+            .request_with_options::<(), serde_json::Value>(Method::GET, "accounts", &test_session(), None, "1", &options)
+            .await;
+        assert!(matches!(result, Err(AppError::ConfigError(_))));
+        assert!(server.requests().is_empty());
+
+        let options = options.with_api_key_override();
+        let _: serde_json::Value = client
+            .request_with_options::<(), _>(Method::GET, "accounts", &test_session(), None, "1", &options)
+            .await
+            .unwrap();
+        assert_eq!(server.requests()[0].header("X-IG-API-KEY"), Some("OTHER"));
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_proxy() {
+        let proxy = MockServer::start(vec![MockResponse::json(200, json!({"ok": true}))]);
+        let mut config = Config::new();
+        config.rest_api.base_url = "http://ig.invalid/gateway/deal".to_string();
+        config.rest_api.proxy_url = Some(proxy.url());
+        let client = IgHttpClientImpl::new(Arc::new(config));
+
+        let result: serde_json::Value = client
+            .request::<(), _>(Method::GET, "markets", &test_session(), None, "1")
+            .await
+            .unwrap();
+
+        assert_eq!(result, json!({"ok": true}));
+        // A forward proxy receives the absolute URL
+        assert_eq!(proxy.requests()[0].path, "http://ig.invalid/gateway/deal/markets");
+    }
+
+    #[tokio::test]
+    async fn test_decode_error_names_field_and_body() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Quote {
+            #[allow(dead_code)]
+            bid: f64,
+        }
+        #[derive(Debug, serde::Deserialize)]
+        struct Quotes {
+            #[allow(dead_code)]
+            markets: Vec<Quote>,
+        }
+
+        let server = MockServer::start(vec![MockResponse::json(
+            200,
+            json!({"markets": [{"bid": 1.5}, {"bid": "n/a"}]}),
+        )]);
+        let client = client_for(&server);
+
+        let result = client
+            .request::<(), Quotes>(Method::GET, "markets", &test_session(), None, "1")
+            .await;
+
+        let Err(AppError::SerializationError(message)) = result else {
+            panic!("expected a serialization error, got {:?}", result);
+        };
+        assert!(message.contains("markets[1].bid"), "{message}");
+        assert!(message.contains(r#""bid":"n/a""#), "{message}");
+    }
+
+    #[tokio::test]
+    async fn test_metrics_record_success_and_error() {
+        use crate::transport::metrics::CountingMetrics;
+
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!({"ok": true})),
+            MockResponse::new(500, "boom"),
+        ]);
+        let metrics = Arc::new(CountingMetrics::new());
+        let client = client_for(&server).with_metrics(metrics.clone());
+
+        client
+            .request::<(), serde_json::Value>(Method::GET, "markets", &test_session(), None, "1")
+            .await
+            .unwrap();
+        let result = client
+            .request::<(), serde_json::Value>(Method::GET, "accounts", &test_session(), None, "1")
+            .await;
+
+        assert!(matches!(result, Err(AppError::Unexpected(StatusCode::INTERNAL_SERVER_ERROR))));
+        assert_eq!(metrics.requests(), 2);
+        assert_eq!(metrics.successes(), 1);
+        assert_eq!(metrics.errors(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_retries_after_timeout() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!({"ok": false})).with_delay(Duration::from_millis(500)),
+            MockResponse::json(200, json!({"ok": true})),
+        ]);
+        let client = client_for(&server);
+        let options = RequestOptions::default()
+            .with_timeout(Duration::from_millis(100))
+            .with_max_retries(1);
+
+        let result: serde_json::Value = client
+            .request_with_options::<(), _>(Method::GET, "markets", &test_session(), None, "1", &options)
+            .await
+            .unwrap();
+
+        assert_eq!(result, json!({"ok": true}));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|r| r.method == "GET" && r.path == "/gateway/deal/markets"));
+    }
+
+    #[tokio::test]
+    async fn test_post_is_not_retried() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!({"dealReference": "REF1"}))
+                .with_delay(Duration::from_millis(500)),
+            MockResponse::json(200, json!({"dealReference": "REF2"})),
+        ]);
+        let client = client_for(&server);
+        let options = RequestOptions::default()
+            .with_timeout(Duration::from_millis(100))
+            .with_max_retries(3);
+
+        let result = client
+            .request_with_options::<_, serde_json::Value>(
+                Method::POST,
+                "positions/otc",
+                &test_session(),
+                Some(&json!({"epic": "X"})),
+                "2",
+                &options,
+            )
+            .await;
+
+        assert!(matches!(result, Err(AppError::Network(ref e)) if e.is_timeout()));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_retry_after_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(IgHttpClientImpl::retry_after(&headers), None);
+
+        headers.insert("X-RateLimit-Reset", "5".parse().unwrap());
+        assert_eq!(IgHttpClientImpl::retry_after(&headers), Some(Duration::from_secs(5)));
+
+        // Retry-After takes precedence
+        headers.insert("Retry-After", "2".parse().unwrap());
+        assert_eq!(IgHttpClientImpl::retry_after(&headers), Some(Duration::from_secs(2)));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_retries_once_after_retry_after() {
+        let server = MockServer::start(vec![
+            MockResponse::new(429, "").with_header("Retry-After", "0"),
+            MockResponse::json(200, json!({"ok": true})),
+        ]);
+        let client = client_for(&server);
+
+        let result: serde_json::Value = client
+            .request::<(), _>(Method::GET, "accounts", &test_session(), None, "1")
+            .await
+            .unwrap();
+
+        assert_eq!(result, json!({"ok": true}));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_post_is_not_retried() {
+        let server = MockServer::start(vec![
+            MockResponse::new(429, "").with_header("Retry-After", "0"),
+            MockResponse::json(200, json!({"dealReference": "REF1"})),
+        ]);
+        let client = client_for(&server);
+
+        let result = client
+            .request::<_, serde_json::Value>(Method::POST, "positions/otc", &test_session(), Some(&json!({})), "2")
+            .await;
+
+        assert!(matches!(result, Err(AppError::RateLimited { .. })));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_long_retry_after_is_not_waited_out() {
+        let server = MockServer::start(vec![
+            MockResponse::new(429, "").with_header("Retry-After", "3600"),
+            MockResponse::json(200, json!({"ok": true})),
+        ]);
+        let client = client_for(&server);
+
+        let result = client
+            .request::<(), serde_json::Value>(Method::GET, "accounts", &test_session(), None, "1")
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(AppError::RateLimited { retry_after: Some(d) }) if d == Duration::from_secs(3600)
+        ));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_surfaces_retry_after() {
+        let server = MockServer::start(vec![
+            MockResponse::new(429, "").with_header("Retry-After", "0"),
+            MockResponse::new(429, "").with_header("Retry-After", "2"),
+        ]);
+        let client = client_for(&server);
+
+        let result = client
+            .request::<(), serde_json::Value>(Method::GET, "accounts", &test_session(), None, "1")
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(AppError::RateLimited { retry_after: Some(d) }) if d == Duration::from_secs(2)
+        ));
+        assert_eq!(server.requests().len(), 2);
     }
 }
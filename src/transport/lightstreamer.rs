@@ -0,0 +1,296 @@
+//! Decoding of Lightstreamer real-time update lines (`U,<subId>,<item>,<v1>|<v2>|...`),
+//! subscription acknowledgements (`SUBOK`, `SUBCMD`, `REQERR`) and session
+//! errors (`CONERR,<code>,<message>`).
+
+use std::fmt;
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+
+use crate::error::AppError;
+use crate::transport::model::SubscriptionStatus;
+
+/// Fields requested for `MARKET:{epic}` subscriptions, in schema order
+pub const MARKET_FIELDS: [&str; 3] = ["BID", "OFFER", "UPDATE_TIME"];
+
+/// Fields requested for `CHART:{epic}:{scale}` subscriptions, in schema order
+pub const CHART_FIELDS: [&str; 11] = [
+    "UTM",
+    "LTV",
+    "BID_OPEN",
+    "BID_HIGH",
+    "BID_LOW",
+    "BID_CLOSE",
+    "OFR_OPEN",
+    "OFR_HIGH",
+    "OFR_LOW",
+    "OFR_CLOSE",
+    "CONS_END",
+];
+
+/// Fields requested for `ACCOUNT:{accountId}` subscriptions, in schema order
+pub const ACCOUNT_FIELDS: [&str; 5] = ["PNL", "DEPOSIT", "AVAILABLE_CASH", "FUNDS", "MARGIN"];
+
+/// Fields requested for `TRADE:{accountId}` subscriptions, in schema order
+pub const TRADE_FIELDS: [&str; 3] = ["CONFIRMS", "OPU", "WOU"];
+
+/// One decoded update line
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemUpdate {
+    /// Subscription the update belongs to
+    pub subscription_id: String,
+    /// 1-based index of the item within the subscription
+    pub item: usize,
+    /// Field values in schema order. `None` means unchanged since the last
+    /// update, or null.
+    pub fields: Vec<Option<String>>,
+}
+
+impl ItemUpdate {
+    /// Value of the field at `index` parsed as a number
+    pub fn number(&self, index: usize) -> Option<f64> {
+        self.fields.get(index)?.as_deref()?.parse().ok()
+    }
+}
+
+/// Magic bytes that start a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decodes a binary frame into the same text a text frame would carry,
+/// gunzipping it first if it is gzip-compressed
+pub fn decode_binary(bytes: &[u8]) -> Result<String, AppError> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        let mut text = String::new();
+        GzDecoder::new(bytes).read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        String::from_utf8(bytes.to_vec())
+            .map_err(|e| AppError::WebSocketError(format!("binary frame is not UTF-8: {}", e)))
+    }
+}
+
+/// Parses an update line; returns `None` for any other kind of line
+pub fn parse_update(line: &str) -> Option<ItemUpdate> {
+    let rest = line.trim_end_matches(['\r', '\n']).strip_prefix("U,")?;
+    let mut parts = rest.splitn(3, ',');
+    let subscription_id = parts.next()?.to_string();
+    let item = parts.next()?.parse().ok()?;
+    let fields = parts.next()?.split('|').map(decode_value).collect();
+
+    Some(ItemUpdate {
+        subscription_id,
+        item,
+        fields,
+    })
+}
+
+/// Session refused or ended by the server with a `CONERR` or `ERROR` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightstreamerError {
+    /// Lightstreamer error code, e.g. 1 for rejected credentials
+    pub code: i32,
+    /// Server-provided description
+    pub message: String,
+}
+
+impl LightstreamerError {
+    /// Whether the server rejected the CST/XST credentials. Retrying with the
+    /// same session will not help; the caller has to log in again.
+    pub fn is_auth_failure(&self) -> bool {
+        self.code == 1
+    }
+}
+
+impl fmt::Display for LightstreamerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Lightstreamer error {}: {}", self.code, self.message)
+    }
+}
+
+/// Parses the first `CONERR,<code>,<message>` or `ERROR,<code>,<message>`
+/// line of a server frame
+pub fn parse_conerr(text: &str) -> Option<LightstreamerError> {
+    text.lines().find_map(|line| {
+        let line = line.trim_end_matches('\r');
+        let rest = line.strip_prefix("CONERR,").or_else(|| line.strip_prefix("ERROR,"))?;
+        let (code, message) = rest.split_once(',').unwrap_or((rest, ""));
+        Some(LightstreamerError {
+            code: code.trim().parse().ok()?,
+            message: percent_decode(message),
+        })
+    })
+}
+
+/// Parses a subscription acknowledgement line into the subscription id and
+/// its new status. `REQERR` lines carry the request id, which the client sets
+/// to the subscription id.
+pub fn parse_subscription_status(line: &str) -> Option<(String, SubscriptionStatus)> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let (kind, rest) = line.split_once(',')?;
+    let mut parts = rest.splitn(3, ',');
+    let id = parts.next()?.to_string();
+    let status = match kind {
+        "SUBOK" => SubscriptionStatus::Subscribed,
+        "SUBCMD" => SubscriptionStatus::CommandSubscribed,
+        "REQERR" => SubscriptionStatus::Failed {
+            code: parts.next()?.trim().parse().ok()?,
+            message: percent_decode(parts.next().unwrap_or("")),
+        },
+        _ => return None,
+    };
+    Some((id, status))
+}
+
+/// Builds the `create` request that opens a Lightstreamer session.
+/// `password` carries the session tokens, so log it through [`redact_password`].
+pub fn create_session_message(client_id: &str, adapter_set: &str, user: &str, password: &str) -> String {
+    format!(
+        "\r\n\r\nLS_op2=create\r\nLS_cid={}\r\nLS_adapter_set={}\r\nLS_user={}\r\nLS_password={}\r\n",
+        client_id, adapter_set, user, password
+    )
+}
+
+/// Masks the `LS_password` value of a protocol message, which holds the CST
+/// and XST tokens, so the message can be logged
+pub fn redact_password(message: &str) -> String {
+    message
+        .split("\r\n")
+        .map(|line| match line.strip_prefix("LS_password=") {
+            Some(_) => "LS_password=[REDACTED]",
+            None => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+/// Decodes one field: empty is "unchanged", `#` is null, `$` is an empty
+/// string, anything else is percent-encoded text
+pub fn decode_value(raw: &str) -> Option<String> {
+    match raw {
+        "" | "#" => None,
+        "$" => Some(String::new()),
+        _ => Some(percent_decode(raw)),
+    }
+}
+
+fn percent_decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = raw
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            out.push(byte);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests_lightstreamer {
+    use super::*;
+
+    #[test]
+    fn test_parse_update() {
+        let update = parse_update("U,3,1,1715594400000||18000.5|#|$|caf%C3%A9\r\n").unwrap();
+        assert_eq!(update.subscription_id, "3");
+        assert_eq!(update.item, 1);
+        assert_eq!(
+            update.fields,
+            vec![
+                Some("1715594400000".to_string()),
+                None,
+                Some("18000.5".to_string()),
+                None,
+                Some(String::new()),
+                Some("café".to_string()),
+            ]
+        );
+        assert_eq!(update.number(2), Some(18000.5));
+        assert_eq!(update.number(1), None);
+    }
+
+    #[test]
+    fn test_session_message_redacted_for_logging() {
+        let message = create_session_message("CID1", "DEMO-igindexdemo", "ACC1", "CST-secretcst|XST-secretxst");
+        assert!(message.contains("LS_password=CST-secretcst|XST-secretxst\r\n"));
+
+        let logged = redact_password(&message);
+        assert!(!logged.contains("secretcst"));
+        assert!(!logged.contains("secretxst"));
+        assert_eq!(
+            logged,
+            "\r\n\r\nLS_op2=create\r\nLS_cid=CID1\r\nLS_adapter_set=DEMO-igindexdemo\r\nLS_user=ACC1\r\nLS_password=[REDACTED]\r\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_subscription_status() {
+        assert_eq!(
+            parse_subscription_status("SUBOK,MARKET-1,1,3\r\n"),
+            Some(("MARKET-1".to_string(), SubscriptionStatus::Subscribed))
+        );
+        assert_eq!(
+            parse_subscription_status("SUBCMD,TRADE-1,1,5,1,2"),
+            Some(("TRADE-1".to_string(), SubscriptionStatus::CommandSubscribed))
+        );
+        assert_eq!(
+            parse_subscription_status("REQERR,CHART-1,19,Specified%20item%20not%20found"),
+            Some((
+                "CHART-1".to_string(),
+                SubscriptionStatus::Failed {
+                    code: 19,
+                    message: "Specified item not found".to_string(),
+                }
+            ))
+        );
+        assert_eq!(parse_subscription_status("CONOK,S1,50000,5000,*"), None);
+    }
+
+    #[test]
+    fn test_parse_conerr() {
+        let error = parse_conerr("CONERR,1,User%2Fpassword check failed\r\n").unwrap();
+        assert_eq!(error.code, 1);
+        assert_eq!(error.message, "User/password check failed");
+        assert!(error.is_auth_failure());
+
+        let error = parse_conerr("PROBE\r\nCONERR,2,Requested Adapter Set not available\r\n").unwrap();
+        assert_eq!(error.code, 2);
+        assert!(!error.is_auth_failure());
+
+        assert_eq!(parse_conerr("CONERR,7").unwrap().message, "");
+        assert_eq!(parse_conerr("CONOK,S1,50000,5000,*"), None);
+        assert_eq!(parse_conerr("CONERR,x,bad code"), None);
+        assert_eq!(parse_conerr("ERROR,65,Expired%20session").unwrap().message, "Expired session");
+        assert_eq!(parse_conerr("U,1,1,Error|ERROR,2"), None);
+    }
+
+    #[test]
+    fn test_decode_binary() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"U,1,1,18000.5|18001.5|12:00:00\r\n").unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        assert_eq!(decode_binary(&gzipped).unwrap(), "U,1,1,18000.5|18001.5|12:00:00\r\n");
+        assert_eq!(decode_binary(b"PROBE\r\n").unwrap(), "PROBE\r\n");
+        assert!(matches!(decode_binary(&[0xff, 0xfe]), Err(AppError::WebSocketError(_))));
+        assert!(matches!(decode_binary(&GZIP_MAGIC), Err(AppError::Io(_))));
+    }
+
+    #[test]
+    fn test_parse_ignores_other_lines() {
+        assert_eq!(parse_update("CONOK,S1,50000,5000,*"), None);
+        assert_eq!(parse_update("SUBOK,1,1,11"), None);
+        assert_eq!(parse_update("U,1,x,1|2"), None);
+    }
+}
@@ -0,0 +1,82 @@
+//! Hooks for collecting request counts, latencies and error rates from the
+//! HTTP client, e.g. to export them to Prometheus.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use reqwest::{Method, StatusCode};
+
+use crate::error::AppError;
+
+/// Receives an event for every HTTP attempt made by `IgHttpClientImpl`,
+/// including retries. Every method defaults to doing nothing.
+pub trait Metrics: Send + Sync {
+    /// A request is about to be sent
+    fn on_request(&self, _method: &Method, _path: &str) {}
+
+    /// A response arrived `elapsed` after the request was sent, whatever its status
+    fn on_response(&self, _status: StatusCode, _elapsed: Duration) {}
+
+    /// The attempt failed, either without a response or with an error status
+    fn on_error(&self, _error: &AppError) {}
+}
+
+/// Metrics sink that discards every event
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {}
+
+/// In-memory counters, for tests or for polling from a metrics exporter
+#[derive(Debug, Default)]
+pub struct CountingMetrics {
+    requests: AtomicU64,
+    successes: AtomicU64,
+    errors: AtomicU64,
+    latency_micros: AtomicU64,
+}
+
+impl CountingMetrics {
+    /// Creates counters that all start at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests sent
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Responses with a 2xx status
+    pub fn successes(&self) -> u64 {
+        self.successes.load(Ordering::Relaxed)
+    }
+
+    /// Attempts that failed
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// Sum of the latencies of every response received
+    pub fn total_latency(&self) -> Duration {
+        Duration::from_micros(self.latency_micros.load(Ordering::Relaxed))
+    }
+}
+
+impl Metrics for CountingMetrics {
+    fn on_request(&self, _method: &Method, _path: &str) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_response(&self, status: StatusCode, elapsed: Duration) {
+        if status.is_success() {
+            self.successes.fetch_add(1, Ordering::Relaxed);
+        }
+        self.latency_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn on_error(&self, _error: &AppError) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
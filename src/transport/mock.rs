@@ -0,0 +1,369 @@
+//! Test doubles for the transport layer.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    error::AppError,
+    session::interface::IgSession,
+    transport::http_client::{IgHttpClient, RequestOptions},
+};
+
+/// A request captured by [`MockHttpClient`]
+#[derive(Debug, Clone)]
+pub(crate) struct RecordedRequest {
+    pub method: Method,
+    pub path: String,
+    pub version: String,
+    pub body: Option<serde_json::Value>,
+    /// `max_retries` of the options, if the request was sent with options
+    pub max_retries: Option<u32>,
+}
+
+/// `IgHttpClient` that answers with queued JSON responses, in order, and records every request
+#[derive(Default)]
+pub(crate) struct MockHttpClient {
+    responses: Mutex<VecDeque<Result<serde_json::Value, AppError>>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+impl MockHttpClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a successful JSON response
+    pub fn push_json(&self, value: serde_json::Value) {
+        self.responses.lock().unwrap().push_back(Ok(value));
+    }
+
+    /// Queues an error response
+    pub fn push_error(&self, error: AppError) {
+        self.responses.lock().unwrap().push_back(Err(error));
+    }
+
+    /// Returns every request received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn respond<T, R>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&T>,
+        version: &str,
+        options: Option<&RequestOptions>,
+    ) -> Result<R, AppError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        self.requests.lock().unwrap().push(RecordedRequest {
+            method: method.clone(),
+            path: path.to_string(),
+            version: version.to_string(),
+            body: body.map(|b| serde_json::to_value(b).unwrap()),
+            max_retries: options.map(|o| o.max_retries),
+        });
+
+        let response = self
+            .responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| panic!("no mock response queued for {} {}", method, path));
+        Ok(serde_json::from_value(response?)?)
+    }
+}
+
+#[async_trait]
+impl IgHttpClient for MockHttpClient {
+    async fn request<T, R>(
+        &self,
+        method: Method,
+        path: &str,
+        _session: &IgSession,
+        body: Option<&T>,
+        version: &str,
+    ) -> Result<R, AppError>
+    where
+        for<'de> R: DeserializeOwned + 'static,
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.respond(method, path, body, version, None)
+    }
+
+    async fn request_with_options<T, R>(
+        &self,
+        method: Method,
+        path: &str,
+        _session: &IgSession,
+        body: Option<&T>,
+        version: &str,
+        options: &RequestOptions,
+    ) -> Result<R, AppError>
+    where
+        for<'de> R: DeserializeOwned + 'static,
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.respond(method, path, body, version, Some(options))
+    }
+
+    async fn request_no_auth<T, R>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&T>,
+        version: &str,
+    ) -> Result<R, AppError>
+    where
+        for<'de> R: DeserializeOwned + 'static,
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.respond(method, path, body, version, None)
+    }
+}
+
+/// Session with dummy tokens for service tests
+pub(crate) fn test_session() -> IgSession {
+    IgSession {
+        cst: "CST".to_string(),
+        token: "XST".to_string(),
+        account_id: "ACC1".to_string(),
+        lightstreamer_endpoint: None,
+    }
+}
+
+/// One entry of IG's `GET /positions`: deal `DIAAAA` of `size` at 18000 on
+/// `epic`, quoted at 18010/18011. Tests adjust other fields in place.
+pub(crate) fn position_json(epic: &str, direction: &str, size: f64) -> serde_json::Value {
+    serde_json::json!({
+        "position": {
+            "contractSize": 1.0,
+            "createdDate": "2025/05/13 10:00:00:000",
+            "createdDateUTC": "2025-05-13T08:00:00",
+            "dealId": "DIAAAA",
+            "dealReference": "REF",
+            "direction": direction,
+            "limitLevel": null,
+            "level": 18000.0,
+            "size": size,
+            "stopLevel": null,
+            "trailingStep": null,
+            "trailingStopDistance": null,
+            "currency": "EUR",
+            "controlledRisk": false,
+            "limitedRiskPremium": null
+        },
+        "market": market_json(epic),
+        "pnl": null
+    })
+}
+
+/// One entry of IG's `GET /workingorders`: deal `DIWWWW`, a 1-lot SELL LIMIT
+/// at 18100 on `epic`
+pub(crate) fn working_order_json(epic: &str) -> serde_json::Value {
+    serde_json::json!({
+        "workingOrderData": {
+            "dealId": "DIWWWW",
+            "direction": "SELL",
+            "epic": epic,
+            "orderSize": 1.0,
+            "orderLevel": 18100.0,
+            "timeInForce": "GOOD_TILL_CANCELLED",
+            "goodTillDate": null,
+            "goodTillDateISO": null,
+            "createdDate": "2025/05/13 10:00:00:000",
+            "createdDateUTC": "2025-05-13T08:00:00",
+            "guaranteedStop": false,
+            "orderType": "LIMIT",
+            "stopDistance": null,
+            "limitDistance": null,
+            "currencyCode": "EUR",
+            "dma": false,
+            "limitedRiskPremium": null
+        },
+        "marketData": market_json(epic)
+    })
+}
+
+/// The market block of [`position_json`] and [`working_order_json`]
+fn market_json(epic: &str) -> serde_json::Value {
+    serde_json::json!({
+        "instrumentName": "Germany 40",
+        "exchangeId": "IG",
+        "expiry": "-",
+        "epic": epic,
+        "instrumentType": "INDICES",
+        "lotSize": 1.0,
+        "high": 18100.0,
+        "low": 17900.0,
+        "percentageChange": 0.1,
+        "netChange": 10.0,
+        "bid": 18010.0,
+        "offer": 18011.0,
+        "updateTime": "10:00:00",
+        "updateTimeUTC": "08:00:00",
+        "delayTime": 0,
+        "streamingPricesAvailable": true,
+        "marketStatus": "TRADEABLE",
+        "scalingFactor": 1
+    })
+}
+
+/// A canned HTTP response served by [`MockServer`]
+#[derive(Debug, Clone)]
+pub(crate) struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub delay: Duration,
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.to_string(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub fn json(status: u16, body: serde_json::Value) -> Self {
+        Self::new(status, &body.to_string()).with_header("Content-Type", "application/json")
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Waits before answering, e.g. to trigger a client timeout
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// A request received by [`MockServer`]
+#[derive(Debug, Clone)]
+pub(crate) struct ServerRequest {
+    pub method: String,
+    pub path: String,
+    /// Header names are lower-cased
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl ServerRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Minimal HTTP/1.1 server on background threads. Each accepted connection
+/// gets the next queued response; once the queue is empty it stops listening.
+pub(crate) struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<ServerRequest>>>,
+}
+
+impl MockServer {
+    pub fn start(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+
+        thread::spawn(move || {
+            for response in responses {
+                let Ok((stream, _)) = listener.accept() else {
+                    return;
+                };
+                let recorded = recorded.clone();
+                // Serve concurrently so a delayed response does not block a retry
+                thread::spawn(move || Self::serve(stream, &response, &recorded));
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Base URL, e.g. `http://127.0.0.1:12345`
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Returns every request received so far
+    pub fn requests(&self) -> Vec<ServerRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn serve(
+        mut stream: TcpStream,
+        response: &MockResponse,
+        recorded: &Mutex<Vec<ServerRequest>>,
+    ) -> Option<()> {
+        let mut reader = BufReader::new(stream.try_clone().ok()?);
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let mut parts = line.split_whitespace();
+        let method = parts.next()?.to_string();
+        let path = parts.next()?.to_string();
+
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).ok()?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((k, v)) = line.split_once(':') {
+                headers.push((k.trim().to_ascii_lowercase(), v.trim().to_string()));
+            }
+        }
+
+        let length = headers
+            .iter()
+            .find(|(k, _)| k == "content-length")
+            .and_then(|(_, v)| v.parse::<usize>().ok())
+            .unwrap_or(0);
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).ok()?;
+        recorded.lock().unwrap().push(ServerRequest {
+            method,
+            path,
+            headers,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        });
+
+        thread::sleep(response.delay);
+        let mut out = format!(
+            "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+            response.status,
+            response.body.len()
+        );
+        for (name, value) in &response.headers {
+            out.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        out.push_str("\r\n");
+        out.push_str(&response.body);
+        // The client may already have given up on a delayed response
+        stream.write_all(out.as_bytes()).ok()
+    }
+}
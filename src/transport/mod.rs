@@ -1,4 +1,14 @@
+pub mod http;
 pub mod http_client;
 pub mod websocket_client;
 pub mod model;
-pub mod ws_interface;
\ No newline at end of file
+pub mod ws_interface;
+pub mod query;
+pub mod rate_limiter;
+pub mod lightstreamer;
+pub mod reconnect;
+pub mod metrics;
+pub mod replay;
+
+#[cfg(test)]
+pub(crate) mod mock;
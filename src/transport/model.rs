@@ -1,5 +1,12 @@
 use serde::{Deserialize, Serialize};
 
+use crate::application::models::market::Resolution;
+use crate::application::models::order::{Direction, OrderConfirmation, OrderStatus, OrderType, TimeInForce};
+use crate::error::AppError;
+use crate::transport::lightstreamer::{
+    ACCOUNT_FIELDS, CHART_FIELDS, ItemUpdate, MARKET_FIELDS, TRADE_FIELDS,
+};
+
 /// Represents a subscription to a specific market or account stream
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Subscription {
@@ -11,6 +18,42 @@ pub struct Subscription {
     pub item: String,
 }
 
+/// Last state the server reported for a subscription
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscriptionStatus {
+    /// Sent, not yet acknowledged
+    Pending,
+    /// Acknowledged with `SUBOK`
+    Subscribed,
+    /// Acknowledged with `SUBCMD` (COMMAND mode)
+    CommandSubscribed,
+    /// Refused with `REQERR`
+    Failed {
+        /// Lightstreamer error code
+        code: i32,
+        /// Server-provided description
+        message: String,
+    },
+}
+
+impl SubscriptionStatus {
+    /// Whether the server accepted the subscription
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::Subscribed | Self::CommandSubscribed)
+    }
+}
+
+/// State of the streaming connection, as reported to a `WebSocketHandler`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// A connection attempt has started
+    Connecting,
+    /// The server accepted the session
+    Connected,
+    /// The connection failed, was closed or was found dead
+    Disconnected,
+}
+
 /// Types of subscriptions available
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum SubscriptionType {
@@ -83,14 +126,296 @@ pub struct MarketUpdate {
     pub timestamp: String,
 }
 
-/// Account update
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AccountUpdate {
+impl MarketUpdate {
+    /// Decodes an update whose fields follow [`MARKET_FIELDS`]. Fields
+    /// Lightstreamer reports as unchanged are taken from `previous`; returns
+    /// `None` until both a bid and an offer are known.
+    pub fn from_update(epic: &str, update: &ItemUpdate, previous: Option<&MarketUpdate>) -> Option<Self> {
+        let field = |name: &str| MARKET_FIELDS.iter().position(|f| *f == name).unwrap();
+        let timestamp = update
+            .fields
+            .get(field("UPDATE_TIME"))
+            .cloned()
+            .flatten()
+            .or_else(|| previous.map(|p| p.timestamp.clone()))
+            .unwrap_or_default();
+
+        Some(Self {
+            epic: epic.to_string(),
+            bid: update.number(field("BID")).or(previous.map(|p| p.bid))?,
+            offer: update.number(field("OFFER")).or(previous.map(|p| p.offer))?,
+            timestamp,
+        })
+    }
+
+    /// Divides the streamed integer-scaled bid and offer by the market's
+    /// `scalingFactor`; a factor of 0 or 1 leaves them unchanged
+    pub fn scaled(mut self, scaling_factor: i64) -> Self {
+        if scaling_factor > 1 {
+            self.bid /= scaling_factor as f64;
+            self.offer /= scaling_factor as f64;
+        }
+        self
+    }
+}
+
+/// Update pushed on an `ACCOUNT:{accountId}` subscription
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AccountUpdate {
+    /// Change in the account's balance fields
+    Balance(BalanceUpdate),
+}
+
+/// Account balance pushed on an `ACCOUNT:{accountId}` subscription.
+///
+/// Fields are `None` when Lightstreamer reports them unchanged since the
+/// previous update.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceUpdate {
+    pub account_id: String,
+    /// Running profit and loss of open positions
+    pub pnl: Option<f64>,
+    pub deposit: Option<f64>,
+    pub available_cash: Option<f64>,
+    pub margin: Option<f64>,
+    pub funds: Option<f64>,
+}
+
+impl BalanceUpdate {
+    /// Decodes an update whose fields follow [`ACCOUNT_FIELDS`]
+    pub fn from_update(account_id: &str, update: &ItemUpdate) -> Self {
+        let field = |name: &str| {
+            let index = ACCOUNT_FIELDS.iter().position(|f| *f == name).unwrap();
+            update.number(index)
+        };
+        Self {
+            account_id: account_id.to_string(),
+            pnl: field("PNL"),
+            deposit: field("DEPOSIT"),
+            available_cash: field("AVAILABLE_CASH"),
+            margin: field("MARGIN"),
+            funds: field("FUNDS"),
+        }
+    }
+}
+
+/// Candle update pushed on a `CHART:{epic}:{scale}` subscription.
+///
+/// Prices are mid prices. Fields are `None` when Lightstreamer reports them
+/// unchanged since the previous update.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartUpdate {
+    /// Market epic
+    pub epic: String,
+    /// Candle resolution
+    pub resolution: Resolution,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub close: Option<f64>,
+    /// Last traded volume
+    pub volume: Option<f64>,
+    /// Candle start time, in milliseconds since the Unix epoch
+    pub timestamp: Option<i64>,
+    /// Whether this update closes the candle
+    pub complete: Option<bool>,
+}
+
+impl ChartUpdate {
+    /// Decodes an update whose fields follow [`CHART_FIELDS`]
+    pub fn from_update(epic: &str, resolution: Resolution, update: &ItemUpdate) -> Self {
+        let field = |name: &str| CHART_FIELDS.iter().position(|f| *f == name).unwrap();
+        let mid = |bid: &str, offer: &str| match (update.number(field(bid)), update.number(field(offer))) {
+            (Some(b), Some(o)) => Some((b + o) / 2.0),
+            (one, other) => one.or(other),
+        };
+
+        Self {
+            epic: epic.to_string(),
+            resolution,
+            open: mid("BID_OPEN", "OFR_OPEN"),
+            high: mid("BID_HIGH", "OFR_HIGH"),
+            low: mid("BID_LOW", "OFR_LOW"),
+            close: mid("BID_CLOSE", "OFR_CLOSE"),
+            volume: update.number(field("LTV")),
+            timestamp: update.number(field("UTM")).map(|t| t as i64),
+            complete: update.number(field("CONS_END")).map(|v| v == 1.0),
+        }
+    }
+}
+
+/// Open position update (`OPU`) pushed on the trade stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionUpdate {
+    pub deal_id: String,
+    pub deal_reference: Option<String>,
+    pub deal_id_origin: Option<String>,
+    pub epic: String,
+    /// OPEN, UPDATED or DELETED
+    pub status: OrderStatus,
+    pub deal_status: Option<String>,
+    pub direction: Direction,
+    pub level: Option<f64>,
+    pub size: Option<f64>,
+    pub currency: Option<String>,
+    pub expiry: Option<String>,
+    pub stop_level: Option<f64>,
+    pub limit_level: Option<f64>,
+    pub guaranteed_stop: Option<bool>,
+    pub trailing_stop: Option<bool>,
+    pub timestamp: Option<String>,
+    pub channel: Option<String>,
+}
+
+/// Working order update (`WOU`) pushed on the trade stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingOrderUpdate {
+    pub deal_id: String,
+    pub deal_reference: Option<String>,
+    pub epic: String,
+    /// OPEN, UPDATED or DELETED
+    pub status: OrderStatus,
+    pub deal_status: Option<String>,
+    pub direction: Direction,
+    pub level: Option<f64>,
+    pub size: Option<f64>,
+    pub currency: Option<String>,
+    pub expiry: Option<String>,
+    pub order_type: Option<OrderType>,
+    pub time_in_force: Option<TimeInForce>,
+    pub good_till_date: Option<String>,
+    pub stop_distance: Option<f64>,
+    pub limit_distance: Option<f64>,
+    pub guaranteed_stop: Option<bool>,
+    pub timestamp: Option<String>,
+    pub channel: Option<String>,
+}
+
+/// Update pushed on a `TRADE:{accountId}` subscription. Each field carries a
+/// JSON payload and is `None` when it did not change in this update.
+#[derive(Debug, Clone)]
+pub struct TradeUpdate {
     /// Account ID
     pub account_id: String,
-    /// Update type (POSITION, ORDER, etc.)
-    pub update_type: String,
-    /// The data associated with the update
-    pub data: serde_json::Value,
+    /// Deal confirmation (`CONFIRMS`)
+    pub confirmation: Option<OrderConfirmation>,
+    /// Open position update (`OPU`)
+    pub position: Option<PositionUpdate>,
+    /// Working order update (`WOU`)
+    pub working_order: Option<WorkingOrderUpdate>,
+}
+
+impl TradeUpdate {
+    /// Decodes an update whose fields follow [`TRADE_FIELDS`]
+    pub fn from_update(account_id: &str, update: &ItemUpdate) -> Result<Self, AppError> {
+        let field = |name: &str| {
+            let idx = TRADE_FIELDS.iter().position(|f| *f == name).unwrap();
+            update
+                .fields
+                .get(idx)
+                .and_then(|v| v.as_deref())
+                .filter(|json| !json.is_empty())
+        };
+
+        Ok(Self {
+            account_id: account_id.to_string(),
+            confirmation: field("CONFIRMS").map(serde_json::from_str).transpose()?,
+            position: field("OPU").map(serde_json::from_str).transpose()?,
+            working_order: field("WOU").map(serde_json::from_str).transpose()?,
+        })
+    }
 }
 
+#[cfg(test)]
+mod tests_model {
+    use super::*;
+    use crate::utils::money::price;
+    use crate::transport::lightstreamer::parse_update;
+
+    #[test]
+    fn test_balance_update_from_update() {
+        let update = parse_update("U,ACCOUNT-1,1,-12.5|10000|8250.75||1737.5").unwrap();
+
+        let balance = BalanceUpdate::from_update("ABC123", &update);
+
+        assert_eq!(
+            balance,
+            BalanceUpdate {
+                account_id: "ABC123".to_string(),
+                pnl: Some(-12.5),
+                deposit: Some(10000.0),
+                available_cash: Some(8250.75),
+                margin: Some(1737.5),
+                funds: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_chart_update_from_update() {
+        let update = parse_update(
+            "U,CHART-1,1,1715594400000|12|18000|18010|17990|18005|18001|18011|17991|18006|0",
+        )
+        .unwrap();
+
+        let chart = ChartUpdate::from_update("IX.D.DAX.IFMM.IP", Resolution::Minute5, &update);
+
+        assert_eq!(
+            chart,
+            ChartUpdate {
+                epic: "IX.D.DAX.IFMM.IP".to_string(),
+                resolution: Resolution::Minute5,
+                open: Some(18000.5),
+                high: Some(18010.5),
+                low: Some(17990.5),
+                close: Some(18005.5),
+                volume: Some(12.0),
+                timestamp: Some(1715594400000),
+                complete: Some(false),
+            }
+        );
+    }
+
+    #[test]
+    fn test_chart_update_partial() {
+        // Only the bid close changed, and the offer side is null
+        let update = parse_update("U,CHART-1,1,||||||#|#|#|#|1").unwrap();
+
+        let chart = ChartUpdate::from_update("IX.D.DAX.IFMM.IP", Resolution::Minute, &update);
+
+        assert_eq!(chart.open, None);
+        assert_eq!(chart.close, None);
+        assert_eq!(chart.complete, Some(true));
+    }
+
+    #[test]
+    fn test_trade_update_from_confirms() {
+        let confirms = r#"{"dealReference":"REF1","dealId":"DIAAAAA","dealStatus":"ACCEPTED","status":"OPEN","date":"2025-05-13T12:00:00.000","epic":"IX.D.DAX.IFMM.IP","expiry":"-","direction":"BUY","level":18005.5,"size":1,"guaranteedStop":false,"trailingStop":false,"reason":"SUCCESS"}"#;
+        let opu = r#"{"dealReference":"REF1","dealId":"DIAAAAA","dealIdOrigin":"DIAAAAA","epic":"IX.D.DAX.IFMM.IP","status":"OPEN","dealStatus":"ACCEPTED","direction":"BUY","level":18005.5,"size":1,"currency":"EUR","expiry":"-","stopLevel":null,"limitLevel":null,"guaranteedStop":false,"trailingStop":false,"timestamp":"2025-05-13T12:00:00.000","channel":"PublicRestOTC"}"#;
+        // Lightstreamer percent-encodes the `|` separator, which JSON payloads never contain
+        let line = format!("U,TRADE-1,1,{}|{}|#", confirms, opu);
+        let update = crate::transport::lightstreamer::parse_update(&line).unwrap();
+
+        let trade = TradeUpdate::from_update("ACC1", &update).unwrap();
+
+        let confirmation = trade.confirmation.unwrap();
+        assert_eq!(confirmation.deal_reference, "REF1");
+        assert_eq!(confirmation.status, OrderStatus::Open);
+        assert_eq!(confirmation.direction, Some(Direction::Buy));
+        assert_eq!(confirmation.level, Some(price!(18005.5)));
+
+        let position = trade.position.unwrap();
+        assert_eq!(position.deal_id, "DIAAAAA");
+        assert_eq!(position.status, OrderStatus::Open);
+        assert_eq!(position.channel.as_deref(), Some("PublicRestOTC"));
+        assert!(trade.working_order.is_none());
+    }
+
+    #[test]
+    fn test_trade_update_invalid_json() {
+        let update = crate::transport::lightstreamer::parse_update("U,TRADE-1,1,{oops||").unwrap();
+        assert!(TradeUpdate::from_update("ACC1", &update).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use url::form_urlencoded;
+
+/// Builds a request path with percent-encoded query parameters
+///
+/// ```
+/// use ig_client::transport::query::QueryBuilder;
+///
+/// let path = QueryBuilder::new("markets").param("searchTerm", "S&P 500").build();
+/// assert_eq!(path, "markets?searchTerm=S%26P%20500");
+/// ```
+#[derive(Debug, Clone)]
+pub struct QueryBuilder {
+    path: String,
+    params: Vec<(String, String)>,
+}
+
+impl QueryBuilder {
+    /// Starts a query for the given path (without a `?`)
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Appends a query parameter; the value is escaped when the path is built
+    pub fn param(mut self, key: &str, value: impl ToString) -> Self {
+        self.params.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Returns the path followed by the encoded query string
+    pub fn build(self) -> String {
+        if self.params.is_empty() {
+            return self.path;
+        }
+        let query = self
+            .params
+            .iter()
+            .map(|(k, v)| format!("{}={}", encode_query_value(k), encode_query_value(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+        format!("{}?{}", self.path, query)
+    }
+}
+
+/// Percent-encodes a single query component, using `%20` for spaces
+pub fn encode_query_value(value: &str) -> String {
+    // `byte_serialize` encodes a literal '+' as %2B, so any '+' left is a space
+    form_urlencoded::byte_serialize(value.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
+}
+
+#[cfg(test)]
+mod tests_query {
+    use super::*;
+
+    #[test]
+    fn test_spaces_are_escaped() {
+        let path = QueryBuilder::new("markets").param("searchTerm", "US Tech 100").build();
+        assert_eq!(path, "markets?searchTerm=US%20Tech%20100");
+    }
+
+    #[test]
+    fn test_reserved_characters_are_escaped() {
+        let path = QueryBuilder::new("markets").param("searchTerm", "AT&T").build();
+        assert_eq!(path, "markets?searchTerm=AT%26T");
+
+        let path = QueryBuilder::new("markets").param("searchTerm", "a+b/c=d").build();
+        assert_eq!(path, "markets?searchTerm=a%2Bb%2Fc%3Dd");
+    }
+
+    #[test]
+    fn test_multiple_params_keep_order() {
+        let path = QueryBuilder::new("history/transactions")
+            .param("from", "2025-01-01T00:00:00")
+            .param("pageSize", 50)
+            .build();
+        assert_eq!(
+            path,
+            "history/transactions?from=2025-01-01T00%3A00%3A00&pageSize=50"
+        );
+    }
+}
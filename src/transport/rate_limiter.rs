@@ -0,0 +1,287 @@
+//! Client-side throttling that keeps requests within IG's per-minute limits.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use reqwest::Method;
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::config::RestApiConfig;
+
+/// IG limits trading and non-trading requests separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestCategory {
+    /// Deals: creating, amending or closing positions and working orders
+    Trading,
+    /// Everything else (account, market data, history, login)
+    NonTrading,
+}
+
+impl RequestCategory {
+    /// Classifies a request; only non-GET calls on `positions/otc` and
+    /// `workingorders/otc` count as trading
+    pub fn classify(method: &Method, path: &str) -> Self {
+        let path = path.trim_start_matches('/');
+        let is_deal_path = path.starts_with("positions/otc") || path.starts_with("workingorders/otc");
+        if is_deal_path && *method != Method::GET {
+            RequestCategory::Trading
+        } else {
+            RequestCategory::NonTrading
+        }
+    }
+
+    /// Permits a bucket of this category holds at `per_minute`: trading
+    /// requests are spaced evenly, non-trading ones may burst a full minute's
+    /// allowance
+    fn capacity(self, per_minute: u32) -> u32 {
+        match self {
+            RequestCategory::Trading => 1,
+            RequestCategory::NonTrading => per_minute,
+        }
+    }
+}
+
+/// Token bucket holding up to `capacity` permits, one added every `interval`.
+/// Tokens may go negative: a caller that takes a missing token is told how
+/// long to wait, which reserves its slot for the next caller.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    interval: Duration,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32, interval: Duration, now: Instant) -> Self {
+        Self {
+            capacity: capacity as f64,
+            interval,
+            tokens: capacity as f64,
+            last: now,
+        }
+    }
+
+    /// Slows the bucket down to `interval` and `capacity` if they are stricter
+    fn tighten(&mut self, capacity: u32, interval: Duration) {
+        if interval > self.interval {
+            self.interval = interval;
+        }
+        self.capacity = self.capacity.min(capacity as f64);
+        self.tokens = self.tokens.min(self.capacity);
+    }
+
+    fn reserve(&mut self, now: Instant) -> Duration {
+        if now > self.last {
+            let elapsed = now - self.last;
+            let refill = elapsed.as_secs_f64() / self.interval.as_secs_f64();
+            self.tokens = (self.tokens + refill).min(self.capacity);
+            self.last = now;
+        }
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            self.interval.mul_f64(-self.tokens)
+        }
+    }
+}
+
+/// Per-category token buckets built from [`RestApiConfig`].
+///
+/// Trading requests are spaced evenly (no burst); non-trading requests may
+/// burst up to a full minute's allowance.
+#[derive(Debug)]
+pub struct RateLimiter {
+    trading: Option<Mutex<TokenBucket>>,
+    non_trading: Option<Mutex<TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Builds the limiter from the configured requests-per-minute values
+    pub fn new(config: &RestApiConfig) -> Self {
+        Self::new_at(config, Instant::now())
+    }
+
+    fn new_at(config: &RestApiConfig, now: Instant) -> Self {
+        let bucket = |category: RequestCategory, per_minute: u32| {
+            (per_minute > 0).then(|| {
+                let interval = Duration::from_secs(60) / per_minute;
+                Mutex::new(TokenBucket::new(category.capacity(per_minute), interval, now))
+            })
+        };
+        Self {
+            trading: bucket(RequestCategory::Trading, config.trading_requests_per_minute),
+            non_trading: bucket(RequestCategory::NonTrading, config.non_trading_requests_per_minute),
+        }
+    }
+
+    /// Lowers the limits to the given requests-per-minute allowances (as
+    /// reported by IG for the API key) where they are stricter than the
+    /// configured ones. Categories left unlimited by the config stay unlimited,
+    /// and an allowance of 0 changes nothing.
+    pub fn tighten(&self, trading_per_minute: u32, non_trading_per_minute: u32) {
+        let apply = |bucket: &Option<Mutex<TokenBucket>>, category: RequestCategory, per_minute: u32| {
+            if let Some(bucket) = bucket
+                && per_minute > 0
+            {
+                let interval = Duration::from_secs(60) / per_minute;
+                bucket.lock().unwrap().tighten(category.capacity(per_minute), interval);
+            }
+        };
+        apply(&self.trading, RequestCategory::Trading, trading_per_minute);
+        apply(&self.non_trading, RequestCategory::NonTrading, non_trading_per_minute);
+    }
+
+    /// Waits until a request of this method and path may be sent
+    pub async fn acquire(&self, method: &Method, path: &str) {
+        let category = RequestCategory::classify(method, path);
+        let wait = self.reserve_at(category, Instant::now());
+        if !wait.is_zero() {
+            debug!("Rate limiting {:?} request to {} for {:?}", category, path, wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes a permit at `now` and returns how long the caller must wait before using it
+    fn reserve_at(&self, category: RequestCategory, now: Instant) -> Duration {
+        let bucket = match category {
+            RequestCategory::Trading => &self.trading,
+            RequestCategory::NonTrading => &self.non_trading,
+        };
+        match bucket {
+            Some(bucket) => bucket.lock().unwrap().reserve(now),
+            None => Duration::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_rate_limiter {
+    use super::*;
+
+    fn config(trading: u32, non_trading: u32) -> RestApiConfig {
+        RestApiConfig {
+            base_url: "https://api.example.com".to_string(),
+            gateway_path: "gateway/deal".to_string(),
+            timeout: 30,
+            connect_timeout: 10,
+            trading_requests_per_minute: trading,
+            non_trading_requests_per_minute: non_trading,
+            proxy_url: None,
+            no_proxy: None,
+            ca_bundle: None,
+            user_agent: None,
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            RequestCategory::classify(&Method::POST, "positions/otc"),
+            RequestCategory::Trading
+        );
+        assert_eq!(
+            RequestCategory::classify(&Method::DELETE, "/workingorders/otc/DIAAAA"),
+            RequestCategory::Trading
+        );
+        assert_eq!(
+            RequestCategory::classify(&Method::GET, "positions"),
+            RequestCategory::NonTrading
+        );
+        assert_eq!(
+            RequestCategory::classify(&Method::GET, "markets?searchTerm=DAX"),
+            RequestCategory::NonTrading
+        );
+    }
+
+    #[test]
+    fn test_trading_burst_is_spaced() {
+        let t0 = Instant::now();
+        // 600 per minute = one every 100ms
+        let limiter = RateLimiter::new_at(&config(600, 30), t0);
+
+        let waits: Vec<_> = (0..4)
+            .map(|_| limiter.reserve_at(RequestCategory::Trading, t0))
+            .collect();
+        assert_eq!(
+            waits,
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+                Duration::from_millis(300),
+            ]
+        );
+
+        // Once the reserved slots have passed, the next call only waits for its own slot
+        let later = t0 + Duration::from_millis(350);
+        assert_eq!(limiter.reserve_at(RequestCategory::Trading, later).as_millis(), 50);
+    }
+
+    #[test]
+    fn test_non_trading_allows_burst_then_throttles() {
+        let t0 = Instant::now();
+        let limiter = RateLimiter::new_at(&config(600, 30), t0);
+
+        for _ in 0..30 {
+            assert_eq!(limiter.reserve_at(RequestCategory::NonTrading, t0), Duration::ZERO);
+        }
+        assert_eq!(
+            limiter.reserve_at(RequestCategory::NonTrading, t0),
+            Duration::from_secs(2)
+        );
+        // Trading bucket is unaffected
+        assert_eq!(limiter.reserve_at(RequestCategory::Trading, t0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_tighten_only_lowers_limits() {
+        let t0 = Instant::now();
+        let limiter = RateLimiter::new_at(&config(600, 30), t0);
+        // 60 trading per minute is stricter; 120 non-trading is not
+        limiter.tighten(60, 120);
+
+        assert_eq!(limiter.reserve_at(RequestCategory::Trading, t0), Duration::ZERO);
+        assert_eq!(limiter.reserve_at(RequestCategory::Trading, t0), Duration::from_secs(1));
+        for _ in 0..30 {
+            assert_eq!(limiter.reserve_at(RequestCategory::NonTrading, t0), Duration::ZERO);
+        }
+        assert_eq!(
+            limiter.reserve_at(RequestCategory::NonTrading, t0),
+            Duration::from_secs(2)
+        );
+
+        // Allowances cannot enable a limit the config disabled
+        let unlimited = RateLimiter::new_at(&config(0, 0), t0);
+        unlimited.tighten(1, 1);
+        assert_eq!(unlimited.reserve_at(RequestCategory::Trading, t0), Duration::ZERO);
+        assert_eq!(unlimited.reserve_at(RequestCategory::Trading, t0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_tighten_keeps_the_burst_rule_of_new() {
+        let t0 = Instant::now();
+        let limiter = RateLimiter::new_at(&config(600, 30), t0);
+        // A stricter allowance behaves like configuring it in the first place
+        limiter.tighten(60, 10);
+        let configured = RateLimiter::new_at(&config(60, 10), t0);
+
+        for category in [RequestCategory::Trading, RequestCategory::NonTrading] {
+            let waits = |limiter: &RateLimiter| -> Vec<_> {
+                (0..12).map(|_| limiter.reserve_at(category, t0)).collect()
+            };
+            assert_eq!(waits(&limiter), waits(&configured));
+        }
+    }
+
+    #[test]
+    fn test_zero_disables_limit() {
+        let t0 = Instant::now();
+        let limiter = RateLimiter::new_at(&config(0, 0), t0);
+        for _ in 0..100 {
+            assert_eq!(limiter.reserve_at(RequestCategory::Trading, t0), Duration::ZERO);
+        }
+    }
+}
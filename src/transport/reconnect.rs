@@ -0,0 +1,120 @@
+//! Exponential backoff with jitter between WebSocket reconnection attempts.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::config::WebSocketConfig;
+
+/// Delay schedule for reconnection attempts: `reconnect_interval` doubled
+/// after every failure up to `reconnect_max_interval`, each delay shortened
+/// by a random fraction of up to `reconnect_jitter` so clients that lost the
+/// connection together do not retry together.
+///
+/// The schedule starts over once a connection has lasted at least
+/// `reconnect_reset_after` seconds.
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    base: Duration,
+    max: Duration,
+    jitter: f64,
+    reset_after: chrono::Duration,
+    attempt: u32,
+    connected_at: Option<DateTime<Utc>>,
+}
+
+impl ReconnectBackoff {
+    /// Builds the schedule from the WebSocket settings
+    pub fn new(config: &WebSocketConfig) -> Self {
+        Self {
+            base: Duration::from_secs(config.reconnect_interval),
+            max: Duration::from_secs(config.reconnect_max_interval),
+            jitter: config.reconnect_jitter.clamp(0.0, 1.0),
+            reset_after: chrono::Duration::seconds(config.reconnect_reset_after as i64),
+            attempt: 0,
+            connected_at: None,
+        }
+    }
+
+    /// Delay before the next attempt
+    pub fn next_delay(&mut self) -> Duration {
+        self.next_delay_with(rand::random::<f64>())
+    }
+
+    /// Delay before the next attempt, with `random` in `[0, 1)` picking the jitter
+    fn next_delay_with(&mut self, random: f64) -> Duration {
+        let delay = 2u32
+            .checked_pow(self.attempt)
+            .and_then(|factor| self.base.checked_mul(factor))
+            .map_or(self.max, |delay| delay.min(self.max));
+        if delay < self.max {
+            self.attempt += 1;
+        }
+        delay.mul_f64(1.0 - self.jitter * random)
+    }
+
+    /// Records that a connection was established at `now`
+    pub fn connected(&mut self, now: DateTime<Utc>) {
+        self.connected_at = Some(now);
+    }
+
+    /// Records that the connection was found dead at `now`; the schedule
+    /// starts over if it had been up for long enough
+    pub fn connection_lost(&mut self, now: DateTime<Utc>) {
+        if let Some(since) = self.connected_at.take()
+            && now - since >= self.reset_after
+        {
+            self.attempt = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_reconnect {
+    use super::*;
+    use crate::config::Config;
+    use crate::utils::clock::{Clock, MockClock};
+    use chrono::TimeZone;
+
+    fn backoff(jitter: f64) -> ReconnectBackoff {
+        let mut config = Config::new().websocket;
+        config.reconnect_interval = 5;
+        config.reconnect_max_interval = 30;
+        config.reconnect_jitter = jitter;
+        config.reconnect_reset_after = 60;
+        ReconnectBackoff::new(&config)
+    }
+
+    fn secs(delays: impl IntoIterator<Item = Duration>) -> Vec<u64> {
+        delays.into_iter().map(|d| d.as_secs()).collect()
+    }
+
+    #[test]
+    fn test_delay_doubles_up_to_cap_and_resets_after_healthy_connection() {
+        let clock = MockClock::new(Utc.with_ymd_and_hms(2025, 5, 13, 12, 0, 0).unwrap());
+        let mut backoff = backoff(0.0);
+
+        assert_eq!(secs((0..5).map(|_| backoff.next_delay())), vec![5, 10, 20, 30, 30]);
+
+        // A connection that drops quickly keeps the long delay
+        backoff.connected(clock.now());
+        clock.advance(Duration::from_secs(59));
+        backoff.connection_lost(clock.now());
+        assert_eq!(backoff.next_delay().as_secs(), 30);
+
+        // One that stayed up past the threshold starts over
+        backoff.connected(clock.now());
+        clock.advance(Duration::from_secs(60));
+        backoff.connection_lost(clock.now());
+        assert_eq!(secs((0..2).map(|_| backoff.next_delay())), vec![5, 10]);
+    }
+
+    #[test]
+    fn test_jitter_shortens_delay() {
+        let mut backoff = backoff(0.5);
+        assert_eq!(backoff.next_delay_with(0.0), Duration::from_secs(5));
+        assert_eq!(backoff.next_delay_with(0.5), Duration::from_millis(7500));
+        let delay = backoff.next_delay();
+        assert!(delay > Duration::from_secs(10) && delay <= Duration::from_secs(20));
+    }
+}
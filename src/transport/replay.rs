@@ -0,0 +1,279 @@
+//! Offline transports: [`ReplayHttpClient`] answers from recorded JSON
+//! fixtures and [`RecordingHttpClient`] writes the responses of a real client
+//! to disk, so strategies can be tested deterministically without a network.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{debug, warn};
+
+use crate::error::AppError;
+use crate::session::interface::IgSession;
+use crate::transport::http_client::{IgHttpClient, RequestOptions};
+
+/// File stem of the fixture for a request, e.g. `GET_markets_searchTerm_DAX`
+/// for `GET markets?searchTerm=DAX`
+pub fn fixture_name(method: &Method, path: &str) -> String {
+    let path: String = path
+        .trim_start_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect();
+    format!("{}_{}", method.as_str(), path)
+}
+
+/// `IgHttpClient` that answers every request with its recorded fixture.
+/// Requests without a fixture fail with [`AppError::ConfigError`].
+#[derive(Debug, Clone, Default)]
+pub struct ReplayHttpClient {
+    fixtures: HashMap<String, serde_json::Value>,
+}
+
+impl ReplayHttpClient {
+    /// Builds the client from responses keyed by method and path, the path
+    /// being relative to the gateway as passed to [`IgHttpClient::request`]
+    pub fn new(fixtures: HashMap<(Method, String), serde_json::Value>) -> Self {
+        Self {
+            fixtures: fixtures
+                .into_iter()
+                .map(|((method, path), value)| (fixture_name(&method, &path), value))
+                .collect(),
+        }
+    }
+
+    /// Loads every `<fixture name>.json` file in `dir`, as written by [`RecordingHttpClient`]
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, AppError> {
+        let mut fixtures = HashMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            fixtures.insert(name.to_string(), value);
+        }
+        debug!("Loaded {} replay fixtures", fixtures.len());
+        Ok(Self { fixtures })
+    }
+
+    /// Adds or replaces the response for a request
+    pub fn insert(&mut self, method: Method, path: &str, value: serde_json::Value) {
+        self.fixtures.insert(fixture_name(&method, path), value);
+    }
+
+    fn replay<R: DeserializeOwned>(&self, method: &Method, path: &str) -> Result<R, AppError> {
+        let name = fixture_name(method, path);
+        let Some(value) = self.fixtures.get(&name) else {
+            warn!("No replay fixture {} for {} {}", name, method, path);
+            return Err(AppError::ConfigError(format!(
+                "no replay fixture for {} {}",
+                method, path
+            )));
+        };
+        Ok(serde_json::from_value(value.clone())?)
+    }
+}
+
+#[async_trait]
+impl IgHttpClient for ReplayHttpClient {
+    async fn request<T, R>(
+        &self,
+        method: Method,
+        path: &str,
+        _session: &IgSession,
+        _body: Option<&T>,
+        _version: &str,
+    ) -> Result<R, AppError>
+    where
+        for<'de> R: DeserializeOwned + 'static,
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.replay(&method, path)
+    }
+
+    async fn request_no_auth<T, R>(
+        &self,
+        method: Method,
+        path: &str,
+        _body: Option<&T>,
+        _version: &str,
+    ) -> Result<R, AppError>
+    where
+        for<'de> R: DeserializeOwned + 'static,
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.replay(&method, path)
+    }
+}
+
+/// Wraps a client and writes every successful response to
+/// `<dir>/<fixture name>.json`, ready for [`ReplayHttpClient::from_dir`].
+/// A later response to the same request overwrites the earlier one.
+pub struct RecordingHttpClient<C: IgHttpClient> {
+    inner: C,
+    dir: PathBuf,
+}
+
+impl<C: IgHttpClient> RecordingHttpClient<C> {
+    /// Records the responses of `inner` into `dir`, creating it if needed
+    pub fn new(inner: C, dir: impl Into<PathBuf>) -> Result<Self, AppError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { inner, dir })
+    }
+
+    fn record<R: DeserializeOwned>(
+        &self,
+        method: &Method,
+        path: &str,
+        value: serde_json::Value,
+    ) -> Result<R, AppError> {
+        let file = self.dir.join(format!("{}.json", fixture_name(method, path)));
+        fs::write(&file, serde_json::to_string_pretty(&value)?)?;
+        debug!("Recorded {} {} to {}", method, path, file.display());
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+#[async_trait]
+impl<C: IgHttpClient> IgHttpClient for RecordingHttpClient<C> {
+    async fn request<T, R>(
+        &self,
+        method: Method,
+        path: &str,
+        session: &IgSession,
+        body: Option<&T>,
+        version: &str,
+    ) -> Result<R, AppError>
+    where
+        for<'de> R: DeserializeOwned + 'static,
+        T: Serialize + Send + Sync + 'static,
+    {
+        let value = self
+            .inner
+            .request::<T, serde_json::Value>(method.clone(), path, session, body, version)
+            .await?;
+        self.record(&method, path, value)
+    }
+
+    async fn request_with_options<T, R>(
+        &self,
+        method: Method,
+        path: &str,
+        session: &IgSession,
+        body: Option<&T>,
+        version: &str,
+        options: &RequestOptions,
+    ) -> Result<R, AppError>
+    where
+        for<'de> R: DeserializeOwned + 'static,
+        T: Serialize + Send + Sync + 'static,
+    {
+        let value = self
+            .inner
+            .request_with_options::<T, serde_json::Value>(
+                method.clone(),
+                path,
+                session,
+                body,
+                version,
+                options,
+            )
+            .await?;
+        self.record(&method, path, value)
+    }
+
+    fn apply_rate_allowance(&self, trading_per_minute: u32, non_trading_per_minute: u32) {
+        self.inner.apply_rate_allowance(trading_per_minute, non_trading_per_minute);
+    }
+
+    async fn request_no_auth<T, R>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&T>,
+        version: &str,
+    ) -> Result<R, AppError>
+    where
+        for<'de> R: DeserializeOwned + 'static,
+        T: Serialize + Send + Sync + 'static,
+    {
+        let value = self
+            .inner
+            .request_no_auth::<T, serde_json::Value>(method.clone(), path, body, version)
+            .await?;
+        self.record(&method, path, value)
+    }
+}
+
+#[cfg(test)]
+mod tests_replay {
+    use super::*;
+    use crate::application::services::account_service::{AccountService, AccountServiceImpl};
+    use crate::application::services::market_service::{MarketService, MarketServiceImpl};
+    use crate::config::Config;
+    use crate::transport::mock::{test_session, MockHttpClient};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn account() -> serde_json::Value {
+        json!({"accounts": [{
+            "accountId": "ACC1",
+            "accountName": "Demo",
+            "accountType": "SPREADBET",
+            "balance": {"balance": 1000.0, "deposit": 0.0, "profitLoss": 0.0, "available": 1000.0},
+            "currency": "GBP",
+            "status": "ENABLED",
+            "preferred": true
+        }]})
+    }
+
+    #[test]
+    fn test_fixture_name() {
+        assert_eq!(fixture_name(&Method::GET, "markets?searchTerm=DAX"), "GET_markets_searchTerm_DAX");
+        assert_eq!(fixture_name(&Method::POST, "/positions/otc"), "POST_positions_otc");
+    }
+
+    #[tokio::test]
+    async fn test_services_run_against_replay() {
+        let client = Arc::new(ReplayHttpClient::new(HashMap::from([
+            ((Method::GET, "accounts".to_string()), account()),
+            ((Method::GET, "markets?searchTerm=DAX".to_string()), json!({"markets": []})),
+        ])));
+        let config = Arc::new(Config::new());
+        let accounts = AccountServiceImpl::new(config.clone(), client.clone());
+        let markets = MarketServiceImpl::new(config, client);
+
+        let info = accounts.get_accounts(&test_session()).await.unwrap();
+        assert_eq!(info.accounts[0].account_id, "ACC1");
+        assert!(markets.search_markets(&test_session(), "DAX").await.unwrap().markets.is_empty());
+
+        let missing = accounts.get_positions(&test_session()).await;
+        assert!(matches!(missing, Err(AppError::ConfigError(msg)) if msg == "no replay fixture for GET positions"));
+    }
+
+    #[tokio::test]
+    async fn test_recorded_responses_replay() {
+        let dir = std::env::temp_dir().join(format!("ig_client_{}_replay", std::process::id()));
+        let inner = MockHttpClient::new();
+        inner.push_json(account());
+        let recording = Arc::new(RecordingHttpClient::new(inner, &dir).unwrap());
+        let accounts = AccountServiceImpl::new(Arc::new(Config::new()), recording);
+        let live = accounts.get_accounts(&test_session()).await.unwrap();
+
+        let replay = ReplayHttpClient::from_dir(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        let accounts = AccountServiceImpl::new(Arc::new(Config::new()), Arc::new(replay.unwrap()));
+        let replayed = accounts.get_accounts(&test_session()).await.unwrap();
+
+        assert_eq!(replayed.accounts[0].account_id, live.accounts[0].account_id);
+        assert_eq!(replayed.accounts[0].currency, "GBP");
+    }
+}
@@ -3,14 +3,194 @@ use std::collections::HashMap;
 use std::time::Duration;
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tokio_tungstenite::tungstenite::Message;
 use tracing::{debug, error, info, warn};
+use crate::application::models::market::{Epic, Resolution};
+use crate::application::services::instrument_catalog::InstrumentCatalog;
 use crate::config::Config;
 use crate::error::AppError;
 use crate::session::interface::IgSession;
-use crate::transport::model::{AccountUpdate, MarketUpdate, Subscription, SubscriptionType, WebSocketMessage};
-use crate::transport::ws_interface::IgWebSocketClient;
+use crate::transport::lightstreamer::{
+    create_session_message, decode_binary, decode_value, parse_conerr, parse_subscription_status,
+    redact_password, ItemUpdate, ACCOUNT_FIELDS, CHART_FIELDS, MARKET_FIELDS,
+    TRADE_FIELDS,
+};
+use crate::utils::clock::{Clock, SystemClock};
+use chrono::{DateTime, Utc};
+use crate::transport::reconnect::ReconnectBackoff;
+use crate::transport::model::{
+    AccountUpdate, BalanceUpdate, ChartUpdate, ConnectionState, MarketUpdate, Subscription,
+    SubscriptionStatus, SubscriptionType, TradeUpdate, WebSocketMessage,
+};
+use crate::transport::ws_interface::{IgWebSocketClient, WebSocketHandler};
+
+/// How long `disconnect` waits for the close frame to be written
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lightstreamer servers tried when the session does not name one
+const DEFAULT_LIGHTSTREAMER_ENDPOINTS: [&str; 2] = [
+    "wss://apd.marketdatasystems.com/lightstreamer",
+    "wss://push.lightstreamer.com/lightstreamer",
+];
+
+/// Endpoints to try, in order: the one IG assigned at login (converted to a
+/// WebSocket URL), then the defaults
+fn lightstreamer_endpoints(session: &IgSession) -> Vec<String> {
+    let mut endpoints = Vec::new();
+    if let Some(endpoint) = session.lightstreamer_endpoint.as_deref() {
+        let endpoint = endpoint.trim().trim_end_matches('/');
+        let endpoint = if let Some(host) = endpoint.strip_prefix("https://") {
+            format!("wss://{}", host)
+        } else if let Some(host) = endpoint.strip_prefix("http://") {
+            format!("ws://{}", host)
+        } else {
+            endpoint.to_string()
+        };
+        if endpoint.ends_with("/lightstreamer") {
+            endpoints.push(endpoint);
+        } else {
+            endpoints.push(format!("{}/lightstreamer", endpoint));
+        }
+    }
+    for endpoint in DEFAULT_LIGHTSTREAMER_ENDPOINTS {
+        if !endpoints.iter().any(|e| e == endpoint) {
+            endpoints.push(endpoint.to_string());
+        }
+    }
+    endpoints
+}
+
+/// Sends a Lightstreamer heartbeat every `interval` until the outgoing channel closes
+async fn heartbeat_loop(clock: Arc<dyn Clock>, tx: Sender<Message>, interval: Duration) {
+    loop {
+        clock.sleep(interval).await;
+        
+        // Send a heartbeat message in the format expected by Lightstreamer
+        let heartbeat_msg = "\r\n\r\nLS_op=hb\r\n";
+        if let Err(e) = tx.send(Message::Text(heartbeat_msg.into())).await {
+            error!("Failed to send heartbeat: {}", e);
+            break;
+        }
+        
+        debug!("Heartbeat sent");
+    }
+}
+
+/// Whether a line is Lightstreamer's `LOOP` request to rebind the session
+fn is_loop_line(line: &str) -> bool {
+    let line = line.trim_end_matches('\r');
+    line == "LOOP" || line.starts_with("LOOP,")
+}
+
+/// Callback handler shared between clones of the client
+type SharedHandler = Arc<Mutex<Option<Arc<dyn WebSocketHandler>>>>;
+
+/// Connection state shared between the client and its background tasks
+#[derive(Clone)]
+struct ConnectionHealth {
+    connected: Arc<Mutex<bool>>,
+    last_message: Arc<Mutex<Option<DateTime<Utc>>>>,
+    tx: Arc<Mutex<Option<Sender<Message>>>>,
+    handler: SharedHandler,
+}
+
+impl ConnectionHealth {
+    /// Records that something arrived from the server
+    fn touch(&self, at: DateTime<Utc>) {
+        *self.last_message.lock().unwrap() = Some(at);
+    }
+
+    /// Reports a state change to the handler, if one is set
+    fn notify(&self, state: ConnectionState) {
+        let handler = self.handler.lock().unwrap().clone();
+        if let Some(handler) = handler {
+            handler.on_state(state);
+        }
+    }
+
+    /// Clears the connected flag, reporting `Disconnected` if it was set
+    fn mark_disconnected(&self) {
+        let was_connected = std::mem::replace(&mut *self.connected.lock().unwrap(), false);
+        if was_connected {
+            self.notify(ConnectionState::Disconnected);
+        }
+    }
+
+    /// Marks the connection dead and drops the outgoing channel, which stops
+    /// the sender task and closes the socket so the next `connect` starts over
+    fn mark_dead(&self) {
+        self.mark_disconnected();
+        self.tx.lock().unwrap().take();
+    }
+}
+
+/// Calls `connect` until it succeeds, sleeping the next `backoff` delay after
+/// each failure. Gives up with the last error after `max_attempts` attempts
+/// (0 retries forever), or at once if the server rejects the credentials or
+/// the configuration is unusable.
+async fn connect_with_backoff<F, Fut>(
+    clock: &dyn Clock,
+    backoff: &Mutex<ReconnectBackoff>,
+    max_attempts: u32,
+    mut connect: F,
+) -> Result<(), AppError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<(), AppError>>,
+{
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match connect().await {
+            Ok(()) => return Ok(()),
+            Err(AppError::Lightstreamer(e)) if e.is_auth_failure() => {
+                return Err(AppError::Lightstreamer(e));
+            }
+            Err(e @ AppError::ConfigError(_)) => return Err(e),
+            Err(e) if max_attempts > 0 && attempts >= max_attempts => {
+                warn!("Connection failed ({}) after {} attempts", e, attempts);
+                return Err(e);
+            }
+            Err(e) => {
+                let delay = backoff.lock().unwrap().next_delay();
+                warn!("Connection failed ({}), retrying in {:?}", e, delay);
+                clock.sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Checks once per `interval` that the server sent something within the last
+/// `max_missed` intervals, and marks the connection dead otherwise. Returns
+/// whether it did; it also stops once the connection is closed.
+async fn watchdog_loop(
+    clock: Arc<dyn Clock>,
+    health: ConnectionHealth,
+    interval: Duration,
+    max_missed: u32,
+) -> bool {
+    let limit = chrono::Duration::from_std(interval * max_missed).unwrap_or(chrono::Duration::MAX);
+    loop {
+        clock.sleep(interval).await;
+        
+        if !*health.connected.lock().unwrap() {
+            return false;
+        }
+        let last = *health.last_message.lock().unwrap();
+        let Some(last) = last else {
+            continue;
+        };
+        let silence = clock.now() - last;
+        if silence >= limit {
+            warn!("No data from server for {}s, marking connection dead", silence.num_seconds());
+            health.mark_dead();
+            return true;
+        }
+    }
+}
 
 /// Implementation of the WebSocket client
 pub struct IgWebSocketClientImpl {
@@ -18,18 +198,50 @@ pub struct IgWebSocketClientImpl {
     config: Arc<Config>,
     /// Connection state
     connected: Arc<Mutex<bool>>,
+    /// Account of the session used for the last connection
+    account_id: Arc<Mutex<Option<String>>>,
     /// Map of active subscriptions
     subscriptions: Arc<Mutex<HashMap<String, Subscription>>>,
+    /// Market subscription ids by epic
+    market_subscriptions: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    /// Latest unscaled prices per market subscription, to fill in unchanged fields
+    market_snapshots: Arc<Mutex<HashMap<String, MarketUpdate>>>,
+    /// Scaling factors of the streamed prices, by epic
+    catalog: Option<Arc<InstrumentCatalog>>,
+    /// Last acknowledgement state per subscription id
+    subscription_status: Arc<Mutex<HashMap<String, SubscriptionStatus>>>,
     /// Sender for outgoing messages
     tx: Arc<Mutex<Option<Sender<Message>>>>,
-    /// Sender for market updates
-    market_tx: Sender<MarketUpdate>,
-    /// Receiver for market updates
-    market_rx: Arc<Mutex<Option<Receiver<MarketUpdate>>>>,
-    /// Sender for account updates
-    account_tx: Sender<AccountUpdate>,
-    /// Receiver for account updates
-    account_rx: Arc<Mutex<Option<Receiver<AccountUpdate>>>>,
+    /// Sender for market updates, shared by every receiver
+    market_tx: broadcast::Sender<MarketUpdate>,
+    /// Sender for account updates, shared by every receiver
+    account_tx: broadcast::Sender<AccountUpdate>,
+    /// Sender for chart updates, shared by every clone
+    chart_tx: Sender<ChartUpdate>,
+    /// Receiver for chart updates
+    chart_rx: Arc<Mutex<Option<Receiver<ChartUpdate>>>>,
+    /// Sender for trade updates, shared by every clone
+    trade_tx: Sender<TradeUpdate>,
+    /// Receiver for trade updates
+    trade_rx: Arc<Mutex<Option<Receiver<TradeUpdate>>>>,
+    /// Time source for the heartbeat
+    clock: Arc<dyn Clock>,
+    /// Time the last message was received from the server
+    last_message: Arc<Mutex<Option<DateTime<Utc>>>>,
+    /// Reader, heartbeat and watchdog tasks of the current connection
+    tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    /// Task writing outgoing messages; it exits once the outgoing channel closes
+    writer_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// Delay schedule for `connect_with_retry`
+    backoff: Arc<Mutex<ReconnectBackoff>>,
+    /// Callbacks invoked alongside the channels
+    handler: SharedHandler,
+    /// Session of the last successful connection, reused to reconnect
+    session: Arc<Mutex<Option<IgSession>>>,
+    /// Whether a lost connection is re-established on its own; cleared by `disconnect`
+    auto_reconnect: Arc<Mutex<bool>>,
+    /// Background reconnection started after the connection was lost
+    reconnect_task: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
 impl IgWebSocketClientImpl {
@@ -37,21 +249,14 @@ impl IgWebSocketClientImpl {
     async fn connect_direct(&self, session: &IgSession) -> Result<(), AppError> {
         info!("Using direct WebSocket connection approach for Lightstreamer");
         
-        // Define the endpoints to try
-        let endpoints = vec![
-            "wss://apd.marketdatasystems.com/lightstreamer",
-            "wss://push.lightstreamer.com/lightstreamer"
-        ];
+        // Define the endpoints to try, starting with the one assigned at login
+        let endpoints = lightstreamer_endpoints(session);
         
         // Generate a unique client ID
         let client_id = format!("IGCLIENT_{}", uuid::Uuid::new_v4().to_string().replace("-", ""));
         
         // Set adapter set based on environment
-        let adapter_sets = if self.config.rest_api.base_url.contains("demo") {
-            vec!["DEMO-igindexdemo", "DEMO-igstreamer", "DEMO-iggroup"]
-        } else {
-            vec!["PROD-igindexlive", "PROD-ig", "PROD-iggroup"]
-        };
+        let adapter_sets = self.config.environment.lightstreamer_adapter_sets();
         
         // Format the password in the exact format expected by Lightstreamer
         // Remove any whitespace and ensure there are no strange characters
@@ -60,6 +265,9 @@ impl IgWebSocketClientImpl {
             session.token.trim().replace(" ", "")
         );
         
+        // Most recent refusal, reported if no endpoint accepts the session
+        let mut last_refusal = None;
+        
         // Try each endpoint
         for endpoint in &endpoints {
             info!("Trying to connect to Lightstreamer endpoint: {}", endpoint);
@@ -103,21 +311,23 @@ impl IgWebSocketClientImpl {
             let (mut ws_tx, mut ws_rx) = ws_stream.split();
             
             // Try with each adapter set
-            for adapter_set in &adapter_sets {
+            for adapter_set in adapter_sets {
                 info!("Trying with adapter set: {}", adapter_set);
                 info!("Using client ID: {}", client_id);
                 
                 // Send a session creation message
                 // Format based on the official Lightstreamer documentation
-                let create_session_msg = format!(
-                    "\r\n\r\nLS_op2=create\r\nLS_cid={}\r\nLS_adapter_set={}\r\nLS_user={}\r\nLS_password={}\r\n",
-                    client_id,
+                let create_session_msg = create_session_message(
+                    &client_id,
                     adapter_set,
                     session.account_id.trim(),
-                    password
+                    &password,
                 );
                 
-                debug!("Session creation message: {}", create_session_msg.replace("\r\n", "[CR][LF]"));
+                debug!(
+                    "Session creation message: {}",
+                    redact_password(&create_session_msg).replace("\r\n", "[CR][LF]")
+                );
                 match ws_tx.send(Message::Text(create_session_msg.into())).await {
                     Ok(_) => info!("Session creation message sent successfully"),
                     Err(e) => {
@@ -134,6 +344,16 @@ impl IgWebSocketClientImpl {
                         Ok(Message::Text(text)) => {
                             info!("Server response: {}", text);
                             
+                            if let Some(refusal) = parse_conerr(&text) {
+                                error!("Server refused the session: {}", refusal);
+                                if refusal.is_auth_failure() {
+                                    // Other endpoints would reject the same tokens
+                                    return Err(AppError::Lightstreamer(refusal));
+                                }
+                                last_refusal = Some(refusal);
+                                continue; // Try the next adapter set
+                            }
+                            
                             // Check if the response contains an error
                             if text.contains("error") || text.contains("Error") || text.contains("ERROR") || text.contains("Cannot continue") {
                                 error!("Server returned an error: {}", text);
@@ -151,13 +371,17 @@ impl IgWebSocketClientImpl {
                             
                             // If we got here, the connection was successful
                             info!("Successfully connected with adapter set: {}", adapter_set);
+                            *self.account_id.lock().unwrap() = Some(session.account_id.trim().to_string());
+                            *self.session.lock().unwrap() = Some(session.clone());
+                            *self.auto_reconnect.lock().unwrap() = true;
                             
                             // Create channels for sending/receiving messages
-                            let (tx, rx) = mpsc::channel::<Message>(100);
+                            let (tx, rx) = mpsc::channel::<Message>(self.config.websocket.send_buffer_size);
                             *self.tx.lock().unwrap() = Some(tx.clone());
                             
                             // Set connection flag
                             *self.connected.lock().unwrap() = true;
+                            self.health().notify(ConnectionState::Connected);
                             
                             // Start heartbeat
                             self.start_heartbeat().await?;
@@ -197,7 +421,10 @@ impl IgWebSocketClientImpl {
         
         // If we got here, all endpoints failed
         error!("All endpoints failed");
-        return Err(AppError::WebSocketError("All endpoints and adapter sets failed".to_string()));
+        match last_refusal {
+            Some(refusal) => Err(AppError::Lightstreamer(refusal)),
+            None => Err(AppError::WebSocketError("All endpoints and adapter sets failed".to_string())),
+        }
     }
     
     /// Start tasks for receiving and sending messages
@@ -209,31 +436,22 @@ impl IgWebSocketClientImpl {
         mut rx: Receiver<Message>
     ) {
         // Task for handling incoming messages
-        let connected_clone = self.connected.clone();
-        tokio::spawn(async move {
+        let health = self.health();
+        let clock = self.clock.clone();
+        let router = self.clone();
+        let reader = tokio::spawn(async move {
             while let Some(msg_result) = ws_rx.next().await {
                 match msg_result {
                     Ok(msg) => {
+                        health.touch(clock.now());
                         match msg {
-                            Message::Text(text) => {
-                                debug!("Received message: {}", text);
-                                
-                                // Check if it's an error or close message
-                                if text.contains("error") || text.contains("Error") || text.contains("ERROR") {
-                                    error!("Server error: {}", text);
-                                    *connected_clone.lock().unwrap() = false;
-                                    break;
-                                }
-                                
-                                // Check if it's a LOOP message (reconnection)
-                                if text.contains("LOOP") {
-                                    warn!("Server requested LOOP, connection will be reestablished");
-                                    *connected_clone.lock().unwrap() = false;
+                            Message::Text(_) | Message::Binary(_) => {
+                                // Errors here mean the server ended the session
+                                if let Err(e) = router.handle_message(msg).await {
+                                    error!("Server ended the session: {}", e);
+                                    health.mark_disconnected();
                                     break;
                                 }
-                                
-                                // Process market or account update messages
-                                // This would be implemented in a separate function
                             },
                             Message::Close(frame) => {
                                 if let Some(frame) = frame {
@@ -241,7 +459,7 @@ impl IgWebSocketClientImpl {
                                 } else {
                                     error!("Server closed the connection without a reason");
                                 }
-                                *connected_clone.lock().unwrap() = false;
+                                health.mark_disconnected();
                                 break;
                             },
                             _ => {
@@ -251,19 +469,22 @@ impl IgWebSocketClientImpl {
                     },
                     Err(e) => {
                         error!("Error receiving message: {}", e);
-                        *connected_clone.lock().unwrap() = false;
+                        health.mark_disconnected();
                         break;
                     }
                 }
             }
             
             // If we got here, the connection has been closed
-            *connected_clone.lock().unwrap() = false;
+            health.mark_disconnected();
             error!("WebSocket connection closed");
+            router.schedule_reconnect();
         });
         
+        self.tasks.lock().unwrap().push(reader);
+        
         // Task for sending outgoing messages
-        tokio::spawn(async move {
+        let writer = tokio::spawn(async move {
             info!("Starting message sending task...");
             while let Some(msg) = rx.recv().await {
                 // Show the message to be sent
@@ -277,50 +498,357 @@ impl IgWebSocketClientImpl {
                 }
             }
         });
+        *self.writer_task.lock().unwrap() = Some(writer);
+    }
+
+    /// Stops the background tasks of the current connection. The writer is
+    /// given up to [`CLOSE_TIMEOUT`] to flush queued messages, such as the
+    /// close frame, once the outgoing channel has been dropped.
+    async fn stop_tasks(&self) {
+        let tasks: Vec<_> = self.tasks.lock().unwrap().drain(..).collect();
+        for task in &tasks {
+            task.abort();
+        }
+        // Heartbeat clones of the sender are gone, so dropping ours closes the channel
+        self.tx.lock().unwrap().take();
+        let writer = self.writer_task.lock().unwrap().take();
+
+        for task in tasks {
+            let _ = task.await;
+        }
+        if let Some(mut writer) = writer
+            && tokio::time::timeout(CLOSE_TIMEOUT, &mut writer).await.is_err()
+        {
+            warn!("Timed out flushing outgoing messages, aborting writer");
+            writer.abort();
+        }
     }
     
     /// Create a new WebSocket client
     pub fn new(config: Arc<Config>) -> Self {
-        let (market_tx, market_rx) = mpsc::channel(100);
-        let (account_tx, account_rx) = mpsc::channel(100);
+        // `broadcast::channel` panics on 0, which `Config::validate` rejects anyway
+        let capacity = config.websocket.channel_capacity.max(1);
+        let (market_tx, _) = broadcast::channel(capacity);
+        let (account_tx, _) = broadcast::channel(capacity);
+        let (chart_tx, chart_rx) = mpsc::channel(100);
+        let (trade_tx, trade_rx) = mpsc::channel(100);
+        let backoff = ReconnectBackoff::new(&config.websocket);
         
         Self {
             config,
             connected: Arc::new(Mutex::new(false)),
+            account_id: Arc::new(Mutex::new(None)),
             subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            market_subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            market_snapshots: Arc::new(Mutex::new(HashMap::new())),
+            catalog: None,
+            subscription_status: Arc::new(Mutex::new(HashMap::new())),
             tx: Arc::new(Mutex::new(None)),
             market_tx,
-            market_rx: Arc::new(Mutex::new(Some(market_rx))),
             account_tx,
-            account_rx: Arc::new(Mutex::new(Some(account_rx))),
+            chart_tx,
+            chart_rx: Arc::new(Mutex::new(Some(chart_rx))),
+            trade_tx,
+            trade_rx: Arc::new(Mutex::new(Some(trade_rx))),
+            clock: Arc::new(SystemClock),
+            last_message: Arc::new(Mutex::new(None)),
+            tasks: Arc::new(Mutex::new(Vec::new())),
+            writer_task: Arc::new(Mutex::new(None)),
+            backoff: Arc::new(Mutex::new(backoff)),
+            handler: Arc::new(Mutex::new(None)),
+            session: Arc::new(Mutex::new(None)),
+            auto_reconnect: Arc::new(Mutex::new(false)),
+            reconnect_task: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Registers callbacks that receive decoded market and account updates,
+    /// in addition to the channels, and connection state changes. Replaces
+    /// any previous handler, for this client and its clones.
+    pub fn set_handler(&self, handler: Arc<dyn WebSocketHandler>) {
+        *self.handler.lock().unwrap() = Some(handler);
+    }
+
+    fn handler(&self) -> Option<Arc<dyn WebSocketHandler>> {
+        self.handler.lock().unwrap().clone()
+    }
+
+    /// Connects, retrying with exponential backoff until it succeeds. Gives
+    /// up with the last error after `websocket.reconnect_max_attempts`
+    /// attempts, or at once if the server rejects the session credentials.
+    pub async fn connect_with_retry(&self, session: &IgSession) -> Result<(), AppError> {
+        if self.is_connected() {
+            return Ok(());
+        }
+        self.backoff.lock().unwrap().connection_lost(self.clock.now());
+
+        connect_with_backoff(
+            self.clock.as_ref(),
+            &self.backoff,
+            self.config.websocket.reconnect_max_attempts,
+            || self.connect(session),
+        )
+        .await?;
+        self.backoff.lock().unwrap().connected(self.clock.now());
+        Ok(())
+    }
+
+    /// Starts reconnecting in the background after the connection was lost,
+    /// unless `disconnect` was called or a reconnection is already running.
+    /// Once connected again, every subscription is requested again.
+    fn schedule_reconnect(&self) {
+        if !*self.auto_reconnect.lock().unwrap() {
+            return;
+        }
+        let Some(session) = self.session.lock().unwrap().clone() else {
+            return;
+        };
+        let mut task = self.reconnect_task.lock().unwrap();
+        if task.as_ref().is_some_and(|task| !task.is_finished()) {
+            return;
+        }
+        info!("Connection lost, reconnecting");
+        let client = self.clone();
+        *task = Some(tokio::spawn(async move {
+            match client.connect_with_retry(&session).await {
+                Ok(()) => client.resubscribe_all().await,
+                Err(e) => error!("Giving up reconnecting: {}", e),
+            }
+        }));
+    }
+
+    /// Requests every known subscription again; a new Lightstreamer session
+    /// starts without any
+    async fn resubscribe_all(&self) {
+        let subscriptions: Vec<_> = self.subscriptions.lock().unwrap().values().cloned().collect();
+        for subscription in subscriptions {
+            let id = subscription.id.clone();
+            if let Err(e) = self.send_message(WebSocketMessage::Subscribe { subscription }).await {
+                warn!("Failed to resubscribe {}: {}", id, e);
+            }
+        }
+    }
+
+    /// Time the last message (data or server probe) was received, if any
+    pub fn last_message_at(&self) -> Option<DateTime<Utc>> {
+        *self.last_message.lock().unwrap()
+    }
+
+    fn health(&self) -> ConnectionHealth {
+        ConnectionHealth {
+            connected: self.connected.clone(),
+            last_message: self.last_message.clone(),
+            tx: self.tx.clone(),
+            handler: self.handler.clone(),
+        }
+    }
+
+    /// Removes a subscription from the local maps, returning it if it existed
+    fn forget_subscription(&self, subscription_id: &str) -> Option<Subscription> {
+        let subscription = self.subscriptions.lock().unwrap().remove(subscription_id)?;
+        self.subscription_status.lock().unwrap().remove(subscription_id);
+        if subscription.subscription_type == SubscriptionType::Market {
+            self.market_snapshots.lock().unwrap().remove(subscription_id);
+            let mut by_epic = self.market_subscriptions.lock().unwrap();
+            if let Some(ids) = by_epic.get_mut(&subscription.item) {
+                ids.retain(|id| id != subscription_id);
+                if ids.is_empty() {
+                    by_epic.remove(&subscription.item);
+                }
+            }
+        }
+        Some(subscription)
+    }
+
+    /// Account to stream: the connected session's, or the configured one before connecting
+    fn account_id(&self) -> String {
+        self.account_id
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| self.config.credentials.account_id.clone())
+    }
+
+    /// Replaces the time source, e.g. with a `MockClock` in tests
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Divides streamed prices by the scaling factor `catalog` holds for
+    /// their epic. Without a catalog, or for epics it lacks, market updates
+    /// carry the raw values.
+    pub fn with_instrument_catalog(mut self, catalog: Arc<InstrumentCatalog>) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
+    pub fn instrument_catalog(&self) -> Option<&Arc<InstrumentCatalog>> {
+        self.catalog.as_ref()
+    }
+
+    fn scaling_factor(&self, epic: &str) -> Option<i64> {
+        self.catalog.as_ref()?.scaling_factor(epic)
+    }
+
+    /// Splits a Lightstreamer update line (`U,<subId>,<item>,<v1>|<v2>|...`)
+    /// and hands its raw values to [`route_update`](Self::route_update)
+    async fn dispatch_update(&self, line: &str) {
+        let Some(rest) = line.trim_end_matches(['\r', '\n']).strip_prefix("U,") else {
+            return;
+        };
+        let mut parts = rest.splitn(3, ',');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(sub_id), Some(_item), Some(values)) => {
+                let fields: Vec<&str> = values.split('|').collect();
+                self.route_update(sub_id, &fields).await;
+            }
+            _ => debug!("Malformed update line: {}", line),
+        }
+    }
+
+    /// Decodes the raw field values of an update for `sub_id`, in schema
+    /// order, and forwards it to the typed channel of the subscription's
+    /// type. Updates for unknown subscriptions are dropped.
+    pub async fn route_update(&self, sub_id: &str, fields: &[&str]) {
+        self.route(ItemUpdate {
+            subscription_id: sub_id.to_string(),
+            item: 1,
+            fields: fields.iter().map(|field| decode_value(field)).collect(),
+        })
+        .await;
+    }
+
+    async fn route(&self, update: ItemUpdate) {
+        let subscription = self.subscriptions.lock().unwrap().get(&update.subscription_id).cloned();
+        let Some(subscription) = subscription else {
+            debug!("Update for unknown subscription {}", update.subscription_id);
+            return;
+        };
+
+        match subscription.subscription_type {
+            SubscriptionType::Market => self.route_market(&subscription, &update),
+            SubscriptionType::Account => self.route_account(&subscription, &update),
+            SubscriptionType::Chart => self.route_chart(&subscription, &update),
+            SubscriptionType::Trade => self.route_trade(&subscription, &update),
+        }
+    }
+
+    fn route_market(&self, subscription: &Subscription, update: &ItemUpdate) {
+        let market = {
+            let mut snapshots = self.market_snapshots.lock().unwrap();
+            let previous = snapshots.get(&subscription.id);
+            let market = MarketUpdate::from_update(&subscription.item, update, previous);
+            if let Some(market) = &market {
+                snapshots.insert(subscription.id.clone(), market.clone());
+            }
+            market
+        };
+        let market = match self.scaling_factor(&subscription.item) {
+            Some(factor) => market.map(|market| market.scaled(factor)),
+            None => market,
+        };
+        match market {
+            Some(market) => {
+                if let Some(handler) = self.handler() {
+                    handler.on_market(market.clone());
+                }
+                if self.market_tx.send(market).is_err() {
+                    debug!("Market update dropped, no receiver");
+                }
+            }
+            None => debug!("Incomplete first update for {}", subscription.item),
+        }
+    }
+
+    fn route_account(&self, subscription: &Subscription, update: &ItemUpdate) {
+        let account = AccountUpdate::Balance(BalanceUpdate::from_update(&subscription.item, update));
+        if let Some(handler) = self.handler() {
+            handler.on_account(account.clone());
+        }
+        if self.account_tx.send(account).is_err() {
+            debug!("Account update dropped, no receiver");
+        }
+    }
+
+    fn route_chart(&self, subscription: &Subscription, update: &ItemUpdate) {
+        // Chart items are `{epic}:{scale}`
+        let Some((epic, resolution)) = subscription
+            .item
+            .rsplit_once(':')
+            .and_then(|(epic, scale)| Some((epic, Resolution::from_chart_scale(scale)?)))
+        else {
+            warn!("Malformed chart item: {}", subscription.item);
+            return;
+        };
+        let chart = ChartUpdate::from_update(epic, resolution, update);
+        // Waiting for room would stall the reader and every other feed with it
+        match self.chart_tx.try_send(chart) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => warn!("Chart update dropped, receiver is behind"),
+            Err(mpsc::error::TrySendError::Closed(_)) => debug!("Chart update dropped, no receiver"),
+        }
+    }
+
+    fn route_trade(&self, subscription: &Subscription, update: &ItemUpdate) {
+        match TradeUpdate::from_update(&subscription.item, update) {
+            Ok(trade) => match self.trade_tx.try_send(trade) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(_)) => warn!("Trade update dropped, receiver is behind"),
+                Err(mpsc::error::TrySendError::Closed(_)) => debug!("Trade update dropped, no receiver"),
+            },
+            Err(e) => warn!("Failed to decode trade update: {}", e),
         }
     }
     
-    /// Handle incoming WebSocket messages
+    /// Stores the status carried by a `SUBOK`, `SUBCMD` or `REQERR` line
+    /// for a known subscription
+    fn record_subscription_status(&self, id: String, status: SubscriptionStatus) {
+        if !self.subscriptions.lock().unwrap().contains_key(&id) {
+            debug!("Acknowledgement for unknown subscription {}", id);
+            return;
+        }
+        if let SubscriptionStatus::Failed { code, message } = &status {
+            warn!("Subscription {} refused: {} {}", id, code, message);
+        }
+        self.subscription_status.lock().unwrap().insert(id, status);
+    }
+
+    /// Handle incoming WebSocket messages. A frame may batch several lines
+    /// of any kind; each is handled on its own, in order. Fails with the
+    /// server's error at a `CONERR`/`ERROR` line, or at a `LOOP` request,
+    /// which both end the session.
     async fn handle_message(&self, msg: Message) -> Result<(), AppError> {
-        if msg.is_text() {
-            let text = msg.to_text().unwrap();
-            debug!("Message received: {}", text.replace("\r\n", "[CR][LF]\n"));
-            
-            // For Lightstreamer messages, we need a different parser
-            if text.contains("SUBOK") || text.contains("SUBCMD") || text.contains("CONOK") {
-                debug!("Lightstreamer control message: {}", text);
-            } else {
-                // Try to parse as JSON
-                match serde_json::from_str::<serde_json::Value>(text) {
-                    Ok(json) => {
-                        debug!("Parsed JSON message: {}", json);
-                        // Process the JSON message
-                    },
-                    Err(e) => {
-                        warn!("Could not parse message as JSON: {}", e);
-                        // Could be another Lightstreamer format
-                        debug!("Message content: {}", text);
-                    }
+        let text = match msg {
+            Message::Text(text) => text.to_string(),
+            // Compressed or binary payloads carry the same protocol text
+            Message::Binary(bytes) => match decode_binary(&bytes) {
+                Ok(text) => text,
+                Err(e) => {
+                    warn!("Skipping undecodable binary frame: {}", e);
+                    return Ok(());
                 }
+            },
+            _ => return Ok(()),
+        };
+        debug!("Message received: {}", text.replace("\r\n", "[CR][LF]\n"));
+
+        for line in text.lines().map(|line| line.trim_end_matches('\r')) {
+            if line.starts_with("U,") {
+                self.dispatch_update(line).await;
+            } else if let Some((id, status)) = parse_subscription_status(line) {
+                self.record_subscription_status(id, status);
+            } else if let Some(e) = parse_conerr(line) {
+                // Only whole control lines end the session; update values
+                // may contain any text
+                return Err(AppError::Lightstreamer(e));
+            } else if is_loop_line(line) {
+                return Err(AppError::WebSocketError("server requested LOOP".to_string()));
+            } else if !line.is_empty() {
+                debug!("Lightstreamer control line: {}", line);
             }
         }
-        
+
         Ok(())
     }
     
@@ -328,23 +856,27 @@ impl IgWebSocketClientImpl {
     async fn process_message(&self, ws_msg: WebSocketMessage) -> Result<(), AppError> {
         match ws_msg {
             WebSocketMessage::Subscribe { subscription } => {
+                self.subscription_status
+                    .lock()
+                    .unwrap()
+                    .insert(subscription.id.clone(), SubscriptionStatus::Pending);
                 // Format and send a subscription message
                 let subscription_msg = match subscription.subscription_type {
                     SubscriptionType::Market => {
-                        format!("\r\n\r\nLS_op=add\r\nLS_subId={}\r\nLS_mode=MERGE\r\nLS_group=MARKET:{}\r\nLS_schema=PRICE\r\n", 
-                            subscription.id, subscription.item)
+                        format!("\r\n\r\nLS_op=add\r\nLS_reqId={}\r\nLS_subId={}\r\nLS_mode=MERGE\r\nLS_group=MARKET:{}\r\nLS_schema={}\r\n", 
+                            subscription.id, subscription.id, subscription.item, MARKET_FIELDS.join(" "))
                     },
                     SubscriptionType::Account => {
-                        format!("\r\n\r\nLS_op=add\r\nLS_subId={}\r\nLS_mode=MERGE\r\nLS_group=ACCOUNT:{}\r\nLS_schema=ACCOUNT\r\n", 
-                            subscription.id, subscription.item)
+                        format!("\r\n\r\nLS_op=add\r\nLS_reqId={}\r\nLS_subId={}\r\nLS_mode=MERGE\r\nLS_group=ACCOUNT:{}\r\nLS_schema={}\r\n", 
+                            subscription.id, subscription.id, subscription.item, ACCOUNT_FIELDS.join(" "))
                     },
                     SubscriptionType::Trade => {
-                        format!("\r\n\r\nLS_op=add\r\nLS_subId={}\r\nLS_mode=MERGE\r\nLS_group=TRADE:{}\r\nLS_schema=TRADE\r\n", 
-                            subscription.id, subscription.item)
+                        format!("\r\n\r\nLS_op=add\r\nLS_reqId={}\r\nLS_subId={}\r\nLS_mode=MERGE\r\nLS_group=TRADE:{}\r\nLS_schema={}\r\n", 
+                            subscription.id, subscription.id, subscription.item, TRADE_FIELDS.join(" "))
                     },
                     SubscriptionType::Chart => {
-                        format!("\r\n\r\nLS_op=add\r\nLS_subId={}\r\nLS_mode=MERGE\r\nLS_group=CHART:{}\r\nLS_schema=CHART\r\n", 
-                            subscription.id, subscription.item)
+                        format!("\r\n\r\nLS_op=add\r\nLS_reqId={}\r\nLS_subId={}\r\nLS_mode=MERGE\r\nLS_group=CHART:{}\r\nLS_schema={}\r\n", 
+                            subscription.id, subscription.id, subscription.item, CHART_FIELDS.join(" "))
                     }
                 };
                 
@@ -403,6 +935,23 @@ impl IgWebSocketClientImpl {
         }
     }
     
+    /// Queues a raw message without waiting. Fails with "send buffer full"
+    /// when `websocket.send_buffer_size` messages are already waiting to be written.
+    pub fn try_send(&self, msg: Message) -> Result<(), AppError> {
+        let tx = self.tx.lock().unwrap().as_ref().cloned();
+        let Some(tx) = tx else {
+            return Err(AppError::WebSocketError("WebSocket not connected".to_string()));
+        };
+        tx.try_send(msg).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => {
+                AppError::WebSocketError("send buffer full".to_string())
+            }
+            mpsc::error::TrySendError::Closed(_) => {
+                AppError::WebSocketError("WebSocket not connected".to_string())
+            }
+        })
+    }
+    
     /// Send a message to the WebSocket server
     async fn send_message(&self, msg: WebSocketMessage) -> Result<(), AppError> {
         if !*self.connected.lock().unwrap() {
@@ -418,23 +967,24 @@ impl IgWebSocketClientImpl {
         if let Some(tx) = self.tx.lock().unwrap().as_ref() {
             let tx_clone = tx.clone();
             
+            let ws_config = &self.config.websocket;
+            let interval = Duration::from_secs(ws_config.heartbeat_interval);
+            
             // Start a task to send heartbeat messages
-            tokio::spawn(async move {
-                let mut interval = tokio::time::interval(Duration::from_secs(30));
-                
-                loop {
-                    interval.tick().await;
-                    
-                    // Send a heartbeat message in the format expected by Lightstreamer
-                    let heartbeat_msg = "\r\n\r\nLS_op=hb\r\n";
-                    if let Err(e) = tx_clone.send(Message::Text(heartbeat_msg.into())).await {
-                        error!("Failed to send heartbeat: {}", e);
-                        break;
-                    }
-                    
-                    debug!("Heartbeat sent");
+            let heartbeat = tokio::spawn(heartbeat_loop(self.clock.clone(), tx_clone, interval));
+            
+            // Start a task that detects a silent server
+            let health = self.health();
+            health.touch(self.clock.now());
+            let clock = self.clock.clone();
+            let max_missed = ws_config.max_missed_heartbeats;
+            let reconnector = self.clone();
+            let watchdog = tokio::spawn(async move {
+                if watchdog_loop(clock, health, interval, max_missed).await {
+                    reconnector.schedule_reconnect();
                 }
             });
+            self.tasks.lock().unwrap().extend([heartbeat, watchdog]);
             
             Ok(())
         } else {
@@ -451,15 +1001,35 @@ impl IgWebSocketClient for IgWebSocketClientImpl {
         if *self.connected.lock().unwrap() {
             return Ok(());
         }
+        // `mpsc::channel` panics on 0; fail before opening a socket instead
+        if self.config.websocket.send_buffer_size == 0 {
+            return Err(AppError::ConfigError(
+                "websocket.send_buffer_size must be greater than 0".to_string(),
+            ));
+        }
         
         info!("Connecting to Lightstreamer server...");
         
+        // Tasks of a connection that died on its own may still be around
+        self.stop_tasks().await;
+        
         // Use the direct WebSocket connection approach
         info!("Using direct WebSocket connection approach...");
-        return self.connect_direct(session).await;
+        let health = self.health();
+        health.notify(ConnectionState::Connecting);
+        let result = self.connect_direct(session).await;
+        if result.is_err() {
+            health.notify(ConnectionState::Disconnected);
+        }
+        result
     }
     
     async fn disconnect(&self) -> Result<(), AppError> {
+        // Stop any reconnection first, it may be what is connecting right now
+        *self.auto_reconnect.lock().unwrap() = false;
+        if let Some(task) = self.reconnect_task.lock().unwrap().take() {
+            task.abort();
+        }
         if !*self.connected.lock().unwrap() {
             return Ok(());
         }
@@ -481,14 +1051,19 @@ impl IgWebSocketClient for IgWebSocketClientImpl {
         }
         
         // Set connected flag
-        *self.connected.lock().unwrap() = false;
+        self.health().mark_disconnected();
+        
+        self.stop_tasks().await;
         
         info!("Disconnected from WebSocket server");
         
         Ok(())
     }
     
-    async fn subscribe_market(&self, epic: &str) -> Result<String, AppError> {
+    async fn subscribe_market(&self, epic: impl Into<Epic> + Send) -> Result<String, AppError> {
+        let epic = epic.into();
+        epic.validate()?;
+        
         // Generate a subscription ID
         let subscription_id = format!("MARKET-{}", uuid::Uuid::new_v4());
         
@@ -504,6 +1079,15 @@ impl IgWebSocketClient for IgWebSocketClientImpl {
             let mut subscriptions = self.subscriptions.lock().unwrap();
             subscriptions.insert(subscription_id.clone(), subscription.clone());
         }
+        self.market_subscriptions
+            .lock()
+            .unwrap()
+            .entry(epic.to_string())
+            .or_default()
+            .push(subscription_id.clone());
+        if self.scaling_factor(epic.as_str()).is_none() {
+            warn!("No scaling factor known for {}, streaming raw prices", epic);
+        }
         
         // Send subscription message
         self.send_message(WebSocketMessage::Subscribe {
@@ -517,12 +1101,13 @@ impl IgWebSocketClient for IgWebSocketClientImpl {
     async fn subscribe_account(&self) -> Result<String, AppError> {
         // Generate a subscription ID
         let subscription_id = format!("ACCOUNT-{}", uuid::Uuid::new_v4());
+        let account_id = self.account_id();
         
         // Create subscription
         let subscription = Subscription {
             id: subscription_id.clone(),
             subscription_type: SubscriptionType::Account,
-            item: "ACCOUNT".to_string(),
+            item: account_id.clone(),
         };
         
         // Store subscription
@@ -536,20 +1121,77 @@ impl IgWebSocketClient for IgWebSocketClientImpl {
             subscription,
         }).await?;
         
-        info!("Subscribed to account updates");
+        info!("Subscribed to account updates for {}", account_id);
         Ok(subscription_id)
     }
     
-    async fn unsubscribe(&self, subscription_id: &str) -> Result<(), AppError> {
-        // Check if subscription exists
+    async fn subscribe_chart(
+        &self,
+        epic: impl Into<Epic> + Send,
+        resolution: Resolution,
+    ) -> Result<String, AppError> {
+        let epic = epic.into();
+        epic.validate()?;
+        let scale = resolution.chart_scale().ok_or_else(|| {
+            AppError::WebSocketError(format!("{} cannot be streamed as a chart", resolution))
+        })?;
+
+        // Generate a subscription ID
+        let subscription_id = format!("CHART-{}", uuid::Uuid::new_v4());
+
+        // Create subscription
+        let subscription = Subscription {
+            id: subscription_id.clone(),
+            subscription_type: SubscriptionType::Chart,
+            item: format!("{}:{}", epic, scale),
+        };
+
+        // Store subscription
         {
             let mut subscriptions = self.subscriptions.lock().unwrap();
-            if !subscriptions.contains_key(subscription_id) {
-                return Err(AppError::WebSocketError(format!("Subscription not found: {}", subscription_id)));
-            }
-            
-            // Remove subscription
-            subscriptions.remove(subscription_id);
+            subscriptions.insert(subscription_id.clone(), subscription.clone());
+        }
+
+        // Send subscription message
+        self.send_message(WebSocketMessage::Subscribe {
+            subscription,
+        }).await?;
+
+        info!("Subscribed to {} chart updates for {}", resolution, epic);
+        Ok(subscription_id)
+    }
+    
+    async fn subscribe_trades(&self) -> Result<String, AppError> {
+        // Generate a subscription ID
+        let subscription_id = format!("TRADE-{}", uuid::Uuid::new_v4());
+        let account_id = self.account_id();
+
+        // Create subscription
+        let subscription = Subscription {
+            id: subscription_id.clone(),
+            subscription_type: SubscriptionType::Trade,
+            item: account_id.clone(),
+        };
+
+        // Store subscription
+        {
+            let mut subscriptions = self.subscriptions.lock().unwrap();
+            subscriptions.insert(subscription_id.clone(), subscription.clone());
+        }
+
+        // Send subscription message
+        self.send_message(WebSocketMessage::Subscribe {
+            subscription,
+        }).await?;
+
+        info!("Subscribed to trade updates for account {}", account_id);
+        Ok(subscription_id)
+    }
+
+    async fn unsubscribe(&self, subscription_id: &str) -> Result<(), AppError> {
+        // Remove subscription, checking that it exists
+        if self.forget_subscription(subscription_id).is_none() {
+            return Err(AppError::WebSocketError(format!("Subscription not found: {}", subscription_id)));
         }
         
         // Send unsubscribe message
@@ -560,49 +1202,986 @@ impl IgWebSocketClient for IgWebSocketClientImpl {
         info!("Unsubscribed from {}", subscription_id);
         Ok(())
     }
+
+    async fn unsubscribe_market(&self, epic: impl Into<Epic> + Send) -> Result<(), AppError> {
+        let epic = epic.into();
+        let ids = self.market_subscriptions.lock().unwrap().get(epic.as_str()).cloned();
+        let Some(ids) = ids else {
+            return Err(AppError::WebSocketError(format!("No market subscription for {}", epic)));
+        };
+
+        for id in ids {
+            self.unsubscribe(&id).await?;
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe_all(&self) -> Result<(), AppError> {
+        let ids: Vec<String> = self.subscriptions.lock().unwrap().keys().cloned().collect();
+        for id in ids {
+            self.unsubscribe(&id).await?;
+        }
+        info!("Unsubscribed from all subscriptions");
+        Ok(())
+    }
     
     fn is_connected(&self) -> bool {
         *self.connected.lock().unwrap()
     }
     
-    fn market_updates(&self) -> Receiver<MarketUpdate> {
-        let mut rx_guard = self.market_rx.lock().unwrap();
-        if let Some(rx) = rx_guard.take() {
-            return rx;
-        }
-        
-        // Create a new channel if none exists
-        let (_, rx) = mpsc::channel::<MarketUpdate>(100);
-        rx
+    fn active_subscriptions(&self) -> Vec<Subscription> {
+        self.subscription_status()
+            .into_iter()
+            .filter(|(_, status)| status.is_active())
+            .map(|(subscription, _)| subscription)
+            .collect()
+    }
+
+    fn subscription_status(&self) -> Vec<(Subscription, SubscriptionStatus)> {
+        let subscriptions = self.subscriptions.lock().unwrap();
+        let statuses = self.subscription_status.lock().unwrap();
+        subscriptions
+            .values()
+            .map(|subscription| {
+                let status = statuses
+                    .get(&subscription.id)
+                    .cloned()
+                    .unwrap_or(SubscriptionStatus::Pending);
+                (subscription.clone(), status)
+            })
+            .collect()
+    }
+
+    fn market_updates(&self) -> broadcast::Receiver<MarketUpdate> {
+        self.market_tx.subscribe()
     }
     
-    fn account_updates(&self) -> Receiver<AccountUpdate> {
-        let mut rx_guard = self.account_rx.lock().unwrap();
-        if let Some(rx) = rx_guard.take() {
-            return rx;
-        }
-        
-        // Create a new channel if none exists
-        let (_, rx) = mpsc::channel::<AccountUpdate>(100);
-        rx
+    fn account_updates(&self) -> broadcast::Receiver<AccountUpdate> {
+        self.account_tx.subscribe()
+    }
+
+    fn chart_updates(&self) -> Result<Receiver<ChartUpdate>, AppError> {
+        self.chart_rx.lock().unwrap().take().ok_or_else(|| {
+            AppError::WebSocketError("chart updates receiver already taken".to_string())
+        })
+    }
+
+    fn trade_updates(&self) -> Result<Receiver<TradeUpdate>, AppError> {
+        self.trade_rx.lock().unwrap().take().ok_or_else(|| {
+            AppError::WebSocketError("trade updates receiver already taken".to_string())
+        })
     }
 }
 
 // Implement Clone for IgWebSocketClientImpl
 impl Clone for IgWebSocketClientImpl {
     fn clone(&self) -> Self {
-        let (market_tx, market_rx) = mpsc::channel(100);
-        let (account_tx, account_rx) = mpsc::channel(100);
-        
         Self {
             config: self.config.clone(),
             connected: self.connected.clone(),
+            account_id: self.account_id.clone(),
             subscriptions: self.subscriptions.clone(),
+            market_subscriptions: self.market_subscriptions.clone(),
+            market_snapshots: self.market_snapshots.clone(),
+            catalog: self.catalog.clone(),
+            subscription_status: self.subscription_status.clone(),
             tx: self.tx.clone(),
-            market_tx,
-            market_rx: Arc::new(Mutex::new(Some(market_rx))),
-            account_tx,
-            account_rx: Arc::new(Mutex::new(Some(account_rx))),
+            market_tx: self.market_tx.clone(),
+            account_tx: self.account_tx.clone(),
+            chart_tx: self.chart_tx.clone(),
+            chart_rx: self.chart_rx.clone(),
+            trade_tx: self.trade_tx.clone(),
+            trade_rx: self.trade_rx.clone(),
+            clock: self.clock.clone(),
+            last_message: self.last_message.clone(),
+            tasks: self.tasks.clone(),
+            writer_task: self.writer_task.clone(),
+            backoff: self.backoff.clone(),
+            handler: self.handler.clone(),
+            session: self.session.clone(),
+            auto_reconnect: self.auto_reconnect.clone(),
+            reconnect_task: self.reconnect_task.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_websocket_client {
+    use super::*;
+    use crate::transport::mock::test_session;
+
+    /// Handler that records every market update, account update and state
+    #[derive(Default)]
+    struct Recording {
+        markets: Mutex<Vec<MarketUpdate>>,
+        accounts: Mutex<Vec<AccountUpdate>>,
+        states: Mutex<Vec<ConnectionState>>,
+    }
+
+    impl WebSocketHandler for Recording {
+        fn on_market(&self, update: MarketUpdate) {
+            self.markets.lock().unwrap().push(update);
+        }
+
+        fn on_account(&self, update: AccountUpdate) {
+            self.accounts.lock().unwrap().push(update);
+        }
+
+        fn on_state(&self, state: ConnectionState) {
+            self.states.lock().unwrap().push(state);
+        }
+    }
+
+    /// Registers subscription `id` on `item` as if it had been requested
+    fn subscribe_fixture(
+        client: &IgWebSocketClientImpl,
+        id: &str,
+        subscription_type: SubscriptionType,
+        item: &str,
+    ) {
+        client.subscriptions.lock().unwrap().insert(
+            id.to_string(),
+            Subscription {
+                id: id.to_string(),
+                subscription_type,
+                item: item.to_string(),
+            },
+        );
+    }
+
+    /// Registers market subscription `id` on `epic` as if it had been requested
+    fn subscribe_market_fixture(client: &IgWebSocketClientImpl, id: &str, epic: &str) {
+        subscribe_fixture(client, id, SubscriptionType::Market, epic);
+    }
+
+    /// Catalog holding GBP/USD, quoted with a scaling factor of 100
+    async fn gbpusd_catalog() -> Arc<InstrumentCatalog> {
+        use crate::application::services::market_service::MarketServiceImpl;
+        use crate::transport::mock::MockHttpClient;
+
+        let http = Arc::new(MockHttpClient::new());
+        http.push_json(serde_json::json!({
+            "instrument": {"epic": "CS.D.GBPUSD.TODAY.IP", "name": "GBP/USD", "instrumentType": "CURRENCIES", "expiry": "-"},
+            "snapshot": {"marketStatus": "TRADEABLE", "scalingFactor": 100}
+        }));
+        let markets = MarketServiceImpl::new(Arc::new(Config::new()), http);
+        let catalog = InstrumentCatalog::build(&markets, &test_session(), ["CS.D.GBPUSD.TODAY.IP"])
+            .await
+            .unwrap();
+        Arc::new(catalog)
+    }
+
+    #[test]
+    fn test_session_endpoint_tried_first() {
+        let mut session = test_session();
+        session.lightstreamer_endpoint = Some("https://demo-apd.marketdatasystems.com".to_string());
+
+        assert_eq!(
+            lightstreamer_endpoints(&session),
+            vec![
+                "wss://demo-apd.marketdatasystems.com/lightstreamer",
+                "wss://apd.marketdatasystems.com/lightstreamer",
+                "wss://push.lightstreamer.com/lightstreamer",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zero_send_buffer_rejected_before_connecting() {
+        let mut config = Config::new();
+        config.websocket.send_buffer_size = 0;
+        let client = IgWebSocketClientImpl::new(Arc::new(config));
+
+        let result = client.connect_with_retry(&test_session()).await;
+
+        assert!(matches!(result, Err(AppError::ConfigError(msg)) if msg.contains("send_buffer_size")));
+        assert!(!client.is_connected());
+    }
+
+    #[tokio::test]
+    async fn test_untaken_chart_receiver_does_not_stall_routing() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let mut market_rx = client.market_updates();
+        for (id, subscription_type, item) in [
+            ("MARKET-1", SubscriptionType::Market, "IX.D.DAX.IFMM.IP"),
+            ("CHART-1", SubscriptionType::Chart, "IX.D.DAX.IFMM.IP:1MINUTE"),
+        ] {
+            subscribe_fixture(&client, id, subscription_type, item);
+        }
+
+        // A clone routes into the same channel, whose receiver nobody took
+        let router = client.clone();
+        let candle = ["1715594400000", "12", "18000", "18010", "17990", "18005", "18001", "18011", "17991", "18006", "1"];
+        tokio::time::timeout(Duration::from_secs(5), async {
+            for _ in 0..150 {
+                router.route_update("CHART-1", &candle).await;
+            }
+            router.route_update("MARKET-1", &["18000.5", "18001.5", "12:00:00"]).await;
+        })
+        .await
+        .expect("routing blocked on a full chart channel");
+
+        assert_eq!(market_rx.try_recv().unwrap().bid, 18000.5);
+        assert_eq!(client.chart_updates().unwrap().len(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_zero_channel_capacity_still_delivers() {
+        let mut config = Config::new();
+        config.websocket.channel_capacity = 0;
+        let client = IgWebSocketClientImpl::new(Arc::new(config));
+        let mut market_rx = client.market_updates();
+        subscribe_market_fixture(&client, "MARKET-1", "IX.D.DAX.IFMM.IP");
+
+        client.route_update("MARKET-1", &["18000.5", "18001.5", "12:00:00"]).await;
+
+        assert_eq!(market_rx.try_recv().unwrap().bid, 18000.5);
+    }
+
+    #[tokio::test]
+    async fn test_balance_update_dispatched_for_session_account() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let (tx, mut sent) = mpsc::channel(10);
+        *client.tx.lock().unwrap() = Some(tx);
+        *client.connected.lock().unwrap() = true;
+        *client.account_id.lock().unwrap() = Some("ACC1".to_string());
+        let mut account_rx = client.account_updates();
+
+        let id = client.subscribe_account().await.unwrap();
+        let Message::Text(request) = sent.try_recv().unwrap() else {
+            panic!("expected a text frame");
+        };
+        assert!(request.contains("LS_group=ACCOUNT:ACC1\r\n"));
+        assert!(request.contains("LS_schema=PNL DEPOSIT AVAILABLE_CASH FUNDS MARGIN\r\n"));
+
+        let update = format!("U,{},1,25.5|5000|4000|5025.5|1000\r\n", id);
+        client.handle_message(Message::Text(update.into())).await.unwrap();
+
+        let AccountUpdate::Balance(balance) = account_rx.try_recv().unwrap();
+        assert_eq!(balance.account_id, "ACC1");
+        assert_eq!(balance.pnl, Some(25.5));
+        assert_eq!(balance.margin, Some(1000.0));
+    }
+
+    #[tokio::test]
+    async fn test_chart_update_dispatched_to_receiver() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let mut chart_rx = client.chart_updates().unwrap();
+        subscribe_fixture(&client, "CHART-1", SubscriptionType::Chart, "IX.D.DAX.IFMM.IP:5MINUTE");
+
+        client
+            .handle_message(Message::Text(
+                "U,CHART-1,1,1715594400000|12|18000|18010|17990|18005|18001|18011|17991|18006|0\r\n".into(),
+            ))
+            .await
+            .unwrap();
+
+        let chart = chart_rx.try_recv().unwrap();
+        assert_eq!(chart.epic, "IX.D.DAX.IFMM.IP");
+        assert_eq!(chart.resolution, Resolution::Minute5);
+        assert_eq!(chart.close, Some(18005.5));
+    }
+
+    #[tokio::test]
+    async fn test_frame_mixing_acknowledgement_and_updates() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let mut market_rx = client.market_updates();
+        subscribe_market_fixture(&client, "MARKET-1", "IX.D.DAX.IFMM.IP");
+
+        let frame = "CONOK,S1,50000,5000,*\r\nSUBOK,MARKET-1,1,3\r\n\
+                     U,MARKET-1,1,18000.5|18001.5|12:00:00\r\nPROBE\r\n\
+                     U,MARKET-1,1,18002||12:00:01\r\n";
+        client.handle_message(Message::Text(frame.into())).await.unwrap();
+
+        assert_eq!(
+            client.subscription_status.lock().unwrap().get("MARKET-1"),
+            Some(&SubscriptionStatus::Subscribed)
+        );
+        assert_eq!(market_rx.recv().await.unwrap().bid, 18000.5);
+        assert_eq!(market_rx.recv().await.unwrap().bid, 18002.0);
+
+        // Updates before an error line are still delivered
+        let frame = "U,MARKET-1,1,18003||12:00:02\r\nERROR,65,Expired\r\n";
+        assert!(matches!(
+            client.handle_message(Message::Text(frame.into())).await,
+            Err(AppError::Lightstreamer(e)) if e.code == 65
+        ));
+        assert_eq!(market_rx.recv().await.unwrap().bid, 18003.0);
+    }
+
+    #[tokio::test]
+    async fn test_gzipped_market_update_dispatched_to_receiver() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let mut market_rx = client.market_updates();
+        subscribe_market_fixture(&client, "MARKET-1", "IX.D.DAX.IFMM.IP");
+
+        let gzip = |text: &str| {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(text.as_bytes()).unwrap();
+            Message::Binary(encoder.finish().unwrap().into())
+        };
+        client
+            .handle_message(gzip("U,MARKET-1,1,18000.5|18001.5|12:00:00\r\n"))
+            .await
+            .unwrap();
+        // Unchanged fields keep their previous value
+        client
+            .handle_message(gzip("U,MARKET-1,1,18002||12:00:01\r\n"))
+            .await
+            .unwrap();
+
+        let first = market_rx.recv().await.unwrap();
+        assert_eq!(first.epic, "IX.D.DAX.IFMM.IP");
+        assert_eq!((first.bid, first.offer), (18000.5, 18001.5));
+        assert_eq!(first.timestamp, "12:00:00");
+        let second = market_rx.recv().await.unwrap();
+        assert_eq!((second.bid, second.offer), (18002.0, 18001.5));
+        assert_eq!(second.timestamp, "12:00:01");
+    }
+
+    #[tokio::test]
+    async fn test_handler_receives_market_updates_and_state() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let recording = Arc::new(Recording::default());
+        client.set_handler(recording.clone());
+        subscribe_market_fixture(&client, "MARKET-1", "IX.D.DAX.IFMM.IP");
+
+        client
+            .handle_message(Message::Text("U,MARKET-1,1,18000.5|18001.5|12:00:00\r\n".into()))
+            .await
+            .unwrap();
+        *client.connected.lock().unwrap() = true;
+        client.disconnect().await.unwrap();
+
+        let markets = recording.markets.lock().unwrap();
+        assert_eq!(markets.len(), 1);
+        assert_eq!(markets[0].epic, "IX.D.DAX.IFMM.IP");
+        assert_eq!((markets[0].bid, markets[0].offer), (18000.5, 18001.5));
+        assert_eq!(*recording.states.lock().unwrap(), vec![ConnectionState::Disconnected]);
+    }
+
+    #[tokio::test]
+    async fn test_trade_update_dispatched_to_receiver() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let mut trade_rx = client.trade_updates().unwrap();
+        subscribe_fixture(&client, "TRADE-1", SubscriptionType::Trade, "ACC1");
+
+        let confirms = r#"{"dealReference":"REF1","dealId":"DIAAAAA","dealStatus":"REJECTED","status":"OPEN","date":"2025-05-13T12:00:00.000","reason":"INSUFFICIENT_FUNDS"}"#;
+        client
+            .handle_message(Message::Text(format!("U,TRADE-1,1,{}||\r\n", confirms).into()))
+            .await
+            .unwrap();
+
+        let trade = trade_rx.try_recv().unwrap();
+        assert_eq!(trade.account_id, "ACC1");
+        let confirmation = trade.confirmation.unwrap();
+        assert_eq!(confirmation.deal_status.as_deref(), Some("REJECTED"));
+        assert_eq!(confirmation.reason.as_deref(), Some("INSUFFICIENT_FUNDS"));
+    }
+
+    #[tokio::test]
+    async fn test_route_update_by_subscription_type() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let mut market_rx = client.market_updates();
+        let mut account_rx = client.account_updates();
+        let mut chart_rx = client.chart_updates().unwrap();
+        let mut trade_rx = client.trade_updates().unwrap();
+        for (id, subscription_type, item) in [
+            ("MARKET-1", SubscriptionType::Market, "IX.D.DAX.IFMM.IP"),
+            ("ACCOUNT-1", SubscriptionType::Account, "ACC1"),
+            ("CHART-1", SubscriptionType::Chart, "IX.D.DAX.IFMM.IP:1MINUTE"),
+            ("TRADE-1", SubscriptionType::Trade, "ACC1"),
+        ] {
+            subscribe_fixture(&client, id, subscription_type, item);
+        }
+
+        client.route_update("MARKET-1", &["18000.5", "18001.5", "12:00:00"]).await;
+        let market = market_rx.try_recv().unwrap();
+        assert_eq!((market.bid, market.offer), (18000.5, 18001.5));
+
+        client.route_update("ACCOUNT-1", &["25.5", "5000", "4000", "5025.5", "1000"]).await;
+        let AccountUpdate::Balance(balance) = account_rx.try_recv().unwrap();
+        assert_eq!(balance.account_id, "ACC1");
+        assert_eq!(balance.available_cash, Some(4000.0));
+
+        let candle = ["1715594400000", "12", "18000", "18010", "17990", "18005", "18001", "18011", "17991", "18006", "1"];
+        client.route_update("CHART-1", &candle).await;
+        let chart = chart_rx.try_recv().unwrap();
+        assert_eq!(chart.resolution, Resolution::Minute);
+        assert_eq!(chart.close, Some(18005.5));
+
+        let confirms = r#"{"dealReference":"REF1","dealId":"DIAAAAA","dealStatus":"ACCEPTED","status":"OPEN","date":"2025-05-13T12:00:00.000"}"#;
+        client.route_update("TRADE-1", &[confirms, "", ""]).await;
+        let trade = trade_rx.try_recv().unwrap();
+        assert_eq!(trade.confirmation.unwrap().deal_status.as_deref(), Some("ACCEPTED"));
+
+        // Unknown subscriptions reach no channel
+        client.route_update("OTHER", &["1", "2", "3"]).await;
+        assert!(market_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_market_prices_divided_by_scaling_factor() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()))
+            .with_instrument_catalog(gbpusd_catalog().await);
+        let mut market_rx = client.market_updates();
+        subscribe_market_fixture(&client, "MARKET-1", "CS.D.GBPUSD.TODAY.IP");
+        subscribe_market_fixture(&client, "MARKET-2", "IX.D.DAX.IFMM.IP");
+
+        client.route_update("MARKET-1", &["12345", "12350", "12:00:00"]).await;
+        let market = market_rx.try_recv().unwrap();
+        assert_eq!((market.bid, market.offer), (123.45, 123.5));
+
+        // Unchanged fields are filled in from the unscaled snapshot
+        client.route_update("MARKET-1", &["12340", "", "12:00:01"]).await;
+        let market = market_rx.try_recv().unwrap();
+        assert_eq!((market.bid, market.offer), (123.4, 123.5));
+
+        // Epics missing from the catalog stream raw values
+        client.route_update("MARKET-2", &["18000.5", "18001.5", "12:00:00"]).await;
+        let market = market_rx.try_recv().unwrap();
+        assert_eq!((market.bid, market.offer), (18000.5, 18001.5));
+    }
+
+    #[test]
+    fn test_updates_receiver_can_only_be_taken_once() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        assert!(client.chart_updates().is_ok());
+        assert!(matches!(client.chart_updates(), Err(AppError::WebSocketError(_))));
+        assert!(client.trade_updates().is_ok());
+        assert!(matches!(client.trade_updates(), Err(AppError::WebSocketError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_market_update_reaches_every_receiver() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let mut ui_rx = client.market_updates();
+        // Receivers taken from a clone share the same channel
+        let mut strategy_rx = client.clone().market_updates();
+
+        let update = MarketUpdate {
+            epic: "IX.D.DAX.IFMM.IP".to_string(),
+            bid: 18000.5,
+            offer: 18001.5,
+            timestamp: "2025-05-13T12:00:00".to_string(),
+        };
+        client.market_tx.send(update).unwrap();
+
+        for rx in [&mut ui_rx, &mut strategy_rx] {
+            let received = rx.recv().await.unwrap();
+            assert_eq!(received.epic, "IX.D.DAX.IFMM.IP");
+            assert_eq!(received.bid, 18000.5);
         }
     }
+
+    #[tokio::test]
+    async fn test_heartbeat_fires_after_interval() {
+        use crate::utils::clock::MockClock;
+        use chrono::TimeZone;
+
+        let clock = Arc::new(MockClock::new(
+            chrono::Utc.with_ymd_and_hms(2025, 5, 13, 12, 0, 0).unwrap(),
+        ));
+        let (tx, mut rx) = mpsc::channel(10);
+        tokio::spawn(heartbeat_loop(clock.clone(), tx, Duration::from_secs(30)));
+        tokio::task::yield_now().await;
+
+        clock.advance(Duration::from_secs(29));
+        tokio::task::yield_now().await;
+        assert!(rx.try_recv().is_err());
+
+        clock.advance(Duration::from_secs(1));
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg, Message::Text("\r\n\r\nLS_op=hb\r\n".into()));
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_marks_silent_connection_dead() {
+        use crate::utils::clock::MockClock;
+        use chrono::TimeZone;
+
+        let t0 = chrono::Utc.with_ymd_and_hms(2025, 5, 13, 12, 0, 0).unwrap();
+        let clock = Arc::new(MockClock::new(t0));
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new())).with_clock(clock.clone());
+        let (tx, _rx) = mpsc::channel(10);
+        *client.tx.lock().unwrap() = Some(tx);
+        *client.connected.lock().unwrap() = true;
+        let health = client.health();
+        health.touch(t0);
+        let watchdog = tokio::spawn(watchdog_loop(clock.clone(), health.clone(), Duration::from_secs(30), 3));
+        tokio::task::yield_now().await;
+
+        // A server probe after the first interval keeps the connection alive
+        clock.advance(Duration::from_secs(30));
+        health.touch(clock.now());
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(60));
+        tokio::task::yield_now().await;
+        assert!(client.is_connected());
+        assert_eq!(client.last_message_at(), Some(t0 + chrono::Duration::seconds(30)));
+
+        // Three silent intervals since that probe
+        clock.advance(Duration::from_secs(30));
+        assert!(watchdog.await.unwrap());
+        assert!(!client.is_connected());
+        assert!(client.tx.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_backoff_gives_up_after_max_attempts() {
+        use crate::utils::clock::MockClock;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let clock = Arc::new(MockClock::new(chrono::Utc::now()));
+        let backoff = Arc::new(Mutex::new(ReconnectBackoff::new(&Config::new().websocket)));
+        let attempts = Arc::new(AtomicU32::new(0));
+        let task = tokio::spawn({
+            let (clock, attempts) = (clock.clone(), attempts.clone());
+            async move {
+                connect_with_backoff(clock.as_ref(), &backoff, 3, || {
+                    attempts.fetch_add(1, Ordering::SeqCst);
+                    async { Err(AppError::WebSocketError("refused".to_string())) }
+                })
+                .await
+            }
+        });
+
+        while !task.is_finished() {
+            clock.advance(Duration::from_secs(3600));
+            tokio::task::yield_now().await;
+        }
+        let result = task.await.unwrap();
+        assert!(matches!(result, Err(AppError::WebSocketError(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe_market_by_epic() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let (tx, mut rx) = mpsc::channel(10);
+        *client.tx.lock().unwrap() = Some(tx);
+        *client.connected.lock().unwrap() = true;
+
+        let dax_1 = client.subscribe_market("IX.D.DAX.IFMM.IP").await.unwrap();
+        let dax_2 = client.subscribe_market("IX.D.DAX.IFMM.IP").await.unwrap();
+        let ftse = client.subscribe_market("IX.D.FTSE.IFMM.IP").await.unwrap();
+
+        client.unsubscribe_market("IX.D.DAX.IFMM.IP").await.unwrap();
+
+        let by_epic = client.market_subscriptions.lock().unwrap().clone();
+        assert_eq!(by_epic.len(), 1);
+        assert_eq!(by_epic["IX.D.FTSE.IFMM.IP"], vec![ftse.clone()]);
+        let remaining: Vec<_> = client.subscriptions.lock().unwrap().keys().cloned().collect();
+        assert_eq!(remaining, vec![ftse]);
+
+        // Three subscribes, then one delete per DAX subscription
+        let sent: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+        assert_eq!(sent.len(), 5);
+        for (msg, id) in sent[3..].iter().zip([dax_1, dax_2]) {
+            assert_eq!(
+                msg,
+                &Message::Text(format!("\r\n\r\nLS_op=delete\r\nLS_subId={}\r\n", id).into())
+            );
+        }
+
+        assert!(matches!(
+            client.unsubscribe_market("IX.D.DAX.IFMM.IP").await,
+            Err(AppError::WebSocketError(_))
+        ));
+        client.unsubscribe_all().await.unwrap();
+        assert!(client.subscriptions.lock().unwrap().is_empty());
+        assert!(client.market_subscriptions.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_subscription_status_tracks_acknowledgements() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let (tx, _rx) = mpsc::channel(10);
+        *client.tx.lock().unwrap() = Some(tx);
+        *client.connected.lock().unwrap() = true;
+
+        let dax = client.subscribe_market("IX.D.DAX.IFMM.IP").await.unwrap();
+        let ftse = client.subscribe_market("IX.D.FTSE.IFMM.IP").await.unwrap();
+        let status = |id: &str| {
+            client
+                .subscription_status()
+                .into_iter()
+                .find(|(subscription, _)| subscription.id == id)
+                .map(|(_, status)| status)
+        };
+        assert_eq!(status(&dax), Some(SubscriptionStatus::Pending));
+        assert!(client.active_subscriptions().is_empty());
+
+        let reply = format!("SUBOK,{},1,3\r\nREQERR,{},19,Item%20not%20found\r\n", dax, ftse);
+        client.handle_message(Message::Text(reply.into())).await.unwrap();
+
+        assert_eq!(status(&dax), Some(SubscriptionStatus::Subscribed));
+        assert_eq!(
+            status(&ftse),
+            Some(SubscriptionStatus::Failed {
+                code: 19,
+                message: "Item not found".to_string(),
+            })
+        );
+        let active: Vec<_> = client.active_subscriptions().into_iter().map(|s| s.id).collect();
+        assert_eq!(active, vec![dax.clone()]);
+
+        client.unsubscribe(&dax).await.unwrap();
+        assert_eq!(status(&dax), None);
+        assert!(client.subscription_status.lock().unwrap().get(&dax).is_none());
+    }
+
+    /// Lightstreamer stand-in that accepts `connections` connections one
+    /// after the other, answers session creation with `reply` and reports
+    /// every text frame it receives, then "CLOSE" for a close frame. Frames
+    /// sent on the returned sender are pushed to the current connection; a
+    /// pushed close frame also drops it.
+    // The handshake callback's error type is fixed by tungstenite
+    #[allow(clippy::result_large_err)]
+    async fn lightstreamer_server(
+        reply: &'static str,
+        connections: usize,
+    ) -> (String, mpsc::UnboundedReceiver<String>, mpsc::UnboundedSender<Message>) {
+        use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+        use tokio_tungstenite::tungstenite::http::HeaderValue;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (seen_tx, seen_rx) = mpsc::unbounded_channel();
+        let (push_tx, mut push_rx) = mpsc::unbounded_channel::<Message>();
+        tokio::spawn(async move {
+            for _ in 0..connections {
+                let (stream, _) = listener.accept().await.unwrap();
+                let echo_protocol = |_: &Request, mut response: Response| {
+                    response.headers_mut().insert(
+                        "Sec-WebSocket-Protocol",
+                        HeaderValue::from_static("js.lightstreamer.com"),
+                    );
+                    Ok(response)
+                };
+                let mut ws = tokio_tungstenite::accept_hdr_async(stream, echo_protocol).await.unwrap();
+                loop {
+                    tokio::select! {
+                        msg = ws.next() => match msg {
+                            Some(Ok(Message::Text(text))) => {
+                                if text.contains("LS_op2=create") {
+                                    ws.send(Message::Text(reply.into())).await.unwrap();
+                                }
+                                let _ = seen_tx.send(text.to_string());
+                            }
+                            Some(Ok(Message::Close(_))) => {
+                                let _ = seen_tx.send("CLOSE".to_string());
+                            }
+                            Some(Ok(_)) => {}
+                            _ => break,
+                        },
+                        Some(push) = push_rx.recv() => {
+                            let close = matches!(push, Message::Close(_));
+                            let _ = ws.send(push).await;
+                            if close {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        (url, seen_rx, push_tx)
+    }
+
+    /// Connects `client` to the test server at `url`
+    async fn connect_to(client: &IgWebSocketClientImpl, url: String) {
+        let mut session = test_session();
+        session.lightstreamer_endpoint = Some(url);
+        client.connect(&session).await.unwrap();
+    }
+
+    /// Waits for the next frame the server sees that contains `needle`
+    async fn seen_containing(seen: &mut mpsc::UnboundedReceiver<String>, needle: &str) -> String {
+        loop {
+            let frame = tokio::time::timeout(Duration::from_secs(5), seen.recv())
+                .await
+                .expect("timed out waiting for a frame")
+                .expect("server closed");
+            if frame.contains(needle) {
+                return frame;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chart_updates_arrive_over_socket() {
+        let (url, mut seen, push) = lightstreamer_server("CONOK,S1,50000,5000,*\r\n", 1).await;
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let mut charts = client.chart_updates().unwrap();
+        connect_to(&client, url).await;
+
+        let id = client.subscribe_chart("IX.D.DAX.IFMM.IP", Resolution::Minute).await.unwrap();
+        seen_containing(&mut seen, "LS_op=add").await;
+        push.send(Message::Text(format!("SUBOK,{id},1,11\r\n").into())).unwrap();
+        // Text that merely mentions an error is not a session error
+        push.send(Message::Text("U,OTHER,1,Error|ERROR\r\n".into())).unwrap();
+        push.send(Message::Text(
+            format!("U,{id},1,1747130400000|12|100|102|99|101|101|103|100|102|0\r\n").into(),
+        ))
+        .unwrap();
+
+        let chart = tokio::time::timeout(Duration::from_secs(5), charts.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(chart.epic, "IX.D.DAX.IFMM.IP");
+        assert_eq!(chart.resolution, Resolution::Minute);
+        assert_eq!(chart.close, Some(101.5));
+        assert_eq!(chart.timestamp, Some(1747130400000));
+        assert_eq!(
+            client.subscription_status.lock().unwrap().get(&id),
+            Some(&SubscriptionStatus::Subscribed)
+        );
+        assert!(client.is_connected());
+
+        // An ERROR control line ends the session
+        push.send(Message::Text("ERROR,65,Expired%20session\r\n".into())).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while client.is_connected() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_binary_frames_arrive_over_socket() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let (url, mut seen, push) = lightstreamer_server("CONOK,S1,50000,5000,*\r\n", 1).await;
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let mut market_rx = client.market_updates();
+        connect_to(&client, url).await;
+
+        let id = client.subscribe_market("IX.D.DAX.IFMM.IP").await.unwrap();
+        seen_containing(&mut seen, "LS_op=add").await;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        write!(encoder, "U,{id},1,18000.5|18001.5|12:00:00\r\n").unwrap();
+        push.send(Message::Binary(encoder.finish().unwrap().into())).unwrap();
+        push.send(Message::Binary(format!("U,{id},1,18002||12:00:01\r\n").into_bytes().into()))
+            .unwrap();
+
+        let timeout = Duration::from_secs(5);
+        let first = tokio::time::timeout(timeout, market_rx.recv()).await.unwrap().unwrap();
+        assert_eq!((first.bid, first.offer), (18000.5, 18001.5));
+        let second = tokio::time::timeout(timeout, market_rx.recv()).await.unwrap().unwrap();
+        assert_eq!((second.bid, second.offer), (18002.0, 18001.5));
+    }
+
+    #[tokio::test]
+    async fn test_balance_updates_arrive_over_socket() {
+        let (url, mut seen, push) = lightstreamer_server("CONOK,S1,50000,5000,*\r\n", 1).await;
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let mut account_rx = client.account_updates();
+        connect_to(&client, url).await;
+
+        let id = client.subscribe_account().await.unwrap();
+        assert!(seen_containing(&mut seen, "LS_op=add").await.contains("LS_group=ACCOUNT:ACC1\r\n"));
+        push.send(Message::Text(format!("U,{id},1,25.5|5000|4000|5025.5|1000\r\n").into()))
+            .unwrap();
+
+        let AccountUpdate::Balance(balance) =
+            tokio::time::timeout(Duration::from_secs(5), account_rx.recv()).await.unwrap().unwrap();
+        assert_eq!(balance.account_id, "ACC1");
+        assert_eq!(balance.pnl, Some(25.5));
+        assert_eq!(balance.funds, Some(5025.5));
+    }
+
+    #[tokio::test]
+    async fn test_handler_called_for_updates_over_socket() {
+        let (url, mut seen, push) = lightstreamer_server("CONOK,S1,50000,5000,*\r\n", 1).await;
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        connect_to(&client, url).await;
+        // Registered after connecting, so the running reader has to pick it up
+        let recording = Arc::new(Recording::default());
+        client.set_handler(recording.clone());
+
+        let market = client.subscribe_market("IX.D.DAX.IFMM.IP").await.unwrap();
+        let account = client.subscribe_account().await.unwrap();
+        seen_containing(&mut seen, "LS_group=ACCOUNT").await;
+        push.send(Message::Text(
+            format!("U,{market},1,18000.5|18001.5|12:00:00\r\nU,{account},1,25.5|5000|4000|5025.5|1000\r\n")
+                .into(),
+        ))
+        .unwrap();
+        push.send(Message::Close(None)).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while client.is_connected() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let markets = recording.markets.lock().unwrap();
+        assert_eq!(markets.len(), 1);
+        assert_eq!((markets[0].bid, markets[0].offer), (18000.5, 18001.5));
+        let accounts = recording.accounts.lock().unwrap();
+        let [AccountUpdate::Balance(balance)] = accounts.as_slice() else {
+            panic!("expected one balance update, got {:?}", accounts);
+        };
+        assert_eq!(balance.available_cash, Some(4000.0));
+        // A reconnection attempt may report further states after this one
+        assert_eq!(recording.states.lock().unwrap().first(), Some(&ConnectionState::Disconnected));
+    }
+
+    #[tokio::test]
+    async fn test_market_prices_scaled_over_socket() {
+        let (url, mut seen, push) = lightstreamer_server("CONOK,S1,50000,5000,*\r\n", 1).await;
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()))
+            .with_instrument_catalog(gbpusd_catalog().await);
+        let mut market_rx = client.market_updates();
+        connect_to(&client, url).await;
+
+        let id = client.subscribe_market("CS.D.GBPUSD.TODAY.IP").await.unwrap();
+        seen_containing(&mut seen, "LS_op=add").await;
+        push.send(Message::Text(format!("U,{id},1,12345|12350|12:00:00\r\n").into())).unwrap();
+
+        let market = tokio::time::timeout(Duration::from_secs(5), market_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((market.bid, market.offer), (123.45, 123.5));
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_stops_background_tasks() {
+        use crate::utils::clock::MockClock;
+        use chrono::TimeZone;
+
+        let (url, mut seen, _push) = lightstreamer_server("CONOK,S1,50000,5000,*\r\n", 1).await;
+        let clock = Arc::new(MockClock::new(
+            chrono::Utc.with_ymd_and_hms(2025, 5, 13, 12, 0, 0).unwrap(),
+        ));
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new())).with_clock(clock.clone());
+        let mut session = test_session();
+        session.lightstreamer_endpoint = Some(url);
+
+        client.connect(&session).await.unwrap();
+        assert!(seen.recv().await.unwrap().contains("LS_op2=create"));
+        let tasks: Vec<_> = client
+            .tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|t| t.abort_handle())
+            .collect();
+        assert_eq!(tasks.len(), 3);
+
+        // Let the heartbeat start its first sleep before moving the clock
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(seen.recv().await.unwrap(), "\r\n\r\nLS_op=hb\r\n");
+
+        client.disconnect().await.unwrap();
+        assert_eq!(seen.recv().await.unwrap(), "CLOSE");
+        assert!(tasks.iter().all(|t| t.is_finished()));
+        assert!(client.writer_task.lock().unwrap().is_none());
+
+        // No heartbeat goes out once disconnected
+        clock.advance(Duration::from_secs(60));
+        tokio::task::yield_now().await;
+        assert!(seen.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_connect_reports_conerr() {
+        let (url, _seen, _push) = lightstreamer_server("CONERR,1,User%2Fpassword check failed\r\n", 1).await;
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let mut session = test_session();
+        session.lightstreamer_endpoint = Some(url);
+
+        match client.connect(&session).await {
+            Err(AppError::Lightstreamer(e)) => {
+                assert_eq!(e.code, 1);
+                assert_eq!(e.message, "User/password check failed");
+                assert!(e.is_auth_failure());
+            }
+            other => panic!("expected a Lightstreamer error, got {:?}", other.err()),
+        }
+        assert!(!client.is_connected());
+    }
+
+    #[test]
+    fn test_try_send_reports_full_buffer() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        assert!(matches!(
+            client.try_send(Message::Text("hb".into())),
+            Err(AppError::WebSocketError(msg)) if msg == "WebSocket not connected"
+        ));
+
+        let (tx, mut rx) = mpsc::channel(2);
+        *client.tx.lock().unwrap() = Some(tx);
+        client.try_send(Message::Text("1".into())).unwrap();
+        client.try_send(Message::Text("2".into())).unwrap();
+        assert!(matches!(
+            client.try_send(Message::Text("3".into())),
+            Err(AppError::WebSocketError(msg)) if msg == "send buffer full"
+        ));
+
+        // Room frees up once the writer drains a message
+        rx.try_recv().unwrap();
+        client.try_send(Message::Text("3".into())).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_chart_rejects_unstreamable_resolution() {
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        let result = client.subscribe_chart("IX.D.DAX.IFMM.IP", Resolution::Day).await;
+        assert!(matches!(result, Err(AppError::WebSocketError(_))));
+    }
+
+    #[test]
+    fn test_defaults_without_session_endpoint() {
+        assert_eq!(
+            lightstreamer_endpoints(&test_session()),
+            DEFAULT_LIGHTSTREAMER_ENDPOINTS.to_vec()
+        );
+
+        // A session endpoint matching a default is not tried twice
+        let mut session = test_session();
+        session.lightstreamer_endpoint = Some("https://apd.marketdatasystems.com".to_string());
+        assert_eq!(lightstreamer_endpoints(&session), DEFAULT_LIGHTSTREAMER_ENDPOINTS.to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_and_resubscribes_after_server_close() {
+        let (url, mut seen, push) = lightstreamer_server("CONOK,S1,50000,5000,*\r\n", 2).await;
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        connect_to(&client, url).await;
+        let market = client.subscribe_market("IX.D.DAX.IFMM.IP").await.unwrap();
+        seen_containing(&mut seen, "LS_op=add").await;
+
+        push.send(Message::Close(None)).unwrap();
+
+        seen_containing(&mut seen, "LS_op2=create").await;
+        let resubscribe = seen_containing(&mut seen, "LS_op=add").await;
+        assert!(resubscribe.contains(&format!("LS_subId={market}")));
+        assert!(client.is_connected());
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_stops_reconnecting() {
+        let (url, mut seen, _push) = lightstreamer_server("CONOK,S1,50000,5000,*\r\n", 1).await;
+        let client = IgWebSocketClientImpl::new(Arc::new(Config::new()));
+        connect_to(&client, url).await;
+
+        client.disconnect().await.unwrap();
+        seen_containing(&mut seen, "CLOSE").await;
+        client.schedule_reconnect();
+
+        assert!(client.reconnect_task.lock().unwrap().is_none());
+        assert!(!client.is_connected());
+    }
 }
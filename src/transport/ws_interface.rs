@@ -1,8 +1,26 @@
 use async_trait::async_trait;
-use tokio::sync::mpsc::Receiver;
+use tokio::sync::{broadcast, mpsc::Receiver};
 use crate::error::AppError;
 use crate::session::interface::IgSession;
-use crate::transport::model::{AccountUpdate, MarketUpdate};
+use crate::application::models::market::{Epic, Resolution};
+use crate::transport::model::{
+    AccountUpdate, ChartUpdate, ConnectionState, MarketUpdate, Subscription, SubscriptionStatus,
+    TradeUpdate,
+};
+
+/// Callbacks for event-driven integrations that do not want to own a
+/// receiver loop. They run on the task that decoded the update, so they
+/// should return quickly. Every method defaults to doing nothing.
+pub trait WebSocketHandler: Send + Sync {
+    /// A decoded market price update
+    fn on_market(&self, _update: MarketUpdate) {}
+
+    /// A decoded account update
+    fn on_account(&self, _update: AccountUpdate) {}
+
+    /// The connection changed state
+    fn on_state(&self, _state: ConnectionState) {}
+}
 
 /// Trait defining the WebSocket client interface
 #[async_trait]
@@ -14,20 +32,59 @@ pub trait IgWebSocketClient: Send + Sync {
     async fn disconnect(&self) -> Result<(), AppError>;
 
     /// Subscribe to market updates
-    async fn subscribe_market(&self, epic: &str) -> Result<String, AppError>;
+    async fn subscribe_market(&self, epic: impl Into<Epic> + Send) -> Result<String, AppError>;
 
     /// Subscribe to account updates
     async fn subscribe_account(&self) -> Result<String, AppError>;
 
+    /// Subscribe to live candles for a market. Only the resolutions with a
+    /// [`Resolution::chart_scale`] can be streamed.
+    async fn subscribe_chart(
+        &self,
+        epic: impl Into<Epic> + Send,
+        resolution: Resolution,
+    ) -> Result<String, AppError>;
+
+    /// Subscribe to deal confirmations and position/working order updates
+    /// for the configured account
+    async fn subscribe_trades(&self) -> Result<String, AppError>;
+
     /// Unsubscribe from a subscription
     async fn unsubscribe(&self, subscription_id: &str) -> Result<(), AppError>;
 
+    /// Unsubscribe from every market subscription for `epic`
+    async fn unsubscribe_market(&self, epic: impl Into<Epic> + Send) -> Result<(), AppError>;
+
+    /// Unsubscribe from every active subscription
+    async fn unsubscribe_all(&self) -> Result<(), AppError>;
+
     /// Check if the client is connected
     fn is_connected(&self) -> bool;
 
-    /// Get a receiver for market updates
-    fn market_updates(&self) -> Receiver<MarketUpdate>;
+    /// Subscriptions the server has acknowledged with `SUBOK` or `SUBCMD`
+    fn active_subscriptions(&self) -> Vec<Subscription>;
+
+    /// Every registered subscription with the last state the server
+    /// reported for it; `Pending` until it is acknowledged or refused
+    fn subscription_status(&self) -> Vec<(Subscription, SubscriptionStatus)>;
+
+    /// Returns a new receiver for market updates; every receiver sees every
+    /// update sent after it was created. A receiver that falls more than
+    /// `websocket.channel_capacity` updates behind skips the oldest ones and
+    /// gets `RecvError::Lagged` from its next `recv`.
+    fn market_updates(&self) -> broadcast::Receiver<MarketUpdate>;
+
+    /// Returns a new receiver for account updates; every receiver sees every
+    /// update sent after it was created. A receiver that falls more than
+    /// `websocket.channel_capacity` updates behind skips the oldest ones and
+    /// gets `RecvError::Lagged` from its next `recv`.
+    fn account_updates(&self) -> broadcast::Receiver<AccountUpdate>;
+
+    /// Takes the receiver for chart updates. There is a single receiver, so
+    /// every call after the first returns `AppError::WebSocketError`.
+    fn chart_updates(&self) -> Result<Receiver<ChartUpdate>, AppError>;
 
-    /// Get a receiver for account updates
-    fn account_updates(&self) -> Receiver<AccountUpdate>;
-}
\ No newline at end of file
+    /// Takes the receiver for trade updates. There is a single receiver, so
+    /// every call after the first returns `AppError::WebSocketError`.
+    fn trade_updates(&self) -> Result<Receiver<TradeUpdate>, AppError>;
+}
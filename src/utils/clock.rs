@@ -0,0 +1,96 @@
+//! Time source abstraction, so that code which waits or compares against
+//! the current time can be driven deterministically in tests.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::watch;
+
+/// Source of the current time and of delays
+#[async_trait]
+pub trait Clock: Send + Sync {
+    /// Current time
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Completes once `duration` has elapsed on this clock
+    async fn sleep(&self, duration: Duration);
+}
+
+/// Wall clock backed by `Utc::now()` and `tokio::time::sleep`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+/// Clock that only moves when [`advance`](MockClock::advance) is called.
+/// Pending sleeps complete as soon as the clock reaches their deadline.
+#[derive(Debug)]
+pub struct MockClock {
+    now: watch::Sender<DateTime<Utc>>,
+}
+
+impl MockClock {
+    /// Creates a clock stopped at `start`
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: watch::Sender::new(start),
+        }
+    }
+
+    /// Moves the clock forward, waking every sleep whose deadline has passed
+    pub fn advance(&self, duration: Duration) {
+        let step = chrono::Duration::from_std(duration).expect("duration out of range");
+        self.now.send_modify(|now| *now += step);
+    }
+}
+
+#[async_trait]
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.borrow()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let deadline = self.now() + chrono::Duration::from_std(duration).expect("duration out of range");
+        let mut rx = self.now.subscribe();
+        // Like `tokio::time::sleep`, always yield so a loop of zero-length sleeps cannot starve others
+        tokio::task::yield_now().await;
+        // The sender lives as long as `self`, so `wait_for` cannot fail here
+        let _ = rx.wait_for(|now| *now >= deadline).await;
+    }
+}
+
+#[cfg(test)]
+mod tests_clock {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_mock_clock_wakes_sleepers_on_advance() {
+        let t0 = Utc.with_ymd_and_hms(2025, 5, 13, 12, 0, 0).unwrap();
+        let clock = Arc::new(MockClock::new(t0));
+
+        let sleeper = clock.clone();
+        let handle = tokio::spawn(async move { sleeper.sleep(Duration::from_secs(10)).await });
+        tokio::task::yield_now().await;
+
+        clock.advance(Duration::from_secs(9));
+        tokio::task::yield_now().await;
+        assert!(!handle.is_finished());
+
+        clock.advance(Duration::from_secs(1));
+        handle.await.unwrap();
+        assert_eq!(clock.now(), t0 + chrono::Duration::seconds(10));
+    }
+}
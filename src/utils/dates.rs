@@ -0,0 +1,54 @@
+//! Formatting and parsing of the `yyyy-MM-ddTHH:mm:ss` timestamps used by
+//! IG's history endpoints.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::error::AppError;
+
+/// Pattern IG expects for `from`/`to` query parameters
+pub const IG_DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Formats a UTC timestamp the way IG's history endpoints expect it
+pub fn format_ig_datetime(datetime: DateTime<Utc>) -> String {
+    datetime.format(IG_DATETIME_FORMAT).to_string()
+}
+
+/// Parses an IG timestamp such as `2025-05-13T10:00:00`, interpreted as UTC.
+/// Fractional seconds, as returned in some responses, are accepted.
+pub fn parse_ig_datetime(value: &str) -> Result<DateTime<Utc>, AppError> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+        .map(|naive| naive.and_utc())
+        .map_err(|e| {
+            AppError::SerializationError(format!(
+                "invalid IG datetime {:?}, expected yyyy-MM-ddTHH:mm:ss: {}",
+                value, e
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests_dates {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_and_parse_round_trip() {
+        let datetime = Utc.with_ymd_and_hms(2025, 5, 3, 7, 4, 9).unwrap();
+        assert_eq!(format_ig_datetime(datetime), "2025-05-03T07:04:09");
+        assert_eq!(parse_ig_datetime("2025-05-03T07:04:09").unwrap(), datetime);
+        assert_eq!(
+            parse_ig_datetime("2025-05-03T07:04:09.250").unwrap(),
+            datetime + chrono::Duration::milliseconds(250)
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_input() {
+        for value in ["", "2025-05-03", "2025/05/03T07:04:09", "03-05-2025T07:04:09", "2025-13-01T00:00:00"] {
+            assert!(
+                matches!(parse_ig_datetime(value), Err(AppError::SerializationError(_))),
+                "{value} should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Helpers for IG deal references, which must match `[A-Za-z0-9_-]{1,30}`.
+
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// Maximum length IG accepts for a deal reference
+pub const MAX_DEAL_REFERENCE_LEN: usize = 30;
+
+/// Length of the random suffix appended by [`generate_deal_reference`]
+const SUFFIX_LEN: usize = 12;
+
+fn is_valid_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Generates a unique, IG-compliant deal reference such as `MYBOT-3f2a9c0d41be`.
+///
+/// Characters IG rejects are stripped from `prefix`, which is then truncated
+/// so that the whole reference fits in 30 characters.
+pub fn generate_deal_reference(prefix: &str) -> String {
+    let suffix: String = Uuid::new_v4().simple().to_string()[..SUFFIX_LEN].to_string();
+    let prefix: String = prefix
+        .chars()
+        .filter(|c| is_valid_char(*c))
+        .take(MAX_DEAL_REFERENCE_LEN - SUFFIX_LEN - 1)
+        .collect();
+
+    if prefix.is_empty() {
+        suffix
+    } else {
+        format!("{}-{}", prefix, suffix)
+    }
+}
+
+/// Checks that `reference` matches `[A-Za-z0-9_-]{1,30}`
+pub fn validate_deal_reference(reference: &str) -> Result<(), AppError> {
+    if reference.is_empty() || reference.len() > MAX_DEAL_REFERENCE_LEN {
+        return Err(AppError::SerializationError(format!(
+            "deal reference must be 1 to {} characters long, got {}",
+            MAX_DEAL_REFERENCE_LEN,
+            reference.len()
+        )));
+    }
+    if let Some(c) = reference.chars().find(|c| !is_valid_char(*c)) {
+        return Err(AppError::SerializationError(format!(
+            "deal reference contains invalid character {:?}",
+            c
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests_dealref {
+    use super::*;
+
+    #[test]
+    fn test_generate_short_prefix() {
+        let reference = generate_deal_reference("bot");
+        assert!(reference.starts_with("bot-"));
+        assert_eq!(reference.len(), 4 + SUFFIX_LEN);
+        assert!(validate_deal_reference(&reference).is_ok());
+        assert_ne!(reference, generate_deal_reference("bot"));
+    }
+
+    #[test]
+    fn test_generate_truncates_long_prefix() {
+        let reference = generate_deal_reference("a_very_long_strategy_name_that_goes_on");
+        assert_eq!(reference.len(), MAX_DEAL_REFERENCE_LEN);
+        assert!(reference.starts_with("a_very_long_strat-"));
+        assert!(validate_deal_reference(&reference).is_ok());
+    }
+
+    #[test]
+    fn test_generate_strips_illegal_characters() {
+        let reference = generate_deal_reference("my bot/v2.0!");
+        assert!(reference.starts_with("mybotv20-"));
+        assert!(validate_deal_reference(&reference).is_ok());
+
+        let reference = generate_deal_reference("%%%");
+        assert_eq!(reference.len(), SUFFIX_LEN);
+        assert!(validate_deal_reference(&reference).is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_references() {
+        assert!(validate_deal_reference("ok_ref-1").is_ok());
+        assert!(matches!(
+            validate_deal_reference(""),
+            Err(AppError::SerializationError(_))
+        ));
+        assert!(matches!(
+            validate_deal_reference(&"x".repeat(31)),
+            Err(AppError::SerializationError(_))
+        ));
+        assert!(matches!(
+            validate_deal_reference("has space"),
+            Err(AppError::SerializationError(_))
+        ));
+    }
+}
@@ -2,20 +2,28 @@
 //
 // Financial calculation utilities for the IG client
 
+use std::collections::HashMap;
+
 use crate::application::models::account::Position;
+use crate::application::models::market::Currency;
 use crate::application::models::order::Direction;
+use crate::utils::money::Price;
 
 /// Calculate the Profit and Loss (P&L) for a position based on current market prices
 ///
+/// Prices are divided by the market's `scaling_factor` to get back to
+/// instrument units, and the result is multiplied by `size` and the
+/// position's `contract_size`. The P&L is in the position's `currency`.
+///
 /// # Arguments
 ///
 /// * `position` - The position to calculate P&L for
 ///
 /// # Returns
 ///
-/// * `Option<f64>` - The calculated P&L if market prices are available, None otherwise
+/// * `Option<Price>` - The calculated P&L if market prices are available, None otherwise
 ///
-pub fn calculate_pnl(position: &Position) -> Option<f64> {
+pub fn calculate_pnl(position: &Position) -> Option<Price> {
     let (bid, offer) = (position.market.bid, position.market.offer);
     
     // Get the appropriate price based on direction
@@ -35,7 +43,80 @@ pub fn calculate_pnl(position: &Position) -> Option<f64> {
     };
     
     // Return P&L
-    Some(price_diff * position.position.size)
+    Some(price_diff / scaling_factor(position) * position.position.size * position.position.contract_size)
+}
+
+/// Calculate the P&L of a position converted into `target_currency`
+///
+/// # Arguments
+///
+/// * `position` - The position to calculate P&L for
+/// * `target_currency` - Currency code to express the P&L in, e.g. the account currency
+/// * `rate_to_target` - Looks up the exchange rate from a currency code to `target_currency`
+///
+/// # Returns
+///
+/// * `Option<Price>` - The converted P&L, or None if no rate is available for the position's currency
+pub fn calculate_pnl_in_currency<F>(
+    position: &Position,
+    target_currency: &str,
+    rate_to_target: F,
+) -> Option<Price>
+where
+    F: Fn(&str) -> Option<Price>,
+{
+    let pnl = calculate_pnl(position)?;
+    if position.position.currency == target_currency {
+        return Some(pnl);
+    }
+    Some(pnl * rate_to_target(&position.position.currency)?)
+}
+
+/// Convert an amount from one currency to another
+///
+/// `rates` maps currency codes to the value of one unit of that currency in
+/// a common reference currency, e.g. the account currency, whose own entry
+/// is 1.0.
+///
+/// # Returns
+///
+/// * `Option<f64>` - The converted amount, or None if either currency has no rate
+pub fn convert_pnl(pnl: f64, from: &str, to: &str, rates: &HashMap<String, f64>) -> Option<f64> {
+    if from == to {
+        return Some(pnl);
+    }
+    let from_rate = rates.get(from)?;
+    let to_rate = rates.get(to).filter(|rate| **rate != 0.0)?;
+    Some(pnl * from_rate / to_rate)
+}
+
+/// Build a rate map for [`convert_pnl`] from an instrument's currencies,
+/// relative to the account currency
+///
+/// Each currency's `exchange_rate` is the value of one unit in the account
+/// currency; `base_exchange_rate`, the inverse quote, is used when it is
+/// missing. Currencies with neither are left out.
+pub fn currency_rates(currencies: &[Currency], account_currency: &str) -> HashMap<String, f64> {
+    let mut rates: HashMap<String, f64> = currencies
+        .iter()
+        .filter_map(|currency| {
+            let rate = currency
+                .exchange_rate
+                .filter(|rate| *rate > 0.0)
+                .or_else(|| currency.base_exchange_rate.filter(|rate| *rate > 0.0).map(|rate| 1.0 / rate))?;
+            Some((currency.code.clone(), rate))
+        })
+        .collect();
+    rates.insert(account_currency.to_string(), 1.0);
+    rates
+}
+
+/// IG quotes some markets scaled (e.g. in pence); non-positive factors are treated as 1
+fn scaling_factor(position: &Position) -> Price {
+    match u32::try_from(position.market.scaling_factor) {
+        Ok(factor) if factor > 0 => Price::from(factor),
+        _ => Price::from(1u8),
+    }
 }
 
 /// Calculate the percentage return for a position
@@ -46,15 +127,155 @@ pub fn calculate_pnl(position: &Position) -> Option<f64> {
 ///
 /// # Returns
 ///
-/// * `Option<f64>` - The calculated percentage return if market prices are available, None otherwise
-pub fn calculate_percentage_return(position: &Position) -> Option<f64> {
+/// * `Option<Price>` - The calculated percentage return if market prices are available, None otherwise
+pub fn calculate_percentage_return(position: &Position) -> Option<Price> {
     let pnl = calculate_pnl(position)?;
-    let initial_value = position.position.level * position.position.size;
+    let initial_value = position.position.level / scaling_factor(position)
+        * position.position.size
+        * position.position.contract_size;
     
     // Avoid division by zero
-    if initial_value == 0.0 {
+    if initial_value == Price::from(0u8) {
         return None;
     }
     
-    Some((pnl / initial_value) * 100.0)
+    Some((pnl / initial_value) * Price::from(100u8))
+}
+
+#[cfg(test)]
+mod tests_finance {
+    use super::*;
+    use crate::utils::money::price;
+    use crate::application::models::account::{PositionDetails, PositionMarket};
+    use crate::application::models::market::MarketStatus;
+
+    fn position(direction: Direction, level: f64, bid: f64, offer: f64) -> Position {
+        Position {
+            position: PositionDetails {
+                contract_size: price!(10.0),
+                created_date: "2025/05/13 10:00:00:000".to_string(),
+                created_date_utc: "2025-05-13T08:00:00".to_string(),
+                deal_id: "DIAAAA".to_string(),
+                deal_reference: "REF".to_string(),
+                direction,
+                limit_level: None,
+                level: price!(level),
+                size: price!(2.0),
+                stop_level: None,
+                trailing_step: None,
+                trailing_stop_distance: None,
+                currency: "USD".to_string(),
+                controlled_risk: false,
+                limited_risk_premium: None,
+            },
+            market: PositionMarket {
+                instrument_name: "US 500 5200 CALL".to_string(),
+                expiry: "JUN-25".to_string(),
+                epic: "OP.D.SPX.5200C.IP".to_string(),
+                instrument_type: "OPT_INDICES".to_string(),
+                lot_size: 1.0,
+                high: price!(0.0),
+                low: price!(0.0),
+                percentage_change: 0.0,
+                net_change: 0.0,
+                bid: price!(bid),
+                offer: price!(offer),
+                update_time: "10:00:00".to_string(),
+                update_time_utc: "08:00:00".to_string(),
+                delay_time: 0,
+                streaming_prices_available: true,
+                market_status: MarketStatus::Tradeable,
+                scaling_factor: 100,
+            },
+            pnl: None,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_calculate_pnl_uses_scaling_factor_and_contract_size() {
+        // 150 scaled points = 1.5 units, x size 2 x contract size 10
+        let buy = position(Direction::Buy, 5000.0, 5150.0, 5160.0);
+        assert_eq!(calculate_pnl(&buy), Some(price!(30.0)));
+
+        let sell = position(Direction::Sell, 5000.0, 5150.0, 5160.0);
+        assert_eq!(calculate_pnl(&sell), Some(price!(-32.0)));
+    }
+
+    #[test]
+    fn test_calculate_percentage_return() {
+        let buy = position(Direction::Buy, 5000.0, 5150.0, 5160.0);
+        // Initial value: 50 units x 2 x 10 = 1000
+        assert_eq!(calculate_percentage_return(&buy), Some(price!(3.0)));
+    }
+
+    #[test]
+    fn test_calculate_pnl_in_currency() {
+        let buy = position(Direction::Buy, 5000.0, 5150.0, 5160.0);
+        let rates = |currency: &str| (currency == "USD").then_some(price!(0.9));
+
+        assert_eq!(calculate_pnl_in_currency(&buy, "USD", rates), Some(price!(30.0)));
+        assert_eq!(calculate_pnl_in_currency(&buy, "EUR", rates), Some(price!(27.0)));
+        assert_eq!(calculate_pnl_in_currency(&buy, "GBP", |_| None), None);
+    }
+
+    fn rates() -> HashMap<String, f64> {
+        HashMap::from([
+            ("EUR".to_string(), 1.0),
+            ("GBP".to_string(), 1.2),
+            ("USD".to_string(), 0.9),
+        ])
+    }
+
+    #[test]
+    fn test_convert_pnl() {
+        assert_eq!(convert_pnl(100.0, "GBP", "EUR", &rates()), Some(120.0));
+        assert_eq!(convert_pnl(120.0, "EUR", "GBP", &rates()), Some(100.0));
+        assert_eq!(convert_pnl(5.0, "CHF", "CHF", &rates()), Some(5.0));
+        assert_eq!(convert_pnl(5.0, "CHF", "EUR", &rates()), None);
+    }
+
+    #[test]
+    fn test_positions_total_pnl_in() {
+        use crate::application::models::account::Positions;
+
+        let mut gbp = position(Direction::Buy, 5000.0, 5150.0, 5160.0);
+        gbp.position.currency = "GBP".to_string();
+        gbp.pnl = Some(price!(100.0));
+        let mut usd = position(Direction::Sell, 5000.0, 5150.0, 5160.0);
+        usd.pnl = Some(price!(-50.0));
+        let unpriced = position(Direction::Buy, 5000.0, 5150.0, 5160.0);
+        let positions = Positions {
+            positions: vec![gbp, usd, unpriced],
+        };
+
+        // 100 GBP x 1.2 - 50 USD x 0.9
+        let total = positions.total_pnl_in("EUR", &rates()).unwrap();
+        assert!((total - 75.0).abs() < 1e-9);
+        assert_eq!(positions.total_pnl_in("JPY", &rates()), None);
+    }
+
+    #[test]
+    fn test_currency_rates_from_instrument() {
+        let currency = |code: &str, base: Option<f64>, rate: Option<f64>| Currency {
+            code: code.to_string(),
+            symbol: None,
+            base_exchange_rate: base,
+            exchange_rate: rate,
+            is_default: None,
+        };
+        let rates = currency_rates(
+            &[
+                currency("USD", Some(1.25), Some(0.8)),
+                currency("GBP", Some(0.8), None),
+                currency("JPY", None, None),
+            ],
+            "EUR",
+        );
+
+        assert_eq!(rates.get("EUR"), Some(&1.0));
+        assert_eq!(rates.get("USD"), Some(&0.8));
+        assert_eq!(rates.get("GBP"), Some(&1.25));
+        assert_eq!(rates.get("JPY"), None);
+    }
 }
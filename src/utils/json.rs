@@ -0,0 +1,140 @@
+//! JSON decoding with errors that say where the document stopped matching
+//! the expected type and what the server actually sent.
+
+use serde::de::DeserializeOwned;
+
+use crate::error::AppError;
+
+/// Characters of the raw body kept in decode errors
+const MAX_BODY_SNIPPET: usize = 200;
+
+/// Decodes `body` into `R`. On failure the `AppError::SerializationError`
+/// names the path of the offending value (e.g. `markets[1].bid`) and
+/// includes the start of the raw body.
+///
+/// An empty body, which IG sends for some updates, is read as `null`, so it
+/// decodes into `()` or an `Option`.
+pub fn decode_json<R: DeserializeOwned>(body: &str) -> Result<R, AppError> {
+    if body.trim().is_empty() {
+        return serde_json::from_value(serde_json::Value::Null)
+            .map_err(|e| AppError::SerializationError(format!("{}; body was empty", e)));
+    }
+    serde_json::from_str(body).map_err(|e| {
+        let path = error_offset(body, &e).map(|offset| path_at(&body[..offset]));
+        AppError::SerializationError(format!(
+            "{} at `{}`; body: {}",
+            e,
+            path.as_deref().unwrap_or("?"),
+            snippet(body)
+        ))
+    })
+}
+
+/// Byte offset just past the position serde reported, if it lies inside `body`
+fn error_offset(body: &str, error: &serde_json::Error) -> Option<usize> {
+    let line_start: usize = body
+        .split_inclusive('\n')
+        .take(error.line().checked_sub(1)?)
+        .map(str::len)
+        .sum();
+    let offset = (line_start + error.column()).min(body.len());
+    (0..=offset).rev().find(|&i| body.is_char_boundary(i))
+}
+
+/// Path of the value being read at the end of `prefix`, a truncated JSON document
+fn path_at(prefix: &str) -> String {
+    enum Frame {
+        Object { key: Option<String>, expect_key: bool },
+        Array { index: usize },
+    }
+
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut chars = prefix.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let mut text = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => text.extend(chars.next()),
+                        '"' => break,
+                        c => text.push(c),
+                    }
+                }
+                if let Some(Frame::Object { key, expect_key }) = stack.last_mut()
+                    && *expect_key
+                {
+                    *key = Some(text);
+                    *expect_key = false;
+                }
+            }
+            '{' => stack.push(Frame::Object { key: None, expect_key: true }),
+            '[' => stack.push(Frame::Array { index: 0 }),
+            '}' | ']' => {
+                stack.pop();
+            }
+            ',' => match stack.last_mut() {
+                Some(Frame::Object { expect_key, .. }) => *expect_key = true,
+                Some(Frame::Array { index }) => *index += 1,
+                None => {}
+            },
+            _ => {}
+        }
+    }
+
+    let mut path = String::new();
+    for frame in &stack {
+        match frame {
+            Frame::Object { key: Some(key), .. } => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+            }
+            Frame::Object { key: None, .. } => {}
+            Frame::Array { index } => path.push_str(&format!("[{}]", index)),
+        }
+    }
+    if path.is_empty() {
+        path.push('.');
+    }
+    path
+}
+
+/// Start of `body`, cut at [`MAX_BODY_SNIPPET`] characters
+fn snippet(body: &str) -> String {
+    match body.char_indices().nth(MAX_BODY_SNIPPET) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests_json {
+    use super::*;
+
+    #[test]
+    fn test_path_at() {
+        assert_eq!(path_at(r#"{"markets": [{"bid": 1.0}, {"bid": "x""#), "markets[1].bid");
+        assert_eq!(path_at(r#"{"a": {"b\"c": [1, [2, 3"#), r#"a.b"c[1][1]"#);
+        assert_eq!(path_at(r#"{"a": 1}"#), ".");
+    }
+
+    #[test]
+    fn test_empty_body_is_null() {
+        decode_json::<()>("").unwrap();
+        assert_eq!(decode_json::<Option<u32>>(" \n").unwrap(), None);
+        assert!(matches!(
+            decode_json::<Vec<u32>>(""),
+            Err(AppError::SerializationError(msg)) if msg.ends_with("body was empty")
+        ));
+    }
+
+    #[test]
+    fn test_snippet_is_truncated() {
+        let body = "é".repeat(300);
+        let cut = snippet(&body);
+        assert_eq!(cut.chars().count(), MAX_BODY_SNIPPET + 3);
+        assert!(cut.ends_with("..."));
+    }
+}
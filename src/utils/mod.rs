@@ -2,4 +2,12 @@ mod error;
 
 pub mod logger;
 pub mod finance;
+#[cfg(feature = "storage")]
 pub mod transactions;
+pub mod dealref;
+pub mod clock;
+pub mod money;
+pub mod dates;
+pub mod prices;
+pub mod json;
+pub mod retry;
@@ -0,0 +1,164 @@
+//! Numeric type used for prices, levels and sizes in the order and position
+//! models and in the finance helpers.
+//!
+//! [`Price`] is `f64` by default. Building with the `decimal` feature makes it
+//! `Decimal`, so sums and differences of prices are exact instead of
+//! drifting by binary rounding error. Decimals are read from and written to
+//! JSON numbers digit for digit, as IG expects, without a round trip through
+//! `f64`.
+
+#[cfg(feature = "decimal")]
+use std::str::FromStr;
+
+#[cfg(feature = "decimal")]
+pub use rust_decimal::Decimal;
+use serde_json::Value;
+
+use crate::error::AppError;
+
+/// Price, level or size as stored in the order and position models
+#[cfg(not(feature = "decimal"))]
+pub type Price = f64;
+
+/// Price, level or size as stored in the order and position models
+#[cfg(feature = "decimal")]
+pub type Price = Decimal;
+
+/// Parses a price sent by IG as text, e.g. a Lightstreamer field
+pub fn parse_price(text: &str) -> Result<Price, AppError> {
+    let text = text.trim();
+    #[cfg(not(feature = "decimal"))]
+    let parsed = text.parse::<f64>().ok().filter(|value| value.is_finite());
+    #[cfg(feature = "decimal")]
+    let parsed = to_decimal_str(text);
+    parsed.ok_or_else(|| AppError::SerializationError(format!("invalid price: {:?}", text)))
+}
+
+/// Reads a price from IG's JSON, which carries either a number or a numeric string
+pub fn price_from_json(value: &Value) -> Result<Price, AppError> {
+    match value {
+        // The number's text keeps the digits exactly as IG sent them
+        Value::Number(number) => parse_price(&number.to_string()),
+        Value::String(text) => parse_price(text),
+        other => Err(AppError::SerializationError(format!(
+            "expected a price, got {}",
+            other
+        ))),
+    }
+}
+
+/// Converts an `f64` to the decimal it was written as, e.g. `0.1` to exactly
+/// `0.1` rather than the nearest binary value; `None` for NaN and infinities
+#[cfg(feature = "decimal")]
+pub fn to_decimal(value: f64) -> Option<Decimal> {
+    if !value.is_finite() {
+        return None;
+    }
+    to_decimal_str(&value.to_string())
+}
+
+/// Converts a decimal to the nearest `f64`
+#[cfg(feature = "decimal")]
+pub fn to_f64(value: Decimal) -> f64 {
+    value.to_string().parse().unwrap_or(f64::NAN)
+}
+
+/// Converts an `f64` to a [`Price`]; under the `decimal` feature, to the
+/// decimal it was written as. Fails for NaN and infinities.
+pub fn price_from_f64(value: f64) -> Result<Price, AppError> {
+    if !value.is_finite() {
+        return Err(AppError::SerializationError(format!("invalid price: {}", value)));
+    }
+    #[cfg(not(feature = "decimal"))]
+    return Ok(value);
+    #[cfg(feature = "decimal")]
+    return parse_price(&value.to_string());
+}
+
+/// [`Price`] literal for test fixtures, built the same way with or without
+/// the `decimal` feature
+#[cfg(test)]
+macro_rules! price {
+    ($value:expr) => {
+        $crate::utils::money::price_from_f64($value as f64).unwrap()
+    };
+}
+#[cfg(test)]
+pub(crate) use price;
+
+/// Converts a [`Price`] to `f64`, for calculations that work in floating point
+pub fn price_to_f64(price: Price) -> f64 {
+    #[cfg(not(feature = "decimal"))]
+    return price;
+    #[cfg(feature = "decimal")]
+    return to_f64(price);
+}
+
+#[cfg(feature = "decimal")]
+fn to_decimal_str(text: &str) -> Option<Decimal> {
+    Decimal::from_str(text)
+        .or_else(|_| Decimal::from_scientific(text))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests_money {
+    use super::*;
+    use serde_json::json;
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_f64_sum_drifts_but_decimal_sum_is_exact() {
+        let fills = ["0.1", "0.2", "0.3", "1.1", "2.2"];
+
+        let float_total: f64 = fills.iter().map(|f| f.parse::<f64>().unwrap()).sum();
+        assert_ne!(float_total, 3.9);
+        assert_ne!(0.1_f64 + 0.2, 0.3);
+
+        let decimal_total: Decimal = fills.iter().map(|f| Decimal::from_str(f).unwrap()).sum();
+        assert_eq!(decimal_total, Decimal::from_str("3.9").unwrap());
+        assert_eq!(decimal_total.to_string(), "3.9");
+    }
+
+    #[test]
+    fn test_price_from_json_accepts_numbers_and_strings() {
+        assert_eq!(price_from_json(&json!(5123.4)).unwrap(), parse_price("5123.4").unwrap());
+        assert_eq!(price_from_json(&json!("5123.4")).unwrap(), parse_price("5123.4").unwrap());
+        assert_eq!(price_from_json(&json!(7)).unwrap(), parse_price("7").unwrap());
+        assert!(price_from_json(&json!(null)).is_err());
+        assert!(parse_price("abc").is_err());
+        assert!(parse_price("").is_err());
+    }
+
+    #[test]
+    fn test_price_from_f64() {
+        assert_eq!(price!(5123.4), parse_price("5123.4").unwrap());
+        assert_eq!(price!(-50), parse_price("-50").unwrap());
+        assert!(price_from_f64(f64::INFINITY).is_err());
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal_conversions() {
+        assert_eq!(to_decimal(0.1), Some(Decimal::from_str("0.1").unwrap()));
+        assert_eq!(to_decimal(1e-7), Some(Decimal::from_str("0.0000001").unwrap()));
+        assert_eq!(to_decimal(f64::NAN), None);
+        assert_eq!(to_f64(Decimal::from_str("5123.45").unwrap()), 5123.45);
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal_serializes_as_json_number() {
+        let value = serde_json::to_value(Decimal::from_str("1.5").unwrap()).unwrap();
+        assert!(value.is_number());
+        assert_eq!(value.as_f64(), Some(1.5));
+        let parsed: Decimal = serde_json::from_value(json!("0.3")).unwrap();
+        assert_eq!(parsed, Decimal::from_str("0.3").unwrap());
+
+        // Digits beyond f64 precision survive both ways
+        let exact = Decimal::from_str("5123.4500000000000001").unwrap();
+        assert_eq!(serde_json::to_string(&exact).unwrap(), "5123.4500000000000001");
+        let parsed: Decimal = serde_json::from_str("5123.4500000000000001").unwrap();
+        assert_eq!(parsed, exact);
+    }
+}
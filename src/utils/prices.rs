@@ -0,0 +1,103 @@
+//! Aggregation of historical price bars into coarser resolutions, e.g. to
+//! build hourly candles from minute data when the hourly allowance is spent.
+
+use crate::application::models::market::{HistoricalPrice, PricePoint};
+
+/// Groups consecutive bars into buckets of `factor` bars and merges each
+/// bucket into one: open from the first bar, close from the last, the highest
+/// high, the lowest low and the summed volume. A trailing bucket with fewer
+/// than `factor` bars is still emitted. A `factor` of 0 is treated as 1.
+///
+/// The bucket takes the snapshot time of its first bar. Buckets are counted
+/// from the first bar, so align the input to the target resolution first.
+pub fn aggregate(prices: &[HistoricalPrice], factor: u32) -> Vec<HistoricalPrice> {
+    prices
+        .chunks(factor.max(1) as usize)
+        .map(merge_bucket)
+        .collect()
+}
+
+fn merge_bucket(bucket: &[HistoricalPrice]) -> HistoricalPrice {
+    let first = &bucket[0];
+    let last = &bucket[bucket.len() - 1];
+    let volumes: Vec<i64> = bucket.iter().filter_map(|p| p.last_traded_volume).collect();
+    HistoricalPrice {
+        snapshot_time: first.snapshot_time.clone(),
+        open_price: first.open_price.clone(),
+        high_price: combine(bucket.iter().map(|p| &p.high_price), f64::max),
+        low_price: combine(bucket.iter().map(|p| &p.low_price), f64::min),
+        close_price: last.close_price.clone(),
+        last_traded_volume: (!volumes.is_empty()).then(|| volumes.iter().sum()),
+    }
+}
+
+/// Folds each side of the price points with `pick`, skipping missing values
+fn combine<'a>(
+    points: impl Iterator<Item = &'a PricePoint> + Clone,
+    pick: fn(f64, f64) -> f64,
+) -> PricePoint {
+    let side = |get: fn(&PricePoint) -> Option<f64>| points.clone().filter_map(get).reduce(pick);
+    PricePoint {
+        bid: side(|p| p.bid),
+        ask: side(|p| p.ask),
+        last_traded: side(|p| p.last_traded),
+    }
+}
+
+#[cfg(test)]
+mod tests_prices {
+    use super::*;
+
+    fn point(mid: f64) -> PricePoint {
+        PricePoint {
+            bid: Some(mid - 0.5),
+            ask: Some(mid + 0.5),
+            last_traded: None,
+        }
+    }
+
+    fn bar(minute: u32, open: f64, high: f64, low: f64, close: f64, volume: i64) -> HistoricalPrice {
+        HistoricalPrice {
+            snapshot_time: format!("2025/05/13 10:{:02}:00", minute),
+            open_price: point(open),
+            high_price: point(high),
+            low_price: point(low),
+            close_price: point(close),
+            last_traded_volume: Some(volume),
+        }
+    }
+
+    #[test]
+    fn test_sixty_minutes_make_one_hour() {
+        let bars: Vec<_> = (0..60)
+            .map(|m| {
+                let open = 100.0 + m as f64;
+                bar(m, open, open + 2.0, open - 1.0, open + 1.0, 10)
+            })
+            .collect();
+
+        let hourly = aggregate(&bars, 60);
+
+        assert_eq!(hourly.len(), 1);
+        let hour = &hourly[0];
+        assert_eq!(hour.snapshot_time, "2025/05/13 10:00:00");
+        assert_eq!(hour.open_price.bid, Some(99.5));
+        assert_eq!(hour.close_price.ask, Some(160.5));
+        assert_eq!(hour.high_price.bid, Some(160.5));
+        assert_eq!(hour.high_price.ask, Some(161.5));
+        assert_eq!(hour.low_price.bid, Some(98.5));
+        assert_eq!(hour.low_price.last_traded, None);
+        assert_eq!(hour.last_traded_volume, Some(600));
+    }
+
+    #[test]
+    fn test_partial_tail_bucket() {
+        let bars: Vec<_> = (0..5).map(|m| bar(m, 100.0, 101.0, 99.0, 100.0, 1)).collect();
+
+        let buckets = aggregate(&bars, 2);
+
+        let volumes: Vec<_> = buckets.iter().map(|b| b.last_traded_volume).collect();
+        assert_eq!(volumes, vec![Some(2), Some(2), Some(1)]);
+        assert_eq!(buckets[2].snapshot_time, "2025/05/13 10:04:00");
+    }
+}
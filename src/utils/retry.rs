@@ -0,0 +1,174 @@
+//! Retrying fallible async operations with exponential backoff and jitter.
+
+use std::future::Future;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use tracing::warn;
+
+use crate::error::AppError;
+
+/// How often and how patiently [`retry_async`] retries an operation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled on every further attempt
+    pub base_delay: Duration,
+    /// Upper bound of a single delay
+    pub max_delay: Duration,
+    /// Fraction in `[0, 1]` by which each delay may be randomly shortened
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy of `max_attempts` attempts starting at `base_delay`, with the
+    /// default cap and jitter
+    pub fn new(max_attempts: u32, base_delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            base_delay,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the upper bound of a single delay
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the fraction by which each delay may be randomly shortened
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Delay before retry number `retry` (starting at 0), with `random` in
+    /// `[0, 1)` picking the jitter
+    fn delay(&self, retry: u32, random: f64) -> Duration {
+        let delay = 2u32
+            .checked_pow(retry)
+            .and_then(|factor| self.base_delay.checked_mul(factor))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        delay.mul_f64(1.0 - self.jitter.clamp(0.0, 1.0) * random)
+    }
+}
+
+/// Whether an error is worth retrying: network failures, 429 and 503.
+/// Everything else (401, 404, 400, decoding errors...) is terminal.
+pub fn is_retryable(error: &AppError) -> bool {
+    match error {
+        AppError::Network(_) | AppError::RateLimited { .. } => true,
+        AppError::Unexpected(status) => *status == StatusCode::SERVICE_UNAVAILABLE,
+        AppError::Context { source, .. } => is_retryable(source),
+        _ => false,
+    }
+}
+
+/// Runs `op` until it succeeds, fails with an error that is not
+/// [retryable](is_retryable), or `policy.max_attempts` attempts are used up,
+/// returning the last result.
+///
+/// After a 429 carrying a `retry_after`, that wait is used if it is longer
+/// than the backoff delay.
+pub async fn retry_async<F, Fut, T>(policy: RetryPolicy, mut op: F) -> Result<T, AppError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, AppError>>,
+{
+    let mut attempt = 1;
+    loop {
+        let error = match op().await {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+        if attempt >= policy.max_attempts || !is_retryable(&error) {
+            return Err(error);
+        }
+
+        let mut delay = policy.delay(attempt - 1, rand::random::<f64>());
+        if let AppError::RateLimited { retry_after: Some(wait) } = error {
+            delay = delay.max(wait);
+        }
+        warn!(
+            "Attempt {}/{} failed ({}), retrying in {:?}",
+            attempt, policy.max_attempts, error, delay
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests_retry {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new(3, Duration::from_millis(1)).with_jitter(0.0)
+    }
+
+    #[test]
+    fn test_delay_doubles_up_to_cap() {
+        let policy = RetryPolicy::new(10, Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(5))
+            .with_jitter(0.5);
+        let delays: Vec<_> = (0..5).map(|retry| policy.delay(retry, 0.0).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+        assert_eq!(policy.delay(1, 0.5), Duration::from_millis(1500));
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_success() {
+        let calls = AtomicU32::new(0);
+        let result = retry_async(policy(), || async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(AppError::Unexpected(StatusCode::SERVICE_UNAVAILABLE)),
+                1 => Err(AppError::RateLimited { retry_after: None }),
+                n => Ok(n),
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_terminal_error_fails_immediately() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), _> = retry_async(policy(), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(AppError::Unauthorized)
+        })
+        .await;
+
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), _> = retry_async(policy(), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(AppError::Unexpected(StatusCode::SERVICE_UNAVAILABLE).with_context("get_positions"))
+        })
+        .await;
+
+        assert!(matches!(result, Err(AppError::Context { .. })));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
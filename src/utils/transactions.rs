@@ -12,7 +12,7 @@ use crate::{
     error::AppError,
     session::auth::IgAuth,
     session::interface::IgAuthenticator,
-    storage::utils::store_transactions,
+    storage::utils::{latest_transaction_date, store_transactions},
 };
 
 const DAYS_TO_BACK_LOOK: i64 = 10;
@@ -74,10 +74,54 @@ pub async fn fetch_and_store_transactions(
     };
 
     debug!("Fetching transactions from {} to {}", from, to);
-    let txs = tx_client.fetch_range(&sess, from, to).await?;
+    let (txs, error) = match tx_client.fetch_range_partial(&sess, from, to).await {
+        Ok(txs) => (txs, None),
+        Err(partial) => (partial.transactions, Some(partial.error)),
+    };
     info!("Fetched {} transactions", txs.len());
 
-    // Store the transactions
+    // Store what was fetched, even if a later page failed
+    let inserted = store_transactions(pool, &txs).await?;
+    info!("Inserted {} rows", inserted);
+
+    match error {
+        Some(error) => Err(error),
+        None => Ok(inserted),
+    }
+}
+
+/// Fetch only the transactions newer than the latest one already stored
+///
+/// The newest stored `deal_date` is used as the cursor. With an empty table
+/// this falls back to the last 10 days, like [`fetch_and_store_transactions`].
+///
+/// # Arguments
+///
+/// * `cfg` - The configuration object
+/// * `pool` - PostgreSQL connection pool
+///
+/// # Returns
+///
+/// * `Result<usize, AppError>` - Number of transactions inserted, or an error
+pub async fn fetch_and_store_new_transactions(
+    cfg: &Config,
+    pool: &PgPool,
+) -> Result<usize, AppError> {
+    let last_seen = match latest_transaction_date(pool).await? {
+        Some(last_seen) => last_seen,
+        None => Utc::now() - Duration::days(DAYS_TO_BACK_LOOK),
+    };
+
+    // Authenticate with IG
+    let auth = IgAuth::new(cfg);
+    let sess = auth.login().await?;
+    info!("Successfully authenticated with IG");
+
+    let tx_client = IgTxClient::new(cfg);
+    debug!("Fetching transactions since {}", last_seen);
+    let (txs, cursor) = tx_client.fetch_since(&sess, last_seen).await?;
+    info!("Fetched {} transactions, newest dealt at {}", txs.len(), cursor);
+
     let inserted = store_transactions(pool, &txs).await?;
     info!("Inserted {} rows", inserted);
 
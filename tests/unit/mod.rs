@@ -3,3 +3,7 @@
    Email: jb@taunais.com
    Date: 4/9/24
 ******************************************************************************/
+
+#[cfg(feature = "storage")]
+mod storage;
+mod surface;
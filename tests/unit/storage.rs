@@ -0,0 +1,139 @@
+//! Database integration tests. They only run when `TEST_DATABASE_URL` points
+//! at a disposable Postgres instance; otherwise they return early.
+
+use ig_client::application::models::market::{MarketSnapshot, MarketStatus};
+use ig_client::storage::utils::{migrate_ig_options, store_market_snapshots};
+use ig_client::utils::money::price_from_f64;
+use sqlx::postgres::PgPoolOptions;
+
+async fn test_pool() -> Option<sqlx::PgPool> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .expect("failed to connect to TEST_DATABASE_URL");
+    Some(pool)
+}
+
+fn snapshot(update_time: &str, bid: f64) -> MarketSnapshot {
+    MarketSnapshot {
+        market_status: MarketStatus::Tradeable,
+        net_change: Some(12.5),
+        percentage_change: Some(0.07),
+        update_time: Some(update_time.to_string()),
+        delay_time: Some(0),
+        bid: Some(price_from_f64(bid).unwrap()),
+        offer: Some(price_from_f64(bid + 1.0).unwrap()),
+        high: None,
+        low: None,
+        binary_odds: None,
+        decimal_places_factor: Some(1),
+        scaling_factor: Some(1),
+        controlled_risk_extra_spread: None,
+        extra: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_store_market_snapshots_upserts() {
+    let Some(pool) = test_pool().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping");
+        return;
+    };
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS ig_market_snapshots (
+            epic TEXT NOT NULL,
+            bid DOUBLE PRECISION,
+            offer DOUBLE PRECISION,
+            update_time TEXT,
+            market_status TEXT NOT NULL,
+            net_change DOUBLE PRECISION,
+            percentage_change DOUBLE PRECISION,
+            UNIQUE (epic, update_time)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let epic = format!("TEST.{}", uuid::Uuid::new_v4());
+    let inserted = store_market_snapshots(
+        &pool,
+        &epic,
+        &[snapshot("10:00:00", 18000.0), snapshot("10:00:01", 18001.0)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(inserted, 2);
+
+    // Same key again updates the existing row instead of adding a new one
+    store_market_snapshots(&pool, &epic, &[snapshot("10:00:01", 18005.0)])
+        .await
+        .unwrap();
+
+    let rows: Vec<(String, f64)> = sqlx::query_as(
+        "SELECT update_time, bid FROM ig_market_snapshots WHERE epic = $1 ORDER BY update_time",
+    )
+    .bind(&epic)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        rows,
+        vec![("10:00:00".to_string(), 18000.0), ("10:00:01".to_string(), 18005.0)]
+    );
+
+    sqlx::query("DELETE FROM ig_market_snapshots WHERE epic = $1")
+        .bind(&epic)
+        .execute(&pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_migrate_ig_options_renames_pnl_eur() {
+    let Some(pool) = test_pool().await else {
+        eprintln!("TEST_DATABASE_URL not set, skipping");
+        return;
+    };
+
+    // The layout before P&L carried its currency
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS ig_options (
+            reference TEXT NOT NULL,
+            deal_date TIMESTAMPTZ NOT NULL,
+            underlying TEXT,
+            strike DOUBLE PRECISION,
+            option_type TEXT,
+            expiry DATE,
+            transaction_type TEXT NOT NULL,
+            pnl_eur DOUBLE PRECISION NOT NULL,
+            is_fee BOOLEAN NOT NULL,
+            raw TEXT NOT NULL,
+            raw_hash TEXT NOT NULL UNIQUE
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    migrate_ig_options(&pool).await.unwrap();
+    // A second run finds nothing to do
+    migrate_ig_options(&pool).await.unwrap();
+
+    let columns: Vec<String> = sqlx::query_scalar(
+        "SELECT column_name::TEXT FROM information_schema.columns \
+         WHERE table_name = 'ig_options' AND column_name IN ('pnl', 'pnl_eur', 'currency') \
+         ORDER BY column_name",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(columns, vec!["currency".to_string(), "pnl".to_string()]);
+}
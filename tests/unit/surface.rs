@@ -0,0 +1,31 @@
+//! The REST and streaming API has to build and work without the `storage`
+//! feature; run with `--no-default-features` to check.
+
+use std::sync::Arc;
+
+use ig_client::application::services::account_service::AccountServiceImpl;
+use ig_client::application::services::market_service::MarketServiceImpl;
+use ig_client::application::services::order_service::OrderServiceImpl;
+use ig_client::config::Config;
+use ig_client::error::AppError;
+use ig_client::transport::http_client::IgHttpClientImpl;
+use ig_client::transport::websocket_client::IgWebSocketClientImpl;
+use ig_client::transport::ws_interface::IgWebSocketClient;
+
+#[test]
+fn test_rest_and_streaming_clients_build() {
+    let config = Arc::new(Config::new());
+    let http = Arc::new(IgHttpClientImpl::new(config.clone()));
+
+    let _accounts = AccountServiceImpl::new(config.clone(), http.clone());
+    let _markets = MarketServiceImpl::new(config.clone(), http.clone());
+    let _orders = OrderServiceImpl::new(config.clone(), http);
+    let ws = IgWebSocketClientImpl::new(config.clone());
+
+    assert!(!ws.is_connected());
+    assert_eq!(
+        config.rest_api.endpoint("session"),
+        format!("{}/session", config.rest_api.base_url.trim_end_matches('/'))
+    );
+    assert_eq!(AppError::NotFound.to_string(), "not found");
+}